@@ -0,0 +1,5824 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fs;
+use std::rc::Rc;
+
+use lang_c::span::Span;
+use lang_c::visit::Visit;
+use lang_c::visit::{
+    visit_array_declarator, visit_binary_operator_expression, visit_call_expression,
+    visit_cast_expression, visit_declaration, visit_do_while_statement, visit_enumerator,
+    visit_expression, visit_external_declaration, visit_for_statement, visit_function_definition,
+    visit_initializer, visit_parameter_declaration, visit_statement, visit_struct_field,
+    visit_translation_unit, visit_unary_operator_expression, visit_while_statement,
+};
+
+use crate::cfg::{switch_labels, ControlFlowGraph};
+use crate::config::{glob_matches, RuleSet};
+use crate::constness::unwritten_pointer_parameters;
+use crate::dataflow::uninitialized_reads;
+use crate::diagnostic::Diagnostic;
+use crate::format::{conversions, literal_text, ArgumentKind, FormatFamily};
+use crate::location::LineIndex;
+
+// Functions whose behaviour depends on the current locale
+const LOCALE_FUNCTIONS: [&str; 6] = [
+    "setlocale",
+    "localeconv",
+    "strcoll",
+    "strxfrm",
+    "wcscoll",
+    "wcsxfrm",
+];
+
+// Wide and multibyte character APIs from wchar.h and stdlib.h
+const WIDE_CHARACTER_FUNCTIONS: [&str; 24] = [
+    "wcscpy", "wcsncpy", "wcscat", "wcsncat", "wcscmp", "wcsncmp", "wcslen", "wcschr", "wcsrchr",
+    "wcsstr", "wprintf", "fwprintf", "swprintf", "wscanf", "fgetwc", "fputwc", "getwc", "putwc",
+    "mbstowcs", "wcstombs", "mbtowc", "wctomb", "mbrtowc", "wcrtomb",
+];
+
+// ctype.h functions whose behaviour is undefined for negative plain char values
+const CTYPE_FUNCTIONS: [&str; 13] = [
+    "isalpha", "isdigit", "isalnum", "isspace", "isupper", "islower", "isprint", "ispunct",
+    "iscntrl", "isgraph", "isxdigit", "toupper", "tolower",
+];
+
+// Functions assert calls when its condition is false, in the C libraries of glibc,
+// BSD and macOS, MSVC, bionic and newlib
+const ASSERTION_FAILURE_FUNCTIONS: [&str; 6] = [
+    "__assert_fail",
+    "__assert",
+    "__assert_rtn",
+    "_assert",
+    "__assert2",
+    "__assert_func",
+];
+
+// Typedef names of integer types from stdint.h and stddef.h with their size in bytes,
+// as on 64-bit targets like POINTER_BYTES
+const INTEGER_TYPEDEFS: [(&str, u64); 16] = [
+    ("int8_t", 1),
+    ("uint8_t", 1),
+    ("int16_t", 2),
+    ("uint16_t", 2),
+    ("int32_t", 4),
+    ("uint32_t", 4),
+    ("int64_t", 8),
+    ("uint64_t", 8),
+    ("intptr_t", 8),
+    ("uintptr_t", 8),
+    ("intmax_t", 8),
+    ("uintmax_t", 8),
+    ("size_t", 8),
+    ("ssize_t", 8),
+    ("ptrdiff_t", 8),
+    ("wchar_t", 4),
+];
+
+// Size of a pointer and of long in the stack frame estimate, as on 64-bit targets,
+// which overestimates rather than underestimates 32-bit frames
+const POINTER_BYTES: u64 = 8;
+
+// Bounded buffer functions with the argument positions of the buffers the size applies
+// to, destination first, and of the size itself
+const BOUNDED_BUFFER_FUNCTIONS: [(&str, &[usize], usize); 10] = [
+    ("strncpy", &[0], 2),
+    ("strncat", &[0], 2),
+    ("strlcpy", &[0], 2),
+    ("strlcat", &[0], 2),
+    ("memcpy", &[0, 1], 2),
+    ("memmove", &[0, 1], 2),
+    ("memset", &[0], 2),
+    ("snprintf", &[0], 1),
+    ("vsnprintf", &[0], 1),
+    ("fgets", &[0], 1),
+];
+
+#[derive(Debug)]
+enum SymbolType {
+    Function {
+        return_type: lang_c::ast::TypeSpecifier,
+    },
+    Variable {
+        type_specifier: lang_c::ast::TypeSpecifier,
+        type_specifiers: Vec<lang_c::ast::TypeSpecifier>, // All of them, e.g. `unsigned` and `char`
+    },
+    Array {
+        element_type: lang_c::ast::TypeSpecifier,
+        type_specifiers: Vec<lang_c::ast::TypeSpecifier>, // All of them, of the element type
+        length: Option<u64>, // None when the length is not a constant expression
+    },
+    Pointer {
+        type_specifiers: Vec<lang_c::ast::TypeSpecifier>, // All of them, of the pointee type
+    },
+}
+
+#[derive(Debug)]
+struct Symbol {
+    _name: String,
+    symbol_type: SymbolType,
+}
+
+// Structured `@recursion max_depth=N measure=param` comment documenting a bounded recursion
+#[derive(Debug)]
+struct RecursionAnnotation {
+    _max_depth: usize,
+    measure: String,
+}
+
+// Way a loop counter has to move to reach its bound
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Direction {
+    Up,
+    Down,
+}
+
+impl Direction {
+    fn reversed(self) -> Self {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+        }
+    }
+}
+
+// Arithmetic type of a value, as far as a format argument check needs to tell. It is
+// classified from all the type specifiers: any of float or double makes it Floating,
+// char in any signedness and 1 byte typedefs such as uint8_t are Character, and every
+// other integer type, _Bool and enums are Integer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ScalarType {
+    Character,
+    Integer,
+    Floating,
+}
+
+// Type of an argument, None as the pointee of a pointer to anything else
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ValueType {
+    Scalar(ScalarType),
+    Pointer(Option<ScalarType>),
+}
+
+impl ValueType {
+    fn description(self) -> &'static str {
+        match self {
+            ValueType::Scalar(ScalarType::Character | ScalarType::Integer) => "an integer",
+            ValueType::Scalar(ScalarType::Floating) => "a floating-point value",
+            ValueType::Pointer(Some(ScalarType::Character)) => "a pointer to char",
+            ValueType::Pointer(Some(ScalarType::Integer)) => "a pointer to an integer",
+            ValueType::Pointer(Some(ScalarType::Floating)) => "a pointer to a floating-point value",
+            ValueType::Pointer(None) => "a pointer",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct StaticAnalyzer {
+    rule_set: RuleSet,                     // Configuration for the static analyzer
+    symbol_table: HashMap<String, Symbol>, // Symbol table to store the types of variables
+    current_function_type_cast: Option<lang_c::ast::TypeSpecifier>, // Type of the current function being analyzed, is None if not cast
+    source: String,                   // Source code of the program being analyzed
+    line_index: LineIndex,            // Locations of offsets in the source
+    current_function: Option<String>, // Name of the current function being analyzed for recursion
+    current_function_parameters: Vec<String>, // Parameter names of the current function, empty if unnamed
+    current_recursion_annotation: Option<RecursionAnnotation>, // Bound documented above the current function
+    variadic_functions: HashSet<String>, // Functions declared with an ellipsis
+    in_allowed_variadic_function: bool, // Visiting an allowed variadic function, which may use va_start and va_arg
+    volatile_objects: HashSet<String>,  // Variables declared volatile, e.g. memory-mapped registers
+    function_pointer_types: HashSet<String>, // Typedef names of function pointer types
+    function_pointers: HashSet<String>, // Variables and parameters declared as function pointers
+    in_dereference_chain: bool, // Visiting the operand of a * or ->, whose chain is already checked
+    conditional_depth: usize,   // Conditional expressions enclosing the expression being visited
+    for_clause_commas: HashSet<usize>, // Starts of comma expressions making up a for statement's initialization or increment
+    compile_time_constants: HashSet<String>, // Enum constants and const variables with a constant value
+    call_graph: BTreeMap<String, Vec<(String, Span)>>, // Functions each function calls, at their first call site
+    type_sizes: HashMap<String, u64>, // Sizes of typedef names and of struct and union tags, e.g. "struct packet"
+    file_scope_variables: HashMap<String, Span>, // File-scope variables declared so far, at their first declaration
+    enum_types: HashMap<String, Vec<String>>, // Enumerators of enum tags and of typedef names of enums
+    original_sources: RefCell<HashMap<String, Option<Rc<str>>>>, // Files before preprocessing by name, None if unreadable
+    enumeration_constants: HashSet<String>,                      // Enumerators declared so far
+    parameter_types: HashMap<String, Vec<Vec<lang_c::ast::TypeSpecifier>>>, // Type specifiers of the parameters of each function, empty for pointers and arrays
+    union_typedefs: HashMap<String, bool>, // Typedef names of union types, with whether the union is allowed
+    const_pointers: HashSet<String>,       // Variables and parameters declared as pointers to const
+    pub(crate) diagnostics: Vec<Diagnostic>, // Violations found so far
+    reported: HashSet<(String, usize, usize)>, // Rule and span of every violation found so far
+}
+
+impl StaticAnalyzer {
+    pub(crate) fn new(rule_set: RuleSet, source: String) -> Self {
+        StaticAnalyzer {
+            rule_set,
+            symbol_table: HashMap::new(),
+            current_function_type_cast: None,
+            line_index: LineIndex::new(&source),
+            source,
+            current_function: None,
+            current_function_parameters: Vec::new(),
+            current_recursion_annotation: None,
+            variadic_functions: HashSet::new(),
+            in_allowed_variadic_function: false,
+            volatile_objects: HashSet::new(),
+            function_pointer_types: HashSet::new(),
+            function_pointers: HashSet::new(),
+            in_dereference_chain: false,
+            conditional_depth: 0,
+            for_clause_commas: HashSet::new(),
+            compile_time_constants: HashSet::new(),
+            call_graph: BTreeMap::new(),
+            type_sizes: HashMap::new(),
+            file_scope_variables: HashMap::new(),
+            enum_types: HashMap::new(),
+            original_sources: RefCell::new(HashMap::new()),
+            enumeration_constants: HashSet::new(),
+            parameter_types: HashMap::new(),
+            union_typedefs: HashMap::new(),
+            const_pointers: HashSet::new(),
+            diagnostics: Vec::new(),
+            reported: HashSet::new(),
+        }
+    }
+
+    // Helper function to get the line number for a given offset in the source code
+    fn get_line_number(&self, span_point: usize) -> usize {
+        self.line_index.line(span_point)
+    }
+
+    // Number of lines spanned by a function, honouring the blank and comment line settings
+    fn get_function_size(&self, span: &Span) -> usize {
+        let location = self.line_index.location(span.start);
+        let start_line = location.line;
+        let end_line = self.get_line_number(span.end);
+        let size = end_line - start_line + 1;
+
+        if self.rule_set.count_blank_lines && self.rule_set.count_comment_lines {
+            return size;
+        }
+
+        // Comments are stripped by the preprocessor, so classify lines from the original file
+        let Some(original_source) = self.original_source(location.file) else {
+            return size;
+        };
+        let lines: Vec<&str> = original_source
+            .lines()
+            .skip(start_line - 1)
+            .take(size)
+            .collect();
+        count_source_lines(
+            &lines,
+            self.rule_set.count_blank_lines,
+            self.rule_set.count_comment_lines,
+        )
+    }
+
+    // Walk the statements of a function body in order, treating the parameters an if
+    // condition or assertion mentions as checked, and report pointer parameters read
+    // before that. assert and most assertion macros expand to a conditional expression
+    // or a short-circuit operator, which count as assertions as well.
+    fn check_parameter_validation(
+        &mut self,
+        function_definition: &lang_c::ast::FunctionDefinition,
+    ) {
+        let mut unchecked = pointer_parameters(&function_definition.declarator.node);
+        let lang_c::ast::Statement::Compound(items) = &function_definition.statement.node else {
+            return;
+        };
+
+        for item in items {
+            if unchecked.is_empty() {
+                return;
+            }
+            let mut reads = ReadCollector::default();
+            match &item.node {
+                lang_c::ast::BlockItem::Statement(statement) => {
+                    if let Some(condition) = self.checked_condition(&statement.node) {
+                        let mut checked = ReadCollector::default();
+                        checked.visit_expression(&condition.node, &condition.span);
+                        unchecked.retain(|parameter| {
+                            !checked.reads.iter().any(|(name, _)| name == parameter)
+                        });
+                    }
+                    reads.visit_statement(&statement.node, &statement.span);
+                }
+                lang_c::ast::BlockItem::Declaration(declaration) => {
+                    reads.visit_declaration(&declaration.node, &declaration.span);
+                }
+                lang_c::ast::BlockItem::StaticAssert(_) => continue,
+            }
+
+            for (name, span) in reads.reads {
+                let Some(index) = unchecked.iter().position(|parameter| *parameter == name) else {
+                    continue;
+                };
+                unchecked.remove(index);
+                self.report(
+                    "require_parameter_validation",
+                    &span,
+                    &format!("Parameter '{}' is used before it is checked", name),
+                    None,
+                );
+            }
+        }
+    }
+
+    // Condition a statement checks: that of an if, or the operand an assertion tests
+    fn checked_condition<'a>(
+        &self,
+        statement: &'a lang_c::ast::Statement,
+    ) -> Option<&'a lang_c::span::Node<lang_c::ast::Expression>> {
+        let expression = match statement {
+            lang_c::ast::Statement::If(if_statement) => return Some(&*if_statement.node.condition),
+            lang_c::ast::Statement::Expression(Some(expression)) => expression,
+            _ => return None,
+        };
+        let mut expression = &**expression;
+        // `(void)(condition || fail())` style assertions
+        while let lang_c::ast::Expression::Cast(cast) = &expression.node {
+            expression = &*cast.node.expression;
+        }
+
+        match &expression.node {
+            lang_c::ast::Expression::Conditional(conditional) => Some(&*conditional.node.condition),
+            lang_c::ast::Expression::BinaryOperator(binary)
+                if matches!(
+                    binary.node.operator.node,
+                    lang_c::ast::BinaryOperator::LogicalAnd
+                        | lang_c::ast::BinaryOperator::LogicalOr
+                ) =>
+            {
+                Some(&*binary.node.lhs)
+            }
+            // Assertion functions rather than macros
+            lang_c::ast::Expression::Call(call) => match &call.node.callee.node {
+                lang_c::ast::Expression::Identifier(identifier)
+                    if identifier.node.name == "assert"
+                        || self
+                            .rule_set
+                            .assertion_macros
+                            .contains(&identifier.node.name) =>
+                {
+                    call.node.arguments.first()
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    // Check the condition of `condition ? (void)0 : __assert_fail(...)`, which assert
+    // expands to, and the argument of calls to assertion functions
+    fn check_assertion_expression(&mut self, expression: &lang_c::ast::Expression) {
+        match expression {
+            lang_c::ast::Expression::Conditional(conditional)
+                if is_assertion_failure(&conditional.node.else_expression.node) =>
+            {
+                self.check_assertion_side_effects(&conditional.node.condition);
+            }
+            lang_c::ast::Expression::Call(call) => {
+                let lang_c::ast::Expression::Identifier(identifier) = &call.node.callee.node else {
+                    return;
+                };
+                let name = &identifier.node.name;
+                if name == "assert" || self.rule_set.assertion_macros.contains(name) {
+                    for argument in &call.node.arguments {
+                        self.check_assertion_side_effects(argument);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Report assignments, increments, decrements and calls to functions not known to be
+    // pure in an asserted condition, which are lost when assertions are compiled out
+    fn check_assertion_side_effects(
+        &mut self,
+        condition: &lang_c::span::Node<lang_c::ast::Expression>,
+    ) {
+        let mut finder = SideEffectFinder {
+            pure_functions: &self.rule_set.pure_functions,
+            side_effects: Vec::new(),
+        };
+        finder.visit_expression(&condition.node, &condition.span);
+        for (span, side_effect) in finder.side_effects {
+            self.report(
+                "restrict_assertion_side_effects",
+                &span,
+                &format!(
+                    "Assertion has a side effect ({}), which is lost when assertions are compiled out",
+                    side_effect
+                ),
+                None,
+            );
+        }
+    }
+
+    fn check_dereference_depth(&mut self, expression: &lang_c::ast::Expression, span: &Span) {
+        let mut depth = 0;
+        let mut current = Some(expression);
+        while let Some(operand) = current.and_then(dereferenced_operand) {
+            depth += 1;
+            current = Some(operand);
+        }
+
+        let max_dereference_depth = self.rule_set.max_dereference_depth;
+        if depth > max_dereference_depth {
+            let snippet = self.get_source_code_from_span(span);
+            self.report(
+                "restrict_dereference_depth",
+                span,
+                &format!(
+                    "Expression dereferences {} pointer levels, at most {} allowed",
+                    depth, max_dereference_depth
+                ),
+                Some(snippet),
+            );
+        }
+    }
+
+    // Count the assertions of a function in the original file, as assert and project
+    // assertion macros no longer exist once the source is preprocessed
+    fn check_assertion_density(
+        &mut self,
+        function_definition: &lang_c::ast::FunctionDefinition,
+        span: &Span,
+    ) {
+        let location = self.line_index.location(span.start);
+        let start_line = location.line;
+        let size = self.get_line_number(span.end) + 1 - start_line;
+        if size < self.rule_set.min_assertion_function_lines {
+            return;
+        }
+        let Some(original_source) = self.original_source(location.file) else {
+            return;
+        };
+        let body: Vec<&str> = original_source
+            .lines()
+            .skip(start_line - 1)
+            .take(size)
+            .collect();
+
+        let assertions = count_assertion_calls(&body.join("\n"), &self.rule_set.assertion_macros);
+        let min_assertions = self.rule_set.min_assertions;
+        if assertions < min_assertions {
+            let name = declarator_name(&function_definition.declarator.node).unwrap_or_default();
+            self.report(
+                "require_assertions",
+                span,
+                &format!(
+                    "Function '{}' has {} assertions, at least {} are required",
+                    name, assertions, min_assertions
+                ),
+                None,
+            );
+        }
+    }
+
+    // Comment block directly above the given offset. The preprocessor strips comments
+    // from the analyzed source, so they are read back from the original file.
+    fn get_preceding_comment(&self, span_point: usize) -> Option<String> {
+        let location = self.line_index.location(span_point);
+        let original_source = self.original_source(location.file)?;
+        let lines: Vec<&str> = original_source.lines().take(location.line - 1).collect();
+
+        let comment_lines: Vec<&str> = lines
+            .iter()
+            .rev()
+            .take_while(|line| {
+                let line = line.trim_start();
+                line.starts_with("//") || line.starts_with("/*") || line.starts_with('*')
+            })
+            .copied()
+            .collect();
+        if comment_lines.is_empty() {
+            return None;
+        }
+
+        let comment: Vec<&str> = comment_lines.into_iter().rev().collect();
+        Some(comment.join("\n"))
+    }
+
+    // A file as it was before preprocessing, read once per analysis
+    fn original_source(&self, file: &str) -> Option<Rc<str>> {
+        self.original_sources
+            .borrow_mut()
+            .entry(file.to_string())
+            .or_insert_with(|| fs::read_to_string(file).ok().map(Rc::from))
+            .clone()
+    }
+
+    fn get_source_code_from_span(&self, span: &Span) -> String {
+        let source_line = &self.source[span.start..span.end];
+        let squiggles = "^".repeat(span.end - span.start); // Create squiggles for the span length
+        format!("{}\n{}", source_line, squiggles)
+    }
+
+    // Record a violation of `rule` at the start of the given span
+    fn report(&mut self, rule: &str, span: &Span, message: &str, snippet: Option<String>) {
+        // A construct reached through overlapping visitor paths, e.g. a call inside a
+        // cast, is only reported once per rule
+        if !self
+            .reported
+            .insert((rule.to_string(), span.start, span.end))
+        {
+            return;
+        }
+        let location = self.line_index.location(span.start);
+        self.diagnostics.push(Diagnostic {
+            rule: rule.to_string(),
+            severity: self.rule_set.severity(rule),
+            file: location.file.to_string(),
+            line: location.line,
+            column: location.column,
+            message: message.to_string(),
+            snippet,
+        });
+    }
+
+    fn check_goto(&mut self, statement: &lang_c::ast::Statement, span: &Span) {
+        if let lang_c::ast::Statement::Goto(_) = statement {
+            self.report("restrict_goto", span, "'goto' statement found", None);
+        }
+    }
+
+    // Report if, else and loop bodies that are not compound statements. `else if` is
+    // allowed, the nested if is checked itself.
+    fn check_braces(&mut self, statement: &lang_c::ast::Statement) {
+        let bodies = match statement {
+            lang_c::ast::Statement::If(if_statement) => {
+                let mut bodies = vec![("if", &*if_statement.node.then_statement)];
+                if let Some(else_statement) = &if_statement.node.else_statement {
+                    if !matches!(else_statement.node, lang_c::ast::Statement::If(_)) {
+                        bodies.push(("else", &**else_statement));
+                    }
+                }
+                bodies
+            }
+            lang_c::ast::Statement::While(while_statement) => {
+                vec![("while", &*while_statement.node.statement)]
+            }
+            lang_c::ast::Statement::DoWhile(do_while_statement) => {
+                vec![("do", &*do_while_statement.node.statement)]
+            }
+            lang_c::ast::Statement::For(for_statement) => {
+                vec![("for", &*for_statement.node.statement)]
+            }
+            _ => return,
+        };
+
+        for (keyword, body) in bodies {
+            if !matches!(body.node, lang_c::ast::Statement::Compound(_)) {
+                self.report(
+                    "require_braces",
+                    &body.span,
+                    &format!("Body of '{}' is not enclosed in braces", keyword),
+                    None,
+                );
+            }
+        }
+    }
+
+    fn check_setjmp(&mut self, call_expression: &lang_c::ast::CallExpression, span: &Span) {
+        if let lang_c::ast::Expression::Identifier(identifier) = &call_expression.callee.node {
+            if identifier.node.name == "setjmp" {
+                self.report("restrict_setjmp", span, "'setjmp' call found", None);
+            }
+        }
+    }
+
+    fn check_longjmp(&mut self, call_expression: &lang_c::ast::CallExpression, span: &Span) {
+        if let lang_c::ast::Expression::Identifier(identifier) = &call_expression.callee.node {
+            if identifier.node.name == "longjmp" {
+                self.report("restrict_longjmp", span, "'longjmp' call found", None);
+            }
+        }
+    }
+
+    fn set_current_function(
+        &mut self,
+        function_definition: &lang_c::ast::FunctionDefinition,
+        span: &Span,
+    ) {
+        let declarator = &function_definition.declarator.node.kind.node;
+        if let lang_c::ast::DeclaratorKind::Identifier(identifier) = declarator {
+            self.current_function = Some(identifier.node.name.clone());
+            self.current_function_parameters =
+                function_parameters(&function_definition.declarator.node);
+
+            if self
+                .rule_set
+                .allowed_recursive_functions
+                .contains(&identifier.node.name)
+            {
+                self.current_recursion_annotation = self
+                    .get_preceding_comment(span.start)
+                    .and_then(|comment| parse_recursion_annotation(&comment));
+            }
+        }
+    }
+
+    fn check_recursion(&mut self, call_expression: &lang_c::ast::CallExpression, span: &Span) {
+        if let lang_c::ast::Expression::Identifier(identifier) = &call_expression.callee.node {
+            if let Some(caller) = &self.current_function {
+                let callees = self.call_graph.entry(caller.clone()).or_default();
+                if !callees
+                    .iter()
+                    .any(|(callee, _)| *callee == identifier.node.name)
+                {
+                    callees.push((identifier.node.name.clone(), *span));
+                }
+            }
+
+            if self.current_function.as_ref() == Some(&identifier.node.name) {
+                if self
+                    .rule_set
+                    .allowed_recursive_functions
+                    .contains(&identifier.node.name)
+                {
+                    self.check_recursion_measure(call_expression, span);
+                } else {
+                    self.report("restrict_recursion", span, "Recursion found", None);
+                }
+            }
+        }
+    }
+
+    // Report every cycle of two or more functions in the call graph of the translation
+    // unit, listing the calls forming it. Cycles made only of allow-listed recursive
+    // functions are accepted.
+    fn check_indirect_recursion(&mut self) {
+        let mut cycles = BTreeSet::new();
+        for function in self.call_graph.keys() {
+            let Some(mut cycle) = shortest_call_cycle(&self.call_graph, function) else {
+                continue;
+            };
+            // The same cycle is found from each of its functions, start it at the smallest
+            let start = (0..cycle.len())
+                .min_by_key(|&index| &cycle[index].0)
+                .unwrap_or_default();
+            cycle.rotate_left(start);
+            cycles.insert(cycle);
+        }
+
+        for cycle in cycles {
+            if cycle
+                .iter()
+                .all(|(caller, _)| self.rule_set.allowed_recursive_functions.contains(caller))
+            {
+                continue;
+            }
+            let calls: Vec<String> = cycle
+                .iter()
+                .enumerate()
+                .map(|(index, (caller, span))| {
+                    let callee = &cycle[(index + 1) % cycle.len()].0;
+                    format!(
+                        "{} calls {} (line {})",
+                        caller,
+                        callee,
+                        self.get_line_number(span.start)
+                    )
+                })
+                .collect();
+            self.report(
+                "restrict_recursion",
+                &cycle[0].1,
+                &format!("Indirect recursion: {}", calls.join(", ")),
+                None,
+            );
+        }
+    }
+
+    // An allow-listed recursive call must be annotated and strictly reduce its measure parameter
+    fn check_recursion_measure(
+        &mut self,
+        call_expression: &lang_c::ast::CallExpression,
+        span: &Span,
+    ) {
+        let Some(annotation) = &self.current_recursion_annotation else {
+            self.report(
+                "restrict_recursion",
+                span,
+                "Recursive function has no '@recursion max_depth=N measure=param' annotation",
+                None,
+            );
+            return;
+        };
+
+        let measure = annotation.measure.clone();
+        let Some(index) = self
+            .current_function_parameters
+            .iter()
+            .position(|parameter| *parameter == measure)
+        else {
+            self.report(
+                "restrict_recursion",
+                span,
+                &format!("Recursion measure '{}' is not a parameter", measure),
+                None,
+            );
+            return;
+        };
+
+        let reduced = call_expression
+            .arguments
+            .get(index)
+            .is_some_and(|argument| is_strictly_reduced(&argument.node, &measure));
+        if !reduced {
+            let snippet = self.get_source_code_from_span(span);
+            self.report(
+                "restrict_recursion",
+                span,
+                &format!("Recursive call does not strictly reduce '{}'", measure),
+                Some(snippet),
+            );
+        }
+    }
+
+    // Iteration bound declared in a comment above a loop, e.g. /* loop-bound: 128 */
+    fn declared_loop_bound(&self, span: &Span) -> Option<u64> {
+        self.get_preceding_comment(span.start)
+            .and_then(|comment| parse_loop_bound(&comment, &self.rule_set.loop_bound_marker))
+    }
+
+    // Check the condition of a while or do-while loop for a comparison against a constant
+    fn check_loop_condition_bounds(&mut self, condition: &lang_c::ast::Expression, span: &Span) {
+        let is_bound =
+            |comparison: &lang_c::ast::Expression| self.is_constant_comparison(comparison);
+        if !is_bounded_condition(condition, &is_bound) {
+            self.report(
+                "fixed_loop_bounds",
+                span,
+                "Loop does not have fixed bounds",
+                None,
+            );
+        }
+    }
+
+    // Whether a comparison has a constant on one side, e.g. `count < 10`
+    fn is_constant_comparison(&self, comparison: &lang_c::ast::Expression) -> bool {
+        let lang_c::ast::Expression::BinaryOperator(binary_operator_expression) = comparison else {
+            return false;
+        };
+        if !matches!(
+            binary_operator_expression.node.operator.node,
+            lang_c::ast::BinaryOperator::Less
+                | lang_c::ast::BinaryOperator::LessOrEqual
+                | lang_c::ast::BinaryOperator::Greater
+                | lang_c::ast::BinaryOperator::GreaterOrEqual
+                | lang_c::ast::BinaryOperator::Equals
+        ) {
+            return false;
+        }
+        let lhs = &binary_operator_expression.node.lhs.node;
+        let rhs = &binary_operator_expression.node.rhs.node;
+
+        // Floating-point comparisons never count as a fixed bound
+        if self.is_floating_expression(lhs) || self.is_floating_expression(rhs) {
+            return false;
+        }
+        self.is_constant_bound(lhs) || self.is_constant_bound(rhs)
+    }
+
+    // Whether an expression is a compile-time constant a loop can be bounded by: a
+    // literal, an enum constant, a const variable initialized with a constant, sizeof,
+    // or arithmetic on them such as `MAX_ITEMS - 1`
+    fn is_constant_bound(&self, expression: &lang_c::ast::Expression) -> bool {
+        match expression {
+            lang_c::ast::Expression::Constant(_)
+            | lang_c::ast::Expression::SizeOfTy(_)
+            | lang_c::ast::Expression::SizeOfVal(_) => true,
+            lang_c::ast::Expression::Identifier(identifier) => {
+                self.compile_time_constants.contains(&identifier.node.name)
+            }
+            lang_c::ast::Expression::BinaryOperator(binary_operator_expression) => {
+                !is_assignment_operator(&binary_operator_expression.node.operator.node)
+                    && self.is_constant_bound(&binary_operator_expression.node.lhs.node)
+                    && self.is_constant_bound(&binary_operator_expression.node.rhs.node)
+            }
+            lang_c::ast::Expression::Cast(cast_expression) => {
+                self.is_constant_bound(&cast_expression.node.expression.node)
+            }
+            _ => false,
+        }
+    }
+
+    // Remember const variables of arithmetic type initialized with a constant, which
+    // loops can be bounded by like literals
+    fn add_compile_time_constants(&mut self, declaration: &lang_c::ast::Declaration) {
+        if is_volatile(&declaration.specifiers) {
+            return;
+        }
+        for init_declarator in &declaration.declarators {
+            let declarator = &init_declarator.node.declarator.node;
+            let Some(initializer) = &init_declarator.node.initializer else {
+                continue;
+            };
+            let lang_c::ast::Initializer::Expression(value) = &initializer.node else {
+                continue;
+            };
+            if !declarator.derived.is_empty()
+                || is_mutable_data(&declaration.specifiers, declarator)
+                || !self.is_constant_bound(&value.node)
+            {
+                continue;
+            }
+            if let Some(name) = declarator_name(declarator) {
+                self.compile_time_constants.insert(name.to_string());
+            }
+        }
+    }
+
+    // A for loop is bounded when its condition compares a variable against a constant
+    // and the increment clause moves the variable towards it
+    fn check_for_loop_bounds(&mut self, for_statement: &lang_c::ast::ForStatement, span: &Span) {
+        let Some(condition) = &for_statement.condition else {
+            self.report(
+                "fixed_loop_bounds",
+                span,
+                "Loop does not have fixed bounds",
+                None,
+            );
+            return;
+        };
+        let step = for_statement.step.as_ref().map(|step| &step.node);
+        let moves_toward_bound = |comparison: &lang_c::ast::Expression| {
+            self.loop_bound(comparison)
+                .is_some_and(|(counter, direction)| {
+                    step.and_then(|step| step_direction(step, counter)) == Some(direction)
+                })
+        };
+        if is_bounded_condition(&condition.node, &moves_toward_bound) {
+            return;
+        }
+
+        let counter = logical_operands(&condition.node)
+            .into_iter()
+            .find_map(|comparison| self.loop_bound(comparison));
+        let message = match counter {
+            Some((counter, _)) => {
+                format!("Loop counter '{}' does not move toward its bound", counter)
+            }
+            None => "Loop does not have fixed bounds".to_string(),
+        };
+        self.report("fixed_loop_bounds", span, &message, None);
+    }
+
+    // Report assignments in a loop body that reset a counter the condition bounds or
+    // move it away from its bound, which can keep the loop from terminating. Steps
+    // toward the bound, e.g. `i += 2` for `i < 10`, are fine.
+    fn check_bound_counter_modifications(
+        &mut self,
+        condition: &lang_c::ast::Expression,
+        body: &lang_c::span::Node<lang_c::ast::Statement>,
+    ) {
+        let bounds: Vec<(&str, Direction)> = logical_operands(condition)
+            .into_iter()
+            .filter_map(|comparison| self.loop_bound(comparison))
+            .collect();
+        if bounds.is_empty() {
+            return;
+        }
+        let counters: Vec<String> = bounds
+            .iter()
+            .map(|(counter, _)| counter.to_string())
+            .collect();
+
+        let mut finder = CounterModificationFinder {
+            counters: &counters,
+            modifications: Vec::new(),
+        };
+        finder.visit_statement(&body.node, &body.span);
+
+        for (counter, span, step) in finder.modifications {
+            let direction = bounds
+                .iter()
+                .find(|(name, _)| *name == counter)
+                .map(|(_, direction)| *direction);
+            if step.is_some() && step == direction {
+                continue;
+            }
+            self.report(
+                "fixed_loop_bounds",
+                &span,
+                &format!(
+                    "Loop counter '{}' is reset or moved away from its bound in the loop body",
+                    counter
+                ),
+                None,
+            );
+        }
+    }
+
+    // Variable a loop condition compares against a constant bound, with the way it has
+    // to move to reach the bound, e.g. up for `i < 10` and `10 > i`
+    fn loop_bound<'a>(
+        &self,
+        condition: &'a lang_c::ast::Expression,
+    ) -> Option<(&'a str, Direction)> {
+        let lang_c::ast::Expression::BinaryOperator(binary_operator_expression) = condition else {
+            return None;
+        };
+        let direction = match binary_operator_expression.node.operator.node {
+            lang_c::ast::BinaryOperator::Less | lang_c::ast::BinaryOperator::LessOrEqual => {
+                Direction::Up
+            }
+            lang_c::ast::BinaryOperator::Greater | lang_c::ast::BinaryOperator::GreaterOrEqual => {
+                Direction::Down
+            }
+            _ => return None,
+        };
+        let lhs = &binary_operator_expression.node.lhs.node;
+        let rhs = &binary_operator_expression.node.rhs.node;
+
+        // Floating-point comparisons never count as a fixed bound
+        if self.is_floating_expression(lhs) || self.is_floating_expression(rhs) {
+            return None;
+        }
+
+        match (lhs, rhs) {
+            (lang_c::ast::Expression::Identifier(counter), bound)
+                if self.is_constant_bound(bound) =>
+            {
+                Some((&counter.node.name, direction))
+            }
+            (bound, lang_c::ast::Expression::Identifier(counter))
+                if self.is_constant_bound(bound) =>
+            {
+                Some((&counter.node.name, direction.reversed()))
+            }
+            _ => None,
+        }
+    }
+
+    fn check_heap_usage(&mut self, call_expression: &lang_c::ast::CallExpression, span: &Span) {
+        if let lang_c::ast::Expression::Identifier(identifier) = &call_expression.callee.node {
+            if self.rule_set.is_heap_function(&identifier.node.name) {
+                let snippet = self.get_source_code_from_span(span);
+                self.report(
+                    "restrict_heap_allocation",
+                    span,
+                    "Heap usage found",
+                    Some(snippet),
+                );
+            }
+        }
+    }
+
+    fn check_stack_allocation(
+        &mut self,
+        call_expression: &lang_c::ast::CallExpression,
+        span: &Span,
+    ) {
+        if let lang_c::ast::Expression::Identifier(identifier) = &call_expression.callee.node {
+            if self
+                .rule_set
+                .is_stack_allocation_function(&identifier.node.name)
+            {
+                let snippet = self.get_source_code_from_span(span);
+                self.report(
+                    "restrict_alloca",
+                    span,
+                    &format!("Stack allocation with '{}' found", identifier.node.name),
+                    Some(snippet),
+                );
+            }
+        }
+    }
+
+    // Check a loop condition for comparisons involving floating-point operands
+    fn check_float_loop_condition(&mut self, condition: &lang_c::ast::Expression, span: &Span) {
+        let lang_c::ast::Expression::BinaryOperator(binary_operator_expression) = condition else {
+            return;
+        };
+        let lhs = &binary_operator_expression.node.lhs.node;
+        let rhs = &binary_operator_expression.node.rhs.node;
+
+        match binary_operator_expression.node.operator.node {
+            lang_c::ast::BinaryOperator::LogicalAnd | lang_c::ast::BinaryOperator::LogicalOr => {
+                self.check_float_loop_condition(lhs, span);
+                self.check_float_loop_condition(rhs, span);
+            }
+            lang_c::ast::BinaryOperator::Equals | lang_c::ast::BinaryOperator::NotEquals => {
+                if self.is_floating_expression(lhs) || self.is_floating_expression(rhs) {
+                    self.report(
+                        "restrict_float_loop_counters",
+                        span,
+                        "Loop bound compares floating-point values for equality",
+                        None,
+                    );
+                }
+            }
+            lang_c::ast::BinaryOperator::Less
+            | lang_c::ast::BinaryOperator::LessOrEqual
+            | lang_c::ast::BinaryOperator::Greater
+            | lang_c::ast::BinaryOperator::GreaterOrEqual => {
+                if self.is_floating_expression(lhs) || self.is_floating_expression(rhs) {
+                    self.report(
+                        "restrict_float_loop_counters",
+                        span,
+                        "Loop is controlled by a floating-point counter",
+                        None,
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn check_for_loop_float_counter(
+        &mut self,
+        for_statement: &lang_c::ast::ForStatement,
+        span: &Span,
+    ) {
+        // A counter declared in the loop header is not in the symbol table yet
+        if let lang_c::ast::ForInitializer::Declaration(declaration) =
+            &for_statement.initializer.node
+        {
+            if scalar_type(&type_specifiers(&declaration.node.specifiers))
+                == Some(ScalarType::Floating)
+            {
+                self.report(
+                    "restrict_float_loop_counters",
+                    span,
+                    "Loop is controlled by a floating-point counter",
+                    None,
+                );
+                return;
+            }
+        }
+
+        if let Some(condition) = &for_statement.condition {
+            self.check_float_loop_condition(&condition.node, span);
+        }
+    }
+
+    fn check_loop_counter_modification(&mut self, for_statement: &lang_c::ast::ForStatement) {
+        let counters = loop_counters(&for_statement.initializer.node);
+        if counters.is_empty() {
+            return;
+        }
+
+        let mut finder = CounterModificationFinder {
+            counters: &counters,
+            modifications: Vec::new(),
+        };
+        finder.visit_statement(&for_statement.statement.node, &for_statement.statement.span);
+
+        for (counter, span, _) in finder.modifications {
+            let snippet = self.get_source_code_from_span(&span);
+            self.report(
+                "restrict_loop_counter_modification",
+                &span,
+                &format!("Loop counter '{}' modified inside the loop body", counter),
+                Some(snippet),
+            );
+        }
+    }
+
+    fn add_volatile_objects(&mut self, declaration: &lang_c::ast::Declaration) {
+        if !is_volatile(&declaration.specifiers) {
+            return;
+        }
+        for init_declarator in &declaration.declarators {
+            if let Some(name) = declarator_name(&init_declarator.node.declarator.node) {
+                self.volatile_objects.insert(name.to_string());
+            }
+        }
+    }
+
+    // A volatile register read for a check and read again in the block the check
+    // guards may have changed in between, so the block acts on a value that was never
+    // checked. Registers that must be read fresh must not be tested through a copy.
+    fn check_volatile_toctou(&mut self, function_definition: &lang_c::ast::FunctionDefinition) {
+        let mut checker = VolatileAccessChecker {
+            volatile_objects: self.volatile_objects.clone(),
+            fresh_read_registers: &self.rule_set.fresh_read_registers,
+            cached_registers: HashMap::new(),
+            findings: Vec::new(),
+        };
+        checker.visit_statement(
+            &function_definition.statement.node,
+            &function_definition.statement.span,
+        );
+
+        for (span, message) in checker.findings {
+            self.report("restrict_volatile_toctou", &span, &message, None);
+        }
+    }
+
+    // Flag gotos that jump backwards, into a nested block, or past an initialization
+    fn check_unstructured_goto(&mut self, function_definition: &lang_c::ast::FunctionDefinition) {
+        let mut collector = GotoCollector::default();
+        collector.visit_statement(
+            &function_definition.statement.node,
+            &function_definition.statement.span,
+        );
+
+        for (label, goto_span, goto_blocks) in &collector.gotos {
+            let Some((label_offset, label_blocks)) = collector.labels.get(label) else {
+                continue;
+            };
+
+            if !goto_blocks.starts_with(label_blocks) {
+                self.report(
+                    "restrict_unstructured_goto",
+                    goto_span,
+                    &format!("'goto' jumps into a nested block at label '{}'", label),
+                    None,
+                );
+            }
+
+            if *label_offset < goto_span.start {
+                self.report(
+                    "restrict_unstructured_goto",
+                    goto_span,
+                    &format!("Backward 'goto' to label '{}'", label),
+                    None,
+                );
+                continue;
+            }
+
+            // Initializations between the goto and the label that are still in scope at the label
+            for (variable, offset, declaration_blocks) in &collector.initializations {
+                if goto_span.end <= *offset
+                    && offset < label_offset
+                    && label_blocks.starts_with(declaration_blocks)
+                {
+                    self.report(
+                        "restrict_unstructured_goto",
+                        goto_span,
+                        &format!("'goto' skips initialization of '{}'", variable),
+                        None,
+                    );
+                }
+            }
+        }
+    }
+
+    // Report the first statement of every region of the function the control-flow
+    // graph cannot reach from its entry
+    fn check_unreachable_code(&mut self, function_definition: &lang_c::ast::FunctionDefinition) {
+        let graph = ControlFlowGraph::build(function_definition);
+        let reachable = graph.reachable();
+
+        let mut unreachable: Vec<usize> = (0..graph.blocks.len())
+            .filter(|&block| !reachable[block] && !graph.blocks[block].elements.is_empty())
+            .collect();
+        unreachable.sort_by_key(|&block| graph.blocks[block].elements[0].span().start);
+
+        // Blocks that follow an unreachable block are part of its region
+        let mut covered = vec![false; graph.blocks.len()];
+        for block in unreachable {
+            if covered[block] {
+                continue;
+            }
+            let mut pending = vec![block];
+            while let Some(next) = pending.pop() {
+                if reachable[next] || covered[next] {
+                    continue;
+                }
+                covered[next] = true;
+                pending.extend(&graph.blocks[next].successors);
+            }
+
+            let span = graph.blocks[block].elements[0].span();
+            self.report("restrict_unreachable_code", &span, "Unreachable code", None);
+        }
+    }
+
+    // Report functions and function pointers declared or defined without a prototype,
+    // either K&R style with an identifier list or with empty parentheses
+    fn check_prototype(&mut self, declarator: &lang_c::span::Node<lang_c::ast::Declarator>) {
+        let Some(identifiers) = derivations(&declarator.node)
+            .into_iter()
+            .find_map(|derived| match derived {
+                lang_c::ast::DerivedDeclarator::KRFunction(identifiers) => Some(identifiers),
+                _ => None,
+            })
+        else {
+            return;
+        };
+
+        let name = declarator_name(&declarator.node).unwrap_or_default();
+        let message = if identifiers.is_empty() {
+            format!(
+                "Function '{}' is declared without a prototype, write (void) for no parameters",
+                name
+            )
+        } else {
+            format!(
+                "Function '{}' uses an old-style identifier list, declare the parameter types in a prototype",
+                name
+            )
+        };
+        self.report("require_prototypes", &declarator.span, &message, None);
+    }
+
+    // Report function definitions and prototypes taking more than max_parameters
+    // parameters. `(void)` and the ellipsis of a variadic function do not count.
+    fn check_parameter_count(&mut self, declarator: &lang_c::span::Node<lang_c::ast::Declarator>) {
+        let count = match derivations(&declarator.node).first() {
+            Some(lang_c::ast::DerivedDeclarator::Function(function_declarator)) => {
+                let parameters = &function_declarator.node.parameters;
+                let is_void = matches!(
+                    &parameters[..],
+                    [parameter] if parameter.node.declarator.is_none()
+                        && matches!(
+                            &parameter.node.specifiers[..],
+                            [lang_c::span::Node {
+                                node: lang_c::ast::DeclarationSpecifier::TypeSpecifier(type_specifier),
+                                ..
+                            }] if type_specifier.node == lang_c::ast::TypeSpecifier::Void
+                        )
+                );
+                if is_void {
+                    0
+                } else {
+                    parameters.len()
+                }
+            }
+            Some(lang_c::ast::DerivedDeclarator::KRFunction(identifiers)) => identifiers.len(),
+            _ => return,
+        };
+
+        let max_parameters = self.rule_set.max_parameters;
+        if count > max_parameters {
+            let name = declarator_name(&declarator.node).unwrap_or_default();
+            self.report(
+                "restrict_parameter_count",
+                &declarator.span,
+                &format!(
+                    "Function '{}' takes {} parameters, more than {}, pass related values in a struct",
+                    name, count, max_parameters
+                ),
+                None,
+            );
+        }
+    }
+
+    // Report case labels that the statements of the previous case fall through to,
+    // unless a comment holding the fallthrough marker precedes the label. Labels that
+    // directly follow another label share its statements and are not reported.
+    fn check_implicit_fallthrough(
+        &mut self,
+        function_definition: &lang_c::ast::FunctionDefinition,
+    ) {
+        let graph = ControlFlowGraph::build(function_definition);
+        let reachable = graph.reachable();
+        for case_label in &graph.case_labels {
+            if !reachable[case_label.previous] {
+                continue;
+            }
+            let follows_label = graph.blocks[case_label.previous].elements.is_empty()
+                && graph
+                    .case_labels
+                    .iter()
+                    .any(|other| other.block == case_label.previous);
+            if follows_label {
+                continue;
+            }
+            let marker = self.rule_set.fallthrough_marker.to_lowercase();
+            if self
+                .get_preceding_comment(case_label.span.start)
+                .is_some_and(|comment| comment.to_lowercase().contains(&marker))
+            {
+                continue;
+            }
+
+            let message = format!(
+                "Previous case falls through to this label without break, return or goto, mark it with a '{}' comment if intended",
+                self.rule_set.fallthrough_marker
+            );
+            self.report(
+                "restrict_implicit_fallthrough",
+                &case_label.span,
+                &message,
+                None,
+            );
+        }
+    }
+
+    // Report the first read of each local variable that some path reaches before the
+    // variable is assigned
+    fn check_uninitialized_reads(&mut self, function_definition: &lang_c::ast::FunctionDefinition) {
+        for (name, span) in uninitialized_reads(function_definition) {
+            self.report(
+                "restrict_uninitialized_reads",
+                &span,
+                &format!("Variable '{}' may be read before it is assigned", name),
+                None,
+            );
+        }
+    }
+
+    // Report pointer parameters whose pointee the function never writes but which are not
+    // declared as pointers to const
+    fn check_const_pointer_parameters(
+        &mut self,
+        function_definition: &lang_c::ast::FunctionDefinition,
+    ) {
+        for (name, span) in unwritten_pointer_parameters(function_definition) {
+            self.report(
+                "require_const_correctness",
+                &span,
+                &format!(
+                    "Pointer parameter '{}' is never written through, declare it as a pointer to const",
+                    name
+                ),
+                None,
+            );
+        }
+    }
+
+    // Record whether a declarator declares a pointer to const. A later declaration of
+    // the same name without const, e.g. a local shadowing a parameter, replaces it.
+    fn add_const_pointer(
+        &mut self,
+        specifiers: &[lang_c::span::Node<lang_c::ast::DeclarationSpecifier>],
+        declarator: &lang_c::ast::Declarator,
+    ) {
+        let Some(name) = declarator_name(declarator) else {
+            return;
+        };
+        let is_pointer = matches!(
+            declarator.derived.first(),
+            Some(derived) if matches!(derived.node, lang_c::ast::DerivedDeclarator::Pointer(_))
+        );
+        if is_pointer && is_const(specifiers) {
+            self.const_pointers.insert(name.to_string());
+        } else {
+            self.const_pointers.remove(name);
+        }
+    }
+
+    // Report casts of a pointer to const to a pointer type without const, which lets
+    // the object be written through the result
+    fn check_const_cast(&mut self, cast_expression: &lang_c::ast::CastExpression, span: &Span) {
+        let type_name = &cast_expression.type_name.node;
+        let casts_to_pointer = type_name.declarator.as_ref().is_some_and(|declarator| {
+            matches!(
+                declarator.node.derived.first(),
+                Some(derived) if matches!(derived.node, lang_c::ast::DerivedDeclarator::Pointer(_))
+            )
+        });
+        let keeps_const = type_name.specifiers.iter().any(|specifier| {
+            matches!(
+                &specifier.node,
+                lang_c::ast::SpecifierQualifier::TypeQualifier(qualifier)
+                    if matches!(qualifier.node, lang_c::ast::TypeQualifier::Const)
+            )
+        });
+        if !casts_to_pointer || keeps_const {
+            return;
+        }
+
+        let lang_c::ast::Expression::Identifier(identifier) = &cast_expression.expression.node
+        else {
+            return;
+        };
+        if self.const_pointers.contains(&identifier.node.name) {
+            self.report(
+                "require_const_correctness",
+                span,
+                &format!(
+                    "Cast discards the const qualifier of what '{}' points to",
+                    identifier.node.name
+                ),
+                None,
+            );
+        }
+    }
+
+    // Report non-const variables defined at file scope, which every function can depend
+    // on. extern declarations are reported where the variable is defined. Static
+    // variables may be allowed, as may names matching one of global_allowed_patterns.
+    fn check_global_declaration(&mut self, declaration: &lang_c::ast::Declaration) {
+        if is_extern_or_typedef(&declaration.specifiers) {
+            return;
+        }
+        if self.rule_set.allow_static_globals && is_static(&declaration.specifiers) {
+            return;
+        }
+        for init_declarator in &declaration.declarators {
+            let declarator = &init_declarator.node.declarator;
+            if !is_mutable_data(&declaration.specifiers, &declarator.node) {
+                continue;
+            }
+            let Some(name) = declarator_name(&declarator.node) else {
+                continue;
+            };
+            let is_allowed = self
+                .rule_set
+                .global_allowed_patterns
+                .iter()
+                .any(|pattern| glob_matches(pattern, name));
+            if is_allowed {
+                continue;
+            }
+            self.report(
+                "restrict_global_mutable_data",
+                &declarator.span,
+                &format!("Global variable '{}' is not const", name),
+                None,
+            );
+        }
+    }
+
+    // Report variables declared in the outermost block of a function that only one
+    // nested block or for statement uses, which could declare them itself. Loop bodies
+    // do not count, as the value may have to carry over between iterations.
+    fn check_local_scopes(&mut self, function_definition: &lang_c::ast::FunctionDefinition) {
+        let lang_c::ast::Statement::Compound(items) = &function_definition.statement.node else {
+            return;
+        };
+
+        for (index, item) in items.iter().enumerate() {
+            let lang_c::ast::BlockItem::Declaration(declaration) = &item.node else {
+                continue;
+            };
+            for init_declarator in &declaration.node.declarators {
+                // Moving the declaration would change when a non-constant initializer runs
+                if let Some(initializer) = &init_declarator.node.initializer {
+                    if !is_constant_initializer(&initializer.node) {
+                        continue;
+                    }
+                }
+                let declarator = &init_declarator.node.declarator;
+                if matches!(
+                    derivations(&declarator.node).first(),
+                    Some(
+                        lang_c::ast::DerivedDeclarator::Function(_)
+                            | lang_c::ast::DerivedDeclarator::KRFunction(_)
+                    )
+                ) {
+                    continue;
+                }
+                let Some(name) = declarator_name(&declarator.node) else {
+                    continue;
+                };
+
+                // Other declarators of the same declaration may use it too
+                let mut using = items[index..].iter().filter_map(|later| {
+                    let mut uses = UseCollector {
+                        name,
+                        uses: Vec::new(),
+                    };
+                    uses.visit_block_item(&later.node, &later.span);
+                    (!uses.uses.is_empty()).then_some((later, uses.uses))
+                });
+                let (Some((statement, uses)), None) = (using.next(), using.next()) else {
+                    continue;
+                };
+                let lang_c::ast::BlockItem::Statement(statement) = &statement.node else {
+                    continue;
+                };
+                let Some(scope) = nested_scopes(statement).into_iter().find(|scope| {
+                    uses.iter()
+                        .all(|usage| scope.start <= usage.start && usage.end <= scope.end)
+                }) else {
+                    continue;
+                };
+
+                let line = self.get_line_number(scope.start);
+                self.report(
+                    "require_smallest_scope",
+                    &declarator.span,
+                    &format!(
+                        "Variable '{}' is only used in the block at line {}, declare it there",
+                        name, line
+                    ),
+                    None,
+                );
+            }
+        }
+    }
+
+    // Report parameters and local variables that are never read. Assigning a variable
+    // is not a read; `(void)x;` is, so it marks a parameter as deliberately unused.
+    fn check_unused_variables(&mut self, function_definition: &lang_c::ast::FunctionDefinition) {
+        let tracker = UsageTracker::track(function_definition);
+        for local in tracker.locals.iter().filter(|local| !local.is_read) {
+            let is_allowed = self
+                .rule_set
+                .unused_allowed_patterns
+                .iter()
+                .any(|pattern| glob_matches(pattern, local.name));
+            if is_allowed {
+                continue;
+            }
+            let kind = if local.is_parameter {
+                "Parameter"
+            } else {
+                "Variable"
+            };
+            self.report(
+                "restrict_unused_variables",
+                &local.span,
+                &format!("{} '{}' is never read", kind, local.name),
+                None,
+            );
+        }
+    }
+
+    // Report parameters and local variables that reuse the name of a variable from an
+    // enclosing block or of a file-scope variable declared earlier in the same file
+    fn check_shadowing(&mut self, function_definition: &lang_c::ast::FunctionDefinition) {
+        let tracker = UsageTracker::track(function_definition);
+        for local in &tracker.locals {
+            let kind = if local.is_parameter {
+                "Parameter"
+            } else {
+                "Variable"
+            };
+            let message = if let Some(shadowed) = local.shadows {
+                format!(
+                    "{} '{}' shadows the variable declared at line {}",
+                    kind,
+                    local.name,
+                    self.get_line_number(shadowed.start)
+                )
+            } else if let Some(shadowed) = self.file_scope_variables.get(local.name).copied() {
+                // Globals of system headers are too many to keep clear of
+                let file = self.line_index.location(local.span.start).file;
+                if self.line_index.location(shadowed.start).file != file {
+                    continue;
+                }
+                format!(
+                    "{} '{}' shadows the file-scope variable declared at line {}",
+                    kind,
+                    local.name,
+                    self.get_line_number(shadowed.start)
+                )
+            } else {
+                continue;
+            };
+            self.report("restrict_shadowing", &local.span, &message, None);
+        }
+    }
+
+    // Record the variables a file-scope declaration declares
+    fn add_file_scope_variables(&mut self, declaration: &lang_c::ast::Declaration) {
+        let is_typedef = declaration.specifiers.iter().any(|specifier| {
+            matches!(
+                &specifier.node,
+                lang_c::ast::DeclarationSpecifier::StorageClass(storage_class)
+                    if matches!(storage_class.node, lang_c::ast::StorageClassSpecifier::Typedef)
+            )
+        });
+        if is_typedef {
+            return;
+        }
+        for init_declarator in &declaration.declarators {
+            let declarator = &init_declarator.node.declarator;
+            if matches!(
+                derivations(&declarator.node).first(),
+                Some(
+                    lang_c::ast::DerivedDeclarator::Function(_)
+                        | lang_c::ast::DerivedDeclarator::KRFunction(_)
+                )
+            ) {
+                continue;
+            }
+            if let Some(name) = declarator_name(&declarator.node) {
+                self.file_scope_variables
+                    .entry(name.to_string())
+                    .or_insert(declarator.span);
+            }
+        }
+    }
+
+    // Whether an expression evaluates to a floating-point value, as far as the symbol table knows
+    fn is_floating_expression(&self, expression: &lang_c::ast::Expression) -> bool {
+        match expression {
+            lang_c::ast::Expression::Constant(constant) => {
+                matches!(constant.node, lang_c::ast::Constant::Float(_))
+            }
+            lang_c::ast::Expression::Identifier(identifier) => {
+                match self.symbol_table.get(&identifier.node.name) {
+                    Some(Symbol {
+                        symbol_type:
+                            SymbolType::Variable {
+                                type_specifiers, ..
+                            },
+                        ..
+                    }) => {
+                        let type_specifiers: Vec<_> = type_specifiers.iter().collect();
+                        scalar_type(&type_specifiers) == Some(ScalarType::Floating)
+                    }
+                    _ => false,
+                }
+            }
+            lang_c::ast::Expression::UnaryOperator(unary_operator_expression) => {
+                match unary_operator_expression.node.operator.node {
+                    lang_c::ast::UnaryOperator::Plus | lang_c::ast::UnaryOperator::Minus => {
+                        self.is_floating_expression(&unary_operator_expression.node.operand.node)
+                    }
+                    _ => false,
+                }
+            }
+            lang_c::ast::Expression::BinaryOperator(binary_operator_expression) => {
+                match binary_operator_expression.node.operator.node {
+                    lang_c::ast::BinaryOperator::Plus
+                    | lang_c::ast::BinaryOperator::Minus
+                    | lang_c::ast::BinaryOperator::Multiply
+                    | lang_c::ast::BinaryOperator::Divide => {
+                        self.is_floating_expression(&binary_operator_expression.node.lhs.node)
+                            || self
+                                .is_floating_expression(&binary_operator_expression.node.rhs.node)
+                    }
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    // Report == and != with a floating-point operand, as far as the symbol table and
+    // float constants tell
+    fn check_float_equality(&mut self, expression: &lang_c::ast::Expression, span: &Span) {
+        let lang_c::ast::Expression::BinaryOperator(binary_operator_expression) = expression else {
+            return;
+        };
+        let operator = match binary_operator_expression.node.operator.node {
+            lang_c::ast::BinaryOperator::Equals => "==",
+            lang_c::ast::BinaryOperator::NotEquals => "!=",
+            _ => return,
+        };
+        if self.is_floating_expression(&binary_operator_expression.node.lhs.node)
+            || self.is_floating_expression(&binary_operator_expression.node.rhs.node)
+        {
+            self.report(
+                "restrict_float_equality",
+                span,
+                &format!(
+                    "Floating-point values compared with '{}', compare their difference against a tolerance",
+                    operator
+                ),
+                None,
+            );
+        }
+    }
+
+    // Report integer constants written in octal, other than 0 itself
+    fn check_octal_constant(&mut self, expression: &lang_c::ast::Expression, span: &Span) {
+        let lang_c::ast::Expression::Constant(constant) = expression else {
+            return;
+        };
+        let lang_c::ast::Constant::Integer(integer) = &constant.node else {
+            return;
+        };
+        if integer.base != lang_c::ast::IntegerBase::Octal
+            || integer.number.trim_start_matches('0').is_empty()
+        {
+            return;
+        }
+
+        let message = match integer_constant_value(expression) {
+            Some(value) => format!(
+                "Octal constant '0{}' has the value {}, write it in decimal or hexadecimal",
+                integer.number, value
+            ),
+            None => format!(
+                "Octal constant '0{}' found, write it in decimal or hexadecimal",
+                integer.number
+            ),
+        };
+        self.report("restrict_octal_constants", span, &message, None);
+    }
+
+    // Whether an expression has a signed integer type, as far as the symbol table and
+    // casts tell. Constants only count when negated, a non-negative value has the same
+    // bits either way.
+    fn is_signed_operand(&self, expression: &lang_c::ast::Expression) -> bool {
+        match expression {
+            lang_c::ast::Expression::Identifier(identifier) => matches!(
+                self.symbol_table.get(&identifier.node.name),
+                Some(Symbol {
+                    symbol_type: SymbolType::Variable { type_specifiers, .. },
+                    ..
+                }) if is_signed_type(&type_specifiers.iter().collect::<Vec<_>>())
+            ),
+            lang_c::ast::Expression::Cast(cast_expression) => {
+                let type_name = &cast_expression.node.type_name.node;
+                let is_pointer = type_name
+                    .declarator
+                    .as_ref()
+                    .is_some_and(|declarator| !declarator.node.derived.is_empty());
+                let type_specifiers: Vec<_> = type_name
+                    .specifiers
+                    .iter()
+                    .filter_map(|specifier| match &specifier.node {
+                        lang_c::ast::SpecifierQualifier::TypeSpecifier(type_specifier) => {
+                            Some(&type_specifier.node)
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                !is_pointer && is_signed_type(&type_specifiers)
+            }
+            lang_c::ast::Expression::UnaryOperator(unary_operator_expression)
+                if unary_operator_expression.node.operator.node
+                    == lang_c::ast::UnaryOperator::Minus =>
+            {
+                let operand = &unary_operator_expression.node.operand.node;
+                matches!(operand, lang_c::ast::Expression::Constant(_))
+                    || self.is_signed_operand(operand)
+            }
+            _ => false,
+        }
+    }
+
+    // Integer type of an expression as its size in bytes and name, as far as the
+    // symbol table, casts and function return types tell. Arithmetic takes the type
+    // of its widest operand, before promotion to int.
+    fn integer_type(&self, expression: &lang_c::ast::Expression) -> Option<(u64, String)> {
+        match expression {
+            lang_c::ast::Expression::Identifier(identifier) => {
+                match &self.symbol_table.get(&identifier.node.name)?.symbol_type {
+                    SymbolType::Variable {
+                        type_specifiers, ..
+                    } => integer_type_of(&type_specifiers.iter().collect::<Vec<_>>()),
+                    _ => None,
+                }
+            }
+            lang_c::ast::Expression::Cast(cast_expression) => {
+                let type_name = &cast_expression.node.type_name.node;
+                if type_name
+                    .declarator
+                    .as_ref()
+                    .is_some_and(|declarator| !declarator.node.derived.is_empty())
+                {
+                    return None;
+                }
+                let type_specifiers: Vec<&lang_c::ast::TypeSpecifier> = type_name
+                    .specifiers
+                    .iter()
+                    .filter_map(|specifier| match &specifier.node {
+                        lang_c::ast::SpecifierQualifier::TypeSpecifier(type_specifier) => {
+                            Some(&type_specifier.node)
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                integer_type_of(&type_specifiers)
+            }
+            lang_c::ast::Expression::Call(call_expression) => {
+                let lang_c::ast::Expression::Identifier(identifier) =
+                    &call_expression.node.callee.node
+                else {
+                    return None;
+                };
+                match &self.symbol_table.get(&identifier.node.name)?.symbol_type {
+                    SymbolType::Function { return_type } => integer_type_of(&[return_type]),
+                    _ => None,
+                }
+            }
+            lang_c::ast::Expression::BinaryOperator(binary_operator_expression) => {
+                let lhs = &binary_operator_expression.node.lhs.node;
+                let rhs = &binary_operator_expression.node.rhs.node;
+                match binary_operator_expression.node.operator.node {
+                    lang_c::ast::BinaryOperator::Plus
+                    | lang_c::ast::BinaryOperator::Minus
+                    | lang_c::ast::BinaryOperator::Multiply
+                    | lang_c::ast::BinaryOperator::Divide
+                    | lang_c::ast::BinaryOperator::Modulo
+                    | lang_c::ast::BinaryOperator::BitwiseAnd
+                    | lang_c::ast::BinaryOperator::BitwiseOr
+                    | lang_c::ast::BinaryOperator::BitwiseXor => {
+                        wider_integer_type(self.integer_type(lhs), self.integer_type(rhs))
+                    }
+                    lang_c::ast::BinaryOperator::ShiftLeft
+                    | lang_c::ast::BinaryOperator::ShiftRight => self.integer_type(lhs),
+                    _ => None,
+                }
+            }
+            lang_c::ast::Expression::UnaryOperator(unary_operator_expression) => {
+                match unary_operator_expression.node.operator.node {
+                    lang_c::ast::UnaryOperator::Plus
+                    | lang_c::ast::UnaryOperator::Minus
+                    | lang_c::ast::UnaryOperator::Complement => {
+                        self.integer_type(&unary_operator_expression.node.operand.node)
+                    }
+                    _ => None,
+                }
+            }
+            lang_c::ast::Expression::Conditional(conditional_expression) => wider_integer_type(
+                self.integer_type(&conditional_expression.node.then_expression.node),
+                self.integer_type(&conditional_expression.node.else_expression.node),
+            ),
+            _ => None,
+        }
+    }
+
+    // Report an integer value stored into a narrower integer type without a cast
+    fn check_narrowing(
+        &mut self,
+        target: &[&lang_c::ast::TypeSpecifier],
+        value: &lang_c::span::Node<lang_c::ast::Expression>,
+    ) {
+        let Some((target_bytes, target_name)) = integer_type_of(target) else {
+            return;
+        };
+        let Some((bytes, name)) = self.integer_type(&value.node) else {
+            return;
+        };
+        if bytes > target_bytes {
+            let snippet = self.get_source_code_from_span(&value.span);
+            self.report(
+                "restrict_implicit_narrowing",
+                &value.span,
+                &format!(
+                    "Value of type '{}' implicitly narrowed to '{}', cast it explicitly",
+                    name, target_name
+                ),
+                Some(snippet),
+            );
+        }
+    }
+
+    fn check_narrowing_assignment(&mut self, expression: &lang_c::ast::Expression) {
+        let lang_c::ast::Expression::BinaryOperator(binary_operator_expression) = expression else {
+            return;
+        };
+        if binary_operator_expression.node.operator.node != lang_c::ast::BinaryOperator::Assign {
+            return;
+        }
+        let lang_c::ast::Expression::Identifier(identifier) =
+            &binary_operator_expression.node.lhs.node
+        else {
+            return;
+        };
+        let Some(Symbol {
+            symbol_type: SymbolType::Variable {
+                type_specifiers, ..
+            },
+            ..
+        }) = self.symbol_table.get(&identifier.node.name)
+        else {
+            return;
+        };
+        let target = type_specifiers.clone();
+        self.check_narrowing(
+            &target.iter().collect::<Vec<_>>(),
+            &binary_operator_expression.node.rhs,
+        );
+    }
+
+    fn check_narrowing_initializers(&mut self, declaration: &lang_c::ast::Declaration) {
+        let target = type_specifiers(&declaration.specifiers);
+        for init_declarator in &declaration.declarators {
+            if !init_declarator.node.declarator.node.derived.is_empty() {
+                continue;
+            }
+            if let Some(lang_c::span::Node {
+                node: lang_c::ast::Initializer::Expression(expression),
+                ..
+            }) = &init_declarator.node.initializer
+            {
+                self.check_narrowing(&target, expression);
+            }
+        }
+    }
+
+    fn check_narrowing_arguments(&mut self, call_expression: &lang_c::ast::CallExpression) {
+        let lang_c::ast::Expression::Identifier(identifier) = &call_expression.callee.node else {
+            return;
+        };
+        let Some(parameter_types) = self.parameter_types.get(&identifier.node.name).cloned() else {
+            return;
+        };
+        for (parameter_type, argument) in parameter_types.iter().zip(&call_expression.arguments) {
+            self.check_narrowing(&parameter_type.iter().collect::<Vec<_>>(), argument);
+        }
+    }
+
+    // Remember the parameter types of a function declared or defined by a declarator
+    fn add_parameter_types(&mut self, declarator: &lang_c::ast::Declarator) {
+        let (
+            Some(name),
+            Some(lang_c::span::Node {
+                node: lang_c::ast::DerivedDeclarator::Function(function_declarator),
+                ..
+            }),
+        ) = (declarator_name(declarator), declarator.derived.first())
+        else {
+            return;
+        };
+        let parameter_types = function_declarator
+            .node
+            .parameters
+            .iter()
+            .map(|parameter| match &parameter.node.declarator {
+                Some(declarator) if !declarator.node.derived.is_empty() => Vec::new(),
+                _ => type_specifiers(&parameter.node.specifiers)
+                    .into_iter()
+                    .cloned()
+                    .collect(),
+            })
+            .collect();
+        self.parameter_types
+            .insert(name.to_string(), parameter_types);
+    }
+
+    // Report shifts and bitwise operators applied to an operand of signed type, whose
+    // sign bit makes the result implementation-defined or undefined
+    fn check_signed_bitwise(&mut self, expression: &lang_c::ast::Expression) {
+        let (operator, operands) = match expression {
+            lang_c::ast::Expression::BinaryOperator(binary_operator_expression) => {
+                let operator = match binary_operator_expression.node.operator.node {
+                    lang_c::ast::BinaryOperator::ShiftLeft => "<<",
+                    lang_c::ast::BinaryOperator::ShiftRight => ">>",
+                    lang_c::ast::BinaryOperator::BitwiseAnd => "&",
+                    lang_c::ast::BinaryOperator::BitwiseOr => "|",
+                    lang_c::ast::BinaryOperator::BitwiseXor => "^",
+                    lang_c::ast::BinaryOperator::AssignShiftLeft => "<<=",
+                    lang_c::ast::BinaryOperator::AssignShiftRight => ">>=",
+                    lang_c::ast::BinaryOperator::AssignBitwiseAnd => "&=",
+                    lang_c::ast::BinaryOperator::AssignBitwiseOr => "|=",
+                    lang_c::ast::BinaryOperator::AssignBitwiseXor => "^=",
+                    _ => return,
+                };
+                (
+                    operator,
+                    vec![
+                        &*binary_operator_expression.node.lhs,
+                        &*binary_operator_expression.node.rhs,
+                    ],
+                )
+            }
+            lang_c::ast::Expression::UnaryOperator(unary_operator_expression)
+                if unary_operator_expression.node.operator.node
+                    == lang_c::ast::UnaryOperator::Complement =>
+            {
+                ("~", vec![&*unary_operator_expression.node.operand])
+            }
+            _ => return,
+        };
+
+        for operand in operands {
+            if self.is_signed_operand(&operand.node) {
+                let snippet = self.get_source_code_from_span(&operand.span);
+                self.report(
+                    "restrict_signed_bitwise",
+                    &operand.span,
+                    &format!("Operator '{}' applied to a signed operand", operator),
+                    Some(snippet),
+                );
+            }
+        }
+    }
+
+    // Report numeric literals outside named constants, enumerators, array sizes and
+    // bit-field widths. Literals a macro expanded to are named by the macro.
+    fn check_magic_numbers(
+        &mut self,
+        external_declaration: &lang_c::ast::ExternalDeclaration,
+        span: &Span,
+    ) {
+        let mut numbers = NumberCollector::default();
+        numbers.visit_external_declaration(external_declaration, span);
+
+        for (constant, span, is_negative) in numbers.numbers {
+            let value = match constant {
+                lang_c::ast::Constant::Integer(integer) => {
+                    let radix = match integer.base {
+                        lang_c::ast::IntegerBase::Hexadecimal
+                            if self.rule_set.allow_hex_numbers =>
+                        {
+                            continue;
+                        }
+                        lang_c::ast::IntegerBase::Decimal => 10,
+                        lang_c::ast::IntegerBase::Octal => 8,
+                        lang_c::ast::IntegerBase::Hexadecimal => 16,
+                        lang_c::ast::IntegerBase::Binary => 2,
+                    };
+                    if integer.number.is_empty() {
+                        Some(0.0)
+                    } else {
+                        u64::from_str_radix(&integer.number, radix)
+                            .ok()
+                            .map(|value| value as f64)
+                    }
+                }
+                lang_c::ast::Constant::Float(float) => match float.base {
+                    lang_c::ast::FloatBase::Decimal => float.number.parse::<f64>().ok(),
+                    lang_c::ast::FloatBase::Hexadecimal => None,
+                },
+                lang_c::ast::Constant::Character(_) => continue,
+            };
+            let value = value.map(|value| if is_negative { -value } else { value });
+            if value.is_some_and(|value| self.rule_set.allowed_numbers.contains(&value)) {
+                continue;
+            }
+            if !self.is_written_in_original(&span) {
+                continue;
+            }
+
+            let sign = if is_negative { "-" } else { "" };
+            let message = format!(
+                "Magic number {}{}, name it with an enum or static const",
+                sign,
+                &self.source[span.start..span.end]
+            );
+            self.report("restrict_magic_numbers", &span, &message, None);
+        }
+    }
+
+    // Whether the line of the original file a span is on holds its text as a token,
+    // which is not the case for text a macro expanded to
+    fn is_written_in_original(&mut self, span: &Span) -> bool {
+        let text = &self.source[span.start..span.end];
+        let location = self.line_index.location(span.start);
+        let original_source = self.original_source(location.file);
+        let Some(line) = original_source
+            .as_deref()
+            .and_then(|source| source.lines().nth(location.line - 1))
+        else {
+            return true;
+        };
+
+        let is_token_character = |character: char| {
+            character.is_ascii_alphanumeric() || character == '_' || character == '.'
+        };
+        line.match_indices(text).any(|(index, _)| {
+            !line[..index].ends_with(is_token_character)
+                && !line[index + text.len()..].starts_with(is_token_character)
+        })
+    }
+
+    // Report array declarators, of variables, parameters and type names alike, whose
+    // size is not an integer constant expression, e.g. `int samples[count]`
+    fn check_variable_length_array(
+        &mut self,
+        array_declarator: &lang_c::ast::ArrayDeclarator,
+        span: &Span,
+    ) {
+        let size_span = match &array_declarator.size {
+            lang_c::ast::ArraySize::VariableExpression(size)
+            | lang_c::ast::ArraySize::StaticExpression(size) => {
+                if self.is_integer_constant_expression(&size.node) {
+                    return;
+                }
+                size.span
+            }
+            // `[*]` in a prototype
+            lang_c::ast::ArraySize::VariableUnknown => *span,
+            lang_c::ast::ArraySize::Unknown => return,
+        };
+        self.report(
+            "restrict_variable_length_arrays",
+            &size_span,
+            "Variable-length array, its size is not an integer constant expression",
+            None,
+        );
+    }
+
+    // Whether an expression is an integer constant expression: integer and character
+    // constants, enumerators, sizeof and the operators combining them. Unlike in C++,
+    // const variables do not count.
+    fn is_integer_constant_expression(&self, expression: &lang_c::ast::Expression) -> bool {
+        match expression {
+            lang_c::ast::Expression::Constant(constant) => {
+                !matches!(constant.node, lang_c::ast::Constant::Float(_))
+            }
+            lang_c::ast::Expression::Identifier(identifier) => {
+                self.enumeration_constants.contains(&identifier.node.name)
+            }
+            lang_c::ast::Expression::SizeOfTy(_)
+            | lang_c::ast::Expression::SizeOfVal(_)
+            | lang_c::ast::Expression::AlignOf(_)
+            | lang_c::ast::Expression::OffsetOf(_) => true,
+            lang_c::ast::Expression::UnaryOperator(unary_operator_expression) => {
+                matches!(
+                    unary_operator_expression.node.operator.node,
+                    lang_c::ast::UnaryOperator::Plus
+                        | lang_c::ast::UnaryOperator::Minus
+                        | lang_c::ast::UnaryOperator::Complement
+                        | lang_c::ast::UnaryOperator::Negate
+                ) && self
+                    .is_integer_constant_expression(&unary_operator_expression.node.operand.node)
+            }
+            lang_c::ast::Expression::BinaryOperator(binary_operator_expression) => {
+                !is_assignment_operator(&binary_operator_expression.node.operator.node)
+                    && binary_operator_expression.node.operator.node
+                        != lang_c::ast::BinaryOperator::Index
+                    && self
+                        .is_integer_constant_expression(&binary_operator_expression.node.lhs.node)
+                    && self
+                        .is_integer_constant_expression(&binary_operator_expression.node.rhs.node)
+            }
+            lang_c::ast::Expression::Conditional(conditional_expression) => {
+                self.is_integer_constant_expression(&conditional_expression.node.condition.node)
+                    && self.is_integer_constant_expression(
+                        &conditional_expression.node.then_expression.node,
+                    )
+                    && self.is_integer_constant_expression(
+                        &conditional_expression.node.else_expression.node,
+                    )
+            }
+            lang_c::ast::Expression::Cast(cast_expression) => {
+                self.is_integer_constant_expression(&cast_expression.node.expression.node)
+            }
+            _ => false,
+        }
+    }
+
+    fn check_banned_function(
+        &mut self,
+        call_expression: &lang_c::ast::CallExpression,
+        span: &Span,
+    ) {
+        let lang_c::ast::Expression::Identifier(identifier) = &call_expression.callee.node else {
+            return;
+        };
+        let name = &identifier.node.name;
+        if !self.rule_set.banned_functions.contains(name) {
+            return;
+        }
+
+        let message = match self.rule_set.banned_function_replacements.get(name) {
+            Some(replacement) => format!(
+                "Call to banned function '{}', use '{}' instead",
+                name, replacement
+            ),
+            None => format!("Call to banned function '{}'", name),
+        };
+        let snippet = self.get_source_code_from_span(span);
+        self.report("restrict_banned_functions", span, &message, Some(snippet));
+    }
+
+    fn check_unsafe_string_function(
+        &mut self,
+        call_expression: &lang_c::ast::CallExpression,
+        span: &Span,
+    ) {
+        let lang_c::ast::Expression::Identifier(identifier) = &call_expression.callee.node else {
+            return;
+        };
+        let name = &identifier.node.name;
+        let Some(replacement) = self.rule_set.unsafe_string_functions.get(name) else {
+            return;
+        };
+
+        let message = format!(
+            "Call to unbounded function '{}', use '{}' instead",
+            name, replacement
+        );
+        let snippet = self.get_source_code_from_span(span);
+        self.report(
+            "restrict_unsafe_string_functions",
+            span,
+            &message,
+            Some(snippet),
+        );
+    }
+
+    fn check_termination_call(
+        &mut self,
+        call_expression: &lang_c::ast::CallExpression,
+        span: &Span,
+    ) {
+        let lang_c::ast::Expression::Identifier(identifier) = &call_expression.callee.node else {
+            return;
+        };
+        let name = &identifier.node.name;
+        if !self.rule_set.termination_functions.contains(name) {
+            return;
+        }
+
+        let message = match &self.rule_set.fault_handler {
+            Some(fault_handler) => format!(
+                "Call to '{}' terminates the program, report the fault through '{}' instead",
+                name, fault_handler
+            ),
+            None => format!("Call to '{}' terminates the program", name),
+        };
+        let snippet = self.get_source_code_from_span(span);
+        self.report("restrict_termination_calls", span, &message, Some(snippet));
+    }
+
+    // Report calls to printf and scanf family functions whose literal format string
+    // consumes a different number of arguments than passed, or a conversion whose
+    // argument has an incompatible type as far as the symbol table tells
+    fn check_format_arguments(
+        &mut self,
+        call_expression: &lang_c::ast::CallExpression,
+        span: &Span,
+    ) {
+        let lang_c::ast::Expression::Identifier(identifier) = &call_expression.callee.node else {
+            return;
+        };
+        let name = &identifier.node.name;
+        let (family, position) = match (
+            self.rule_set.printf_functions.get(name),
+            self.rule_set.scanf_functions.get(name),
+        ) {
+            (Some(&position), _) => (FormatFamily::Printf, position),
+            (None, Some(&position)) => (FormatFamily::Scanf, position),
+            (None, None) => return,
+        };
+        // Formats built at run time cannot be checked
+        let Some(lang_c::ast::Expression::StringLiteral(literal)) = call_expression
+            .arguments
+            .get(position)
+            .map(|argument| &argument.node)
+        else {
+            return;
+        };
+        let Some(conversions) = conversions(&literal_text(&literal.node), family) else {
+            return;
+        };
+
+        let arguments = &call_expression.arguments[position + 1..];
+        if conversions.len() != arguments.len() {
+            let message = format!(
+                "Format string of '{}' expects {} arguments, but {} are passed",
+                name,
+                conversions.len(),
+                arguments.len()
+            );
+            let snippet = self.get_source_code_from_span(span);
+            self.report("restrict_format_mismatches", span, &message, Some(snippet));
+        }
+
+        for (index, (conversion, argument)) in conversions.iter().zip(arguments).enumerate() {
+            let Some(value_type) = self.value_type(&argument.node) else {
+                continue;
+            };
+            let expected = match (family, conversion.kind, value_type) {
+                (FormatFamily::Printf, ArgumentKind::Integer, ValueType::Scalar(scalar)) => {
+                    (scalar == ScalarType::Floating).then_some("an integer")
+                }
+                (FormatFamily::Printf, ArgumentKind::Integer, ValueType::Pointer(_)) => {
+                    Some("an integer")
+                }
+                (FormatFamily::Printf, ArgumentKind::Floating, _) => {
+                    let is_floating = value_type == ValueType::Scalar(ScalarType::Floating);
+                    (!is_floating).then_some("a floating-point value")
+                }
+                (FormatFamily::Printf, ArgumentKind::String, _) => {
+                    let is_string = matches!(
+                        value_type,
+                        ValueType::Pointer(None | Some(ScalarType::Character))
+                    );
+                    (!is_string).then_some("a string")
+                }
+                (_, _, ValueType::Scalar(_)) => Some("a pointer"),
+                (FormatFamily::Scanf, ArgumentKind::Integer, ValueType::Pointer(pointee)) => {
+                    (pointee == Some(ScalarType::Floating)).then_some("a pointer to an integer")
+                }
+                (FormatFamily::Scanf, ArgumentKind::Floating, ValueType::Pointer(pointee)) => {
+                    matches!(pointee, Some(ScalarType::Character | ScalarType::Integer))
+                        .then_some("a pointer to a floating-point value")
+                }
+                (FormatFamily::Scanf, ArgumentKind::String, ValueType::Pointer(pointee)) => {
+                    matches!(pointee, Some(ScalarType::Integer | ScalarType::Floating))
+                        .then_some("a character buffer")
+                }
+                (_, ArgumentKind::Pointer, ValueType::Pointer(_)) => None,
+            };
+            if let Some(expected) = expected {
+                let message = format!(
+                    "Conversion '{}' in the format of '{}' expects {}, but argument {} is {}",
+                    conversion.specification,
+                    name,
+                    expected,
+                    position + index + 2,
+                    value_type.description()
+                );
+                self.report("restrict_format_mismatches", &argument.span, &message, None);
+            }
+        }
+    }
+
+    // Type of a format argument, as far as the symbol table and constants tell
+    fn value_type(&self, expression: &lang_c::ast::Expression) -> Option<ValueType> {
+        match expression {
+            lang_c::ast::Expression::Constant(constant) => match constant.node {
+                lang_c::ast::Constant::Float(_) => Some(ValueType::Scalar(ScalarType::Floating)),
+                _ => Some(ValueType::Scalar(ScalarType::Integer)),
+            },
+            lang_c::ast::Expression::StringLiteral(_) => {
+                Some(ValueType::Pointer(Some(ScalarType::Character)))
+            }
+            lang_c::ast::Expression::SizeOfTy(_)
+            | lang_c::ast::Expression::SizeOfVal(_)
+            | lang_c::ast::Expression::AlignOf(_) => Some(ValueType::Scalar(ScalarType::Integer)),
+            lang_c::ast::Expression::Identifier(identifier) => {
+                match &self.symbol_table.get(&identifier.node.name)?.symbol_type {
+                    SymbolType::Variable {
+                        type_specifiers, ..
+                    } => scalar_type(&type_specifiers.iter().collect::<Vec<_>>())
+                        .map(ValueType::Scalar),
+                    SymbolType::Array {
+                        type_specifiers, ..
+                    }
+                    | SymbolType::Pointer {
+                        type_specifiers, ..
+                    } => Some(ValueType::Pointer(scalar_type(
+                        &type_specifiers.iter().collect::<Vec<_>>(),
+                    ))),
+                    SymbolType::Function { .. } => None,
+                }
+            }
+            lang_c::ast::Expression::UnaryOperator(unary_operator_expression) => {
+                let operand = &unary_operator_expression.node.operand.node;
+                match unary_operator_expression.node.operator.node {
+                    lang_c::ast::UnaryOperator::Address => match self.value_type(operand) {
+                        Some(ValueType::Scalar(scalar)) => Some(ValueType::Pointer(Some(scalar))),
+                        _ => Some(ValueType::Pointer(None)),
+                    },
+                    lang_c::ast::UnaryOperator::Plus | lang_c::ast::UnaryOperator::Minus => {
+                        match self.value_type(operand)? {
+                            ValueType::Scalar(scalar) => Some(ValueType::Scalar(scalar)),
+                            ValueType::Pointer(_) => None,
+                        }
+                    }
+                    _ => None,
+                }
+            }
+            lang_c::ast::Expression::Cast(cast_expression) => {
+                let type_name = &cast_expression.node.type_name.node;
+                let type_specifiers: Vec<_> = type_name
+                    .specifiers
+                    .iter()
+                    .filter_map(|specifier| match &specifier.node {
+                        lang_c::ast::SpecifierQualifier::TypeSpecifier(type_specifier) => {
+                            Some(&type_specifier.node)
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                if type_specifiers.is_empty() {
+                    return None;
+                }
+                let scalar = scalar_type(&type_specifiers);
+                match type_name
+                    .declarator
+                    .as_ref()
+                    .map(|declarator| &declarator.node.derived[..])
+                {
+                    None | Some([]) => scalar.map(ValueType::Scalar),
+                    Some(
+                        [lang_c::span::Node {
+                            node: lang_c::ast::DerivedDeclarator::Pointer(_),
+                            ..
+                        }],
+                    ) => Some(ValueType::Pointer(scalar)),
+                    Some(_) => Some(ValueType::Pointer(None)),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    // Report function pointer variables and remember them and function pointer
+    // typedefs, so calls through them and uses of the typedefs are found too
+    fn check_function_pointer_declaration(&mut self, declaration: &lang_c::ast::Declaration) {
+        let is_typedef = declaration.specifiers.iter().any(|specifier| {
+            matches!(
+                &specifier.node,
+                lang_c::ast::DeclarationSpecifier::StorageClass(storage_class)
+                    if matches!(storage_class.node, lang_c::ast::StorageClassSpecifier::Typedef)
+            )
+        });
+        let type_specifiers =
+            declaration
+                .specifiers
+                .iter()
+                .filter_map(|specifier| match &specifier.node {
+                    lang_c::ast::DeclarationSpecifier::TypeSpecifier(type_specifier) => {
+                        Some(&type_specifier.node)
+                    }
+                    _ => None,
+                });
+        let has_function_pointer_type = self.is_function_pointer_type(type_specifiers);
+
+        for init_declarator in &declaration.declarators {
+            let declarator = &init_declarator.node.declarator;
+            if !self.declares_function_pointer(&declarator.node, has_function_pointer_type) {
+                continue;
+            }
+            let Some(name) = declarator_name(&declarator.node) else {
+                continue;
+            };
+            if is_typedef {
+                self.function_pointer_types.insert(name.to_string());
+                continue;
+            }
+            self.function_pointers.insert(name.to_string());
+            self.report(
+                "restrict_function_pointers",
+                &declarator.span,
+                &format!("Function pointer '{}' declared", name),
+                None,
+            );
+        }
+    }
+
+    fn check_function_pointer_parameter(
+        &mut self,
+        parameter: &lang_c::ast::ParameterDeclaration,
+        span: &Span,
+    ) {
+        let type_specifiers = parameter
+            .specifiers
+            .iter()
+            .filter_map(|specifier| match &specifier.node {
+                lang_c::ast::DeclarationSpecifier::TypeSpecifier(type_specifier) => {
+                    Some(&type_specifier.node)
+                }
+                _ => None,
+            });
+        let has_function_pointer_type = self.is_function_pointer_type(type_specifiers);
+        let declares_function_pointer = match &parameter.declarator {
+            Some(declarator) => {
+                self.declares_function_pointer(&declarator.node, has_function_pointer_type)
+            }
+            None => has_function_pointer_type,
+        };
+        if !declares_function_pointer {
+            return;
+        }
+
+        let name = parameter
+            .declarator
+            .as_ref()
+            .and_then(|declarator| declarator_name(&declarator.node));
+        let message = match name {
+            Some(name) => {
+                self.function_pointers.insert(name.to_string());
+                format!("Function pointer parameter '{}' declared", name)
+            }
+            None => "Function pointer parameter declared".to_string(),
+        };
+        self.report("restrict_function_pointers", span, &message, None);
+    }
+
+    fn check_function_pointer_member(&mut self, struct_field: &lang_c::ast::StructField) {
+        let type_specifiers =
+            struct_field
+                .specifiers
+                .iter()
+                .filter_map(|specifier| match &specifier.node {
+                    lang_c::ast::SpecifierQualifier::TypeSpecifier(type_specifier) => {
+                        Some(&type_specifier.node)
+                    }
+                    _ => None,
+                });
+        let has_function_pointer_type = self.is_function_pointer_type(type_specifiers);
+
+        for struct_declarator in &struct_field.declarators {
+            let Some(declarator) = &struct_declarator.node.declarator else {
+                continue;
+            };
+            if !self.declares_function_pointer(&declarator.node, has_function_pointer_type) {
+                continue;
+            }
+            let name = declarator_name(&declarator.node).unwrap_or_default();
+            self.report(
+                "restrict_function_pointers",
+                &declarator.span,
+                &format!("Function pointer member '{}' declared", name),
+                None,
+            );
+        }
+    }
+
+    // Calls through anything but the name of a function: pointer variables and
+    // parameters, dereferences, struct members and casts
+    fn check_function_pointer_call(
+        &mut self,
+        call_expression: &lang_c::ast::CallExpression,
+        span: &Span,
+    ) {
+        let through_pointer = match &call_expression.callee.node {
+            lang_c::ast::Expression::Identifier(identifier) => {
+                self.function_pointers.contains(&identifier.node.name)
+            }
+            _ => true,
+        };
+        if through_pointer {
+            let snippet = self.get_source_code_from_span(span);
+            self.report(
+                "restrict_function_pointers",
+                span,
+                "Call through a function pointer",
+                Some(snippet),
+            );
+        }
+    }
+
+    // Whether the type specifiers name a function pointer typedef
+    fn is_function_pointer_type<'a>(
+        &self,
+        mut type_specifiers: impl Iterator<Item = &'a lang_c::ast::TypeSpecifier>,
+    ) -> bool {
+        type_specifiers.any(|type_specifier| {
+            matches!(
+                type_specifier,
+                lang_c::ast::TypeSpecifier::TypedefName(name)
+                    if self.function_pointer_types.contains(&name.node.name)
+            )
+        })
+    }
+
+    // Whether a declarator declares a function pointer, or an array of them. Functions
+    // are left out, their parameters are checked on their own.
+    fn declares_function_pointer(
+        &self,
+        declarator: &lang_c::ast::Declarator,
+        has_function_pointer_type: bool,
+    ) -> bool {
+        let derivations = derivations(declarator);
+        if matches!(
+            derivations.first(),
+            Some(
+                lang_c::ast::DerivedDeclarator::Function(_)
+                    | lang_c::ast::DerivedDeclarator::KRFunction(_)
+            )
+        ) {
+            return false;
+        }
+        has_function_pointer_type
+            || derivations.windows(2).any(|pair| {
+                matches!(
+                    pair,
+                    [
+                        lang_c::ast::DerivedDeclarator::Pointer(_),
+                        lang_c::ast::DerivedDeclarator::Function(_)
+                            | lang_c::ast::DerivedDeclarator::KRFunction(_)
+                    ]
+                )
+            })
+    }
+
+    fn add_variadic_functions(&mut self, declaration: &lang_c::ast::Declaration) {
+        for init_declarator in &declaration.declarators {
+            let declarator = &init_declarator.node.declarator.node;
+            if is_variadic_declarator(declarator) {
+                if let Some(name) = declarator_name(declarator) {
+                    self.variadic_functions.insert(name.to_string());
+                }
+            }
+        }
+    }
+
+    fn is_allowed_variadic_function(&self, name: &str) -> bool {
+        self.rule_set
+            .allowed_variadic_functions
+            .iter()
+            .any(|function| function == name)
+    }
+
+    fn check_variadic_definition(
+        &mut self,
+        function_definition: &lang_c::ast::FunctionDefinition,
+        span: &Span,
+    ) {
+        let declarator = &function_definition.declarator.node;
+        self.in_allowed_variadic_function =
+            declarator_name(declarator).is_some_and(|name| self.is_allowed_variadic_function(name));
+        if is_variadic_declarator(declarator) && !self.in_allowed_variadic_function {
+            self.report(
+                "restrict_variadic",
+                span,
+                "Variadic function definition found",
+                None,
+            );
+        }
+    }
+
+    fn check_variadic_call(&mut self, call_expression: &lang_c::ast::CallExpression, span: &Span) {
+        let lang_c::ast::Expression::Identifier(identifier) = &call_expression.callee.node else {
+            return;
+        };
+        let name = identifier.node.name.as_str();
+
+        let message = match name {
+            "va_start" | "__builtin_va_start" if self.in_allowed_variadic_function => return,
+            "va_start" | "__builtin_va_start" => "'va_start' call found".to_string(),
+            _ if self.variadic_functions.contains(name)
+                && !self.is_allowed_variadic_function(name)
+                && !self
+                    .rule_set
+                    .variadic_library_functions
+                    .iter()
+                    .any(|function| function == name) =>
+            {
+                format!("Call to variadic function '{}'", name)
+            }
+            _ => return,
+        };
+        let snippet = self.get_source_code_from_span(span);
+        self.report("restrict_variadic", span, &message, Some(snippet));
+    }
+
+    // Report conditional expressions nested deeper than allowed in other conditional
+    // expressions, once per chain, and those used as an operand of && or ||
+    fn check_nested_conditional(
+        &mut self,
+        expression: &lang_c::ast::Expression,
+        span: &Span,
+        is_conditional: bool,
+    ) {
+        if is_conditional && self.conditional_depth == self.rule_set.max_conditional_nesting + 1 {
+            self.report(
+                "restrict_nested_conditionals",
+                span,
+                &format!(
+                    "Conditional expressions nested more than {} deep",
+                    self.rule_set.max_conditional_nesting
+                ),
+                None,
+            );
+        }
+
+        let lang_c::ast::Expression::BinaryOperator(binary_operator_expression) = expression else {
+            return;
+        };
+        let operator = match binary_operator_expression.node.operator.node {
+            lang_c::ast::BinaryOperator::LogicalAnd => "&&",
+            lang_c::ast::BinaryOperator::LogicalOr => "||",
+            _ => return,
+        };
+        for operand in [
+            &binary_operator_expression.node.lhs,
+            &binary_operator_expression.node.rhs,
+        ] {
+            if let lang_c::ast::Expression::Conditional(_) = operand.node {
+                self.report(
+                    "restrict_nested_conditionals",
+                    &operand.span,
+                    &format!(
+                        "Conditional expression used as an operand of '{}'",
+                        operator
+                    ),
+                    None,
+                );
+            }
+        }
+    }
+
+    // Report union definitions and the variables and members declared with a union
+    // type, unless the union's tag or a typedef name of it is allowed. Unions of
+    // system headers are left alone.
+    fn check_union(
+        &mut self,
+        type_specifiers: &[&lang_c::span::Node<lang_c::ast::TypeSpecifier>],
+        declarators: &[&lang_c::span::Node<lang_c::ast::Declarator>],
+        is_typedef: bool,
+        kind: &str,
+    ) {
+        let names: Vec<&str> = declarators
+            .iter()
+            .filter_map(|declarator| declarator_name(&declarator.node))
+            .collect();
+
+        for type_specifier in type_specifiers {
+            if self.line_index.is_system_header(type_specifier.span.start) {
+                return;
+            }
+            let (name, is_definition, is_allowed) = match &type_specifier.node {
+                lang_c::ast::TypeSpecifier::Struct(struct_type)
+                    if struct_type.node.kind.node == lang_c::ast::StructKind::Union =>
+                {
+                    let tag = struct_type
+                        .node
+                        .identifier
+                        .as_ref()
+                        .map(|identifier| identifier.node.name.as_str());
+                    (tag, struct_type.node.declarations.is_some(), false)
+                }
+                lang_c::ast::TypeSpecifier::TypedefName(identifier) => {
+                    match self.union_typedefs.get(&identifier.node.name) {
+                        Some(&is_allowed) => {
+                            (Some(identifier.node.name.as_str()), false, is_allowed)
+                        }
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            };
+
+            let typedef_names: &[&str] = if is_typedef { &names } else { &[] };
+            let is_allowed = is_allowed
+                || name.iter().chain(typedef_names).any(|candidate| {
+                    self.rule_set
+                        .allowed_unions
+                        .iter()
+                        .any(|allowed| allowed == *candidate)
+                });
+            for typedef_name in typedef_names {
+                self.union_typedefs
+                    .insert(typedef_name.to_string(), is_allowed);
+            }
+            if is_allowed {
+                continue;
+            }
+
+            if is_definition {
+                let message = match name {
+                    Some(name) => format!("Union '{}' defined", name),
+                    None => "Anonymous union defined".to_string(),
+                };
+                self.report("restrict_unions", &type_specifier.span, &message, None);
+            }
+            if is_typedef {
+                continue;
+            }
+            for declarator in declarators {
+                let is_function = declarator.node.derived.iter().any(|derived| {
+                    matches!(
+                        derived.node,
+                        lang_c::ast::DerivedDeclarator::Function(_)
+                            | lang_c::ast::DerivedDeclarator::KRFunction(_)
+                    )
+                });
+                if is_function {
+                    continue;
+                }
+                let Some(object) = declarator_name(&declarator.node) else {
+                    continue;
+                };
+                let message = match name {
+                    Some(name) => {
+                        format!("{} '{}' declared with union type '{}'", kind, object, name)
+                    }
+                    None => format!("{} '{}' declared with a union type", kind, object),
+                };
+                self.report("restrict_unions", &declarator.span, &message, None);
+            }
+        }
+    }
+
+    // Report bit-fields declared with another type than the allowed fixed-width types,
+    // as whether a plain int bit-field is signed and how the others are laid out is
+    // up to the compiler, and signed bit-fields of a single bit, which hold 0 and -1
+    fn check_bit_fields(&mut self, struct_field: &lang_c::ast::StructField) {
+        let type_specifiers: Vec<&lang_c::ast::TypeSpecifier> = struct_field
+            .specifiers
+            .iter()
+            .filter_map(|specifier| match &specifier.node {
+                lang_c::ast::SpecifierQualifier::TypeSpecifier(type_specifier) => {
+                    Some(&type_specifier.node)
+                }
+                _ => None,
+            })
+            .collect();
+        let (is_allowed, is_signed) = match type_specifiers[..] {
+            [lang_c::ast::TypeSpecifier::TypedefName(identifier)] => (
+                self.rule_set
+                    .bit_field_types
+                    .contains(&identifier.node.name),
+                identifier.node.name.starts_with("int"),
+            ),
+            _ => (false, false),
+        };
+
+        for struct_declarator in &struct_field.declarators {
+            let Some(bit_width) = &struct_declarator.node.bit_width else {
+                continue;
+            };
+            let name = struct_declarator
+                .node
+                .declarator
+                .as_ref()
+                .and_then(|declarator| declarator_name(&declarator.node))
+                .unwrap_or("<unnamed>");
+            let message = if !is_allowed {
+                let type_text: Vec<String> = type_specifiers
+                    .iter()
+                    .map(|type_specifier| type_specifier_text(type_specifier))
+                    .collect();
+                format!(
+                    "Bit-field '{}' declared with type '{}', use one of {}",
+                    name,
+                    type_text.join(" "),
+                    self.rule_set.bit_field_types.join(", ")
+                )
+            } else if is_signed && constant_expression_value(&bit_width.node) == Some(1) {
+                format!(
+                    "Signed bit-field '{}' is 1 bit wide and can only hold 0 and -1",
+                    name
+                )
+            } else {
+                continue;
+            };
+            self.report(
+                "restrict_bit_field_types",
+                &struct_declarator.span,
+                &message,
+                None,
+            );
+        }
+    }
+
+    // Switches over state machine types must handle unexpected states in their
+    // default case by calling one of the configured fault handlers, not just break
+    fn check_state_switch(&mut self, switch_statement: &lang_c::ast::SwitchStatement, span: &Span) {
+        let lang_c::ast::Expression::Identifier(identifier) = &switch_statement.expression.node
+        else {
+            return;
+        };
+        let Some(Symbol {
+            symbol_type: SymbolType::Variable { type_specifier, .. },
+            ..
+        }) = self.symbol_table.get(&identifier.node.name)
+        else {
+            return;
+        };
+        let Some(state_type) = type_name(type_specifier) else {
+            return;
+        };
+        if !self
+            .rule_set
+            .state_types
+            .iter()
+            .any(|name| name == state_type)
+        {
+            return;
+        }
+
+        let mut finder = CallFinder {
+            names: &self.rule_set.fault_handlers,
+            found: false,
+        };
+        for statement in default_case_statements(&switch_statement.statement.node) {
+            finder.visit_statement(&statement.node, &statement.span);
+        }
+        if finder.found {
+            return;
+        }
+
+        let message = format!(
+            "Switch over state type '{}' does not call a fault handler ({}) in its default case",
+            state_type,
+            self.rule_set.fault_handlers.join(", ")
+        );
+        self.report("require_state_fault_handling", span, &message, None);
+    }
+
+    // Report switches without a default label. With strict_switch_default the default
+    // also has to be the last label and assert or call a fault handler.
+    fn check_switch_default(
+        &mut self,
+        switch_statement: &lang_c::ast::SwitchStatement,
+        span: &Span,
+    ) {
+        let body = &switch_statement.statement.node;
+        let labels = switch_labels(body);
+        if !labels
+            .iter()
+            .any(|label| matches!(label, lang_c::ast::Label::Default))
+        {
+            self.report(
+                "require_switch_default",
+                span,
+                "Switch has no default label",
+                None,
+            );
+            return;
+        }
+        if !self.rule_set.strict_switch_default {
+            return;
+        }
+
+        if !matches!(labels.last(), Some(lang_c::ast::Label::Default)) {
+            self.report(
+                "require_switch_default",
+                span,
+                "Default label is not the last label of the switch",
+                None,
+            );
+        }
+        if !self.handles_unexpected_case(&default_case_statements(body)) {
+            let message = format!(
+                "Default case neither asserts nor calls a fault handler ({})",
+                self.rule_set.fault_handlers.join(", ")
+            );
+            self.report("require_switch_default", span, &message, None);
+        }
+    }
+
+    // Report case labels of a switch over an enum variable that are not enumerators of
+    // the enum, and without a default label, the enumerators no case handles
+    fn check_enum_switch(&mut self, switch_statement: &lang_c::ast::SwitchStatement, span: &Span) {
+        let lang_c::ast::Expression::Identifier(identifier) = &switch_statement.expression.node
+        else {
+            return;
+        };
+        let Some(Symbol {
+            symbol_type: SymbolType::Variable { type_specifier, .. },
+            ..
+        }) = self.symbol_table.get(&identifier.node.name)
+        else {
+            return;
+        };
+        let enum_name = type_name(type_specifier)
+            .unwrap_or("<anonymous>")
+            .to_string();
+        let enumerators = match type_specifier {
+            lang_c::ast::TypeSpecifier::Enum(enum_type)
+                if !enum_type.node.enumerators.is_empty() =>
+            {
+                enum_type
+                    .node
+                    .enumerators
+                    .iter()
+                    .map(|enumerator| enumerator.node.identifier.node.name.clone())
+                    .collect()
+            }
+            _ => match self.enum_types.get(&enum_name) {
+                Some(enumerators) => enumerators.clone(),
+                None => return,
+            },
+        };
+
+        let mut handled = HashSet::new();
+        let mut has_default = false;
+        for label in switch_labels(&switch_statement.statement.node) {
+            let expression = match label {
+                lang_c::ast::Label::Case(expression) => expression,
+                lang_c::ast::Label::CaseRange(case_range) => &case_range.node.low,
+                lang_c::ast::Label::Default => {
+                    has_default = true;
+                    continue;
+                }
+                lang_c::ast::Label::Identifier(_) => continue,
+            };
+            match &expression.node {
+                lang_c::ast::Expression::Identifier(identifier)
+                    if enumerators.contains(&identifier.node.name) =>
+                {
+                    handled.insert(identifier.node.name.as_str());
+                }
+                _ => self.report(
+                    "require_exhaustive_enum_switch",
+                    &expression.span,
+                    &format!("Case label is not an enumerator of enum '{}'", enum_name),
+                    None,
+                ),
+            }
+        }
+        if has_default {
+            return;
+        }
+
+        let missing: Vec<&str> = enumerators
+            .iter()
+            .map(String::as_str)
+            .filter(|enumerator| !handled.contains(enumerator))
+            .collect();
+        if !missing.is_empty() {
+            self.report(
+                "require_exhaustive_enum_switch",
+                span,
+                &format!(
+                    "Switch over enum '{}' does not handle {}",
+                    enum_name,
+                    missing.join(", ")
+                ),
+                None,
+            );
+        }
+    }
+
+    // Record the enumerators of the enums a declaration defines, by tag and by the
+    // typedef names it declares for them
+    fn add_enum_types(&mut self, declaration: &lang_c::ast::Declaration) {
+        let Some(enum_type) = declaration
+            .specifiers
+            .iter()
+            .find_map(|specifier| match &specifier.node {
+                lang_c::ast::DeclarationSpecifier::TypeSpecifier(type_specifier) => {
+                    match &type_specifier.node {
+                        lang_c::ast::TypeSpecifier::Enum(enum_type) => Some(&enum_type.node),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            })
+        else {
+            return;
+        };
+
+        let tag = enum_type
+            .identifier
+            .as_ref()
+            .map(|identifier| identifier.node.name.clone());
+        let enumerators: Vec<String> = if enum_type.enumerators.is_empty() {
+            // A reference to an enum defined earlier, e.g. `typedef enum mode mode_t;`
+            match tag.as_ref().and_then(|tag| self.enum_types.get(tag)) {
+                Some(enumerators) => enumerators.clone(),
+                None => return,
+            }
+        } else {
+            enum_type
+                .enumerators
+                .iter()
+                .map(|enumerator| enumerator.node.identifier.node.name.clone())
+                .collect()
+        };
+        if let Some(tag) = tag {
+            self.enum_types.insert(tag, enumerators.clone());
+        }
+
+        let is_typedef = declaration.specifiers.iter().any(|specifier| {
+            matches!(
+                &specifier.node,
+                lang_c::ast::DeclarationSpecifier::StorageClass(storage_class)
+                    if matches!(storage_class.node, lang_c::ast::StorageClassSpecifier::Typedef)
+            )
+        });
+        if is_typedef {
+            for init_declarator in &declaration.declarators {
+                let declarator = &init_declarator.node.declarator.node;
+                if let (true, Some(name)) =
+                    (declarator.derived.is_empty(), declarator_name(declarator))
+                {
+                    self.enum_types
+                        .insert(name.to_string(), enumerators.clone());
+                }
+            }
+        }
+    }
+
+    // Whether statements call a fault handler or assert, looking for assertion macros in
+    // the original file as they are gone from the preprocessed source
+    fn handles_unexpected_case(
+        &self,
+        statements: &[&lang_c::span::Node<lang_c::ast::Statement>],
+    ) -> bool {
+        let mut names = self.rule_set.fault_handlers.clone();
+        names.push("assert".to_string());
+        names.extend(self.rule_set.assertion_macros.iter().cloned());
+        let mut finder = CallFinder {
+            names: &names,
+            found: false,
+        };
+        for statement in statements {
+            finder.visit_statement(&statement.node, &statement.span);
+        }
+        if finder.found {
+            return true;
+        }
+
+        let (Some(first), Some(last)) = (statements.first(), statements.last()) else {
+            return false;
+        };
+        let location = self.line_index.location(first.span.start);
+        let start_line = location.line;
+        let Some(original_source) = self.original_source(location.file) else {
+            return false;
+        };
+        let lines: Vec<&str> = original_source
+            .lines()
+            .skip(start_line - 1)
+            .take(self.get_line_number(last.span.end) + 1 - start_line)
+            .collect();
+        count_assertion_calls(&lines.join("\n"), &self.rule_set.assertion_macros) > 0
+    }
+
+    fn check_locale_api(&mut self, call_expression: &lang_c::ast::CallExpression, span: &Span) {
+        let lang_c::ast::Expression::Identifier(identifier) = &call_expression.callee.node else {
+            return;
+        };
+        let name = identifier.node.name.as_str();
+
+        let message = if LOCALE_FUNCTIONS.contains(&name) {
+            format!("Call to locale-dependent function '{}'", name)
+        } else if WIDE_CHARACTER_FUNCTIONS.contains(&name) {
+            format!("Call to wide-character function '{}'", name)
+        } else if CTYPE_FUNCTIONS.contains(&name)
+            && call_expression
+                .arguments
+                .first()
+                .is_some_and(|argument| self.is_plain_char(&argument.node))
+        {
+            format!("'{}' called with a plain char argument", name)
+        } else {
+            return;
+        };
+
+        let snippet = self.get_source_code_from_span(span);
+        self.report("restrict_locale_apis", span, &message, Some(snippet));
+    }
+
+    // The size passed to a bounded buffer function must be derived from sizeof of the
+    // destination array, so it follows the array when its declaration changes
+    fn check_size_argument(&mut self, call_expression: &lang_c::ast::CallExpression, span: &Span) {
+        let lang_c::ast::Expression::Identifier(identifier) = &call_expression.callee.node else {
+            return;
+        };
+        let function = identifier.node.name.as_str();
+        let Some(&(_, buffers, size_index)) = BOUNDED_BUFFER_FUNCTIONS
+            .iter()
+            .find(|(name, _, _)| *name == function)
+        else {
+            return;
+        };
+        let (Some(destination), Some(size)) = (
+            call_expression.arguments.get(buffers[0]),
+            call_expression.arguments.get(size_index),
+        ) else {
+            return;
+        };
+
+        // Only arrays declared in view have a size the argument can be checked against
+        let lang_c::ast::Expression::Identifier(destination) = &destination.node else {
+            return;
+        };
+        let destination = destination.node.name.as_str();
+        let Some(Symbol {
+            symbol_type: SymbolType::Array { .. },
+            ..
+        }) = self.symbol_table.get(destination)
+        else {
+            return;
+        };
+        if mentions_size_of(&size.node, destination) {
+            return;
+        }
+        let destination_size = self.array_bytes(destination);
+
+        let message = if let Some(value) = constant_expression_value(&size.node) {
+            match destination_size {
+                Some(destination_size) if value > destination_size => format!(
+                    "Size argument {} to '{}' exceeds the {} bytes of '{}', use sizeof({})",
+                    value, function, destination_size, destination, destination
+                ),
+                _ => format!(
+                    "Constant size argument {} to '{}' is not derived from '{}', use sizeof({})",
+                    value, function, destination, destination
+                ),
+            }
+        } else if let Some(other) = size_of_operand(&size.node) {
+            format!(
+                "Size argument to '{}' is the size of '{}', not of the destination '{}'",
+                function, other, destination
+            )
+        } else {
+            return;
+        };
+
+        let snippet = self.get_source_code_from_span(span);
+        self.report(
+            "require_sizeof_size_arguments",
+            span,
+            &message,
+            Some(snippet),
+        );
+    }
+
+    // A buffer passed as `&array[k]` or `array + k` only has the elements after k left,
+    // so a size covering the whole array overruns it, e.g. memcpy(&buf[4], src, sizeof(buf))
+    fn check_offset_buffer(&mut self, call_expression: &lang_c::ast::CallExpression) {
+        let lang_c::ast::Expression::Identifier(identifier) = &call_expression.callee.node else {
+            return;
+        };
+        let function = identifier.node.name.as_str();
+        let Some(&(_, buffers, size_index)) = BOUNDED_BUFFER_FUNCTIONS
+            .iter()
+            .find(|(name, _, _)| *name == function)
+        else {
+            return;
+        };
+        let Some(size) = call_expression
+            .arguments
+            .get(size_index)
+            .and_then(|size| self.size_value(&size.node))
+        else {
+            return;
+        };
+
+        for &buffer_index in buffers {
+            let Some(buffer) = call_expression.arguments.get(buffer_index) else {
+                continue;
+            };
+            let Some((array, offset)) = offset_into_array(&buffer.node) else {
+                continue;
+            };
+            let (Some(array_bytes), Some(element_bytes)) =
+                (self.array_bytes(array), self.element_bytes(array))
+            else {
+                continue;
+            };
+            let remaining = array_bytes.saturating_sub(offset.saturating_mul(element_bytes));
+            if size <= remaining {
+                continue;
+            }
+
+            let message = format!(
+                "Size {} passed to '{}' exceeds the {} bytes of '{}' left after element {}",
+                size, function, remaining, array, offset
+            );
+            // Reported at the buffer, as both buffers of a call can overrun
+            let snippet = self.get_source_code_from_span(&buffer.span);
+            self.report(
+                "restrict_offset_buffer_overrun",
+                &buffer.span,
+                &message,
+                Some(snippet),
+            );
+        }
+    }
+
+    // Size in bytes of an array declared in view, None if it is not one or the size is unknown
+    fn array_bytes(&self, name: &str) -> Option<u64> {
+        let Some(Symbol {
+            symbol_type: SymbolType::Array { length, .. },
+            ..
+        }) = self.symbol_table.get(name)
+        else {
+            return None;
+        };
+        Some((*length)? * self.element_bytes(name)?)
+    }
+
+    // Size in bytes of one element of an array declared in view
+    fn element_bytes(&self, name: &str) -> Option<u64> {
+        let Some(Symbol {
+            symbol_type: SymbolType::Array { element_type, .. },
+            ..
+        }) = self.symbol_table.get(name)
+        else {
+            return None;
+        };
+        type_size(element_type)
+    }
+
+    // Estimate the stack a function needs for its locals, counting those of every block
+    // as if all were live at once, and report it when over the budget
+    fn check_stack_frame_size(&mut self, function_definition: &lang_c::ast::FunctionDefinition) {
+        let mut collector = DeclarationCollector::default();
+        collector.visit_statement(
+            &function_definition.statement.node,
+            &function_definition.statement.span,
+        );
+
+        let mut frame_bytes = 0;
+        let mut largest: Option<(&str, u64)> = None;
+        for declaration in collector.declarations {
+            // Static locals live in the data segment, extern ones elsewhere
+            if is_static(&declaration.specifiers) || is_extern_or_typedef(&declaration.specifiers) {
+                continue;
+            }
+            let base = self.type_bytes(&type_specifiers(&declaration.specifiers));
+            for init_declarator in &declaration.declarators {
+                let declarator = &init_declarator.node.declarator.node;
+                let Some(bytes) = self.declarator_bytes(base, declarator) else {
+                    continue;
+                };
+                frame_bytes += bytes;
+                if largest.map_or(true, |(_, largest_bytes)| bytes > largest_bytes) {
+                    largest = declarator_name(declarator).map(|name| (name, bytes));
+                }
+            }
+        }
+
+        let max_stack_frame_bytes = self.rule_set.max_stack_frame_bytes;
+        if frame_bytes <= max_stack_frame_bytes {
+            return;
+        }
+        let name = declarator_name(&function_definition.declarator.node).unwrap_or_default();
+        let mut message = format!(
+            "Locals of '{}' need about {} bytes of stack, more than {}",
+            name, frame_bytes, max_stack_frame_bytes
+        );
+        if let Some((largest, bytes)) = largest {
+            message.push_str(&format!(", the largest is '{}' ({} bytes)", largest, bytes));
+        }
+        self.report(
+            "restrict_stack_frame_size",
+            &function_definition.declarator.span,
+            &message,
+            None,
+        );
+    }
+
+    // Remember the sizes of struct and union definitions and of typedefs, so locals
+    // declared with them can be measured
+    fn add_type_sizes(&mut self, declaration: &lang_c::ast::Declaration) {
+        let type_specifiers = type_specifiers(&declaration.specifiers);
+        for type_specifier in &type_specifiers {
+            let lang_c::ast::TypeSpecifier::Struct(struct_type) = type_specifier else {
+                continue;
+            };
+            if struct_type.node.declarations.is_none() {
+                continue;
+            }
+            let (Some(tag), Some(bytes)) = (
+                struct_tag(&struct_type.node),
+                self.struct_bytes(&struct_type.node),
+            ) else {
+                continue;
+            };
+            self.type_sizes.insert(tag, bytes);
+        }
+
+        let is_typedef = declaration.specifiers.iter().any(|specifier| {
+            matches!(
+                &specifier.node,
+                lang_c::ast::DeclarationSpecifier::StorageClass(storage_class)
+                    if matches!(storage_class.node, lang_c::ast::StorageClassSpecifier::Typedef)
+            )
+        });
+        if !is_typedef {
+            return;
+        }
+        let base = self.type_bytes(&type_specifiers);
+        for init_declarator in &declaration.declarators {
+            let declarator = &init_declarator.node.declarator.node;
+            if let (Some(name), Some(bytes)) = (
+                declarator_name(declarator),
+                self.declarator_bytes(base, declarator),
+            ) {
+                self.type_sizes.insert(name.to_string(), bytes);
+            }
+        }
+    }
+
+    // Size in bytes of the type named by declaration specifiers, None when unknown
+    fn type_bytes(&self, type_specifiers: &[&lang_c::ast::TypeSpecifier]) -> Option<u64> {
+        for type_specifier in type_specifiers {
+            match type_specifier {
+                lang_c::ast::TypeSpecifier::Struct(struct_type) => {
+                    return self.struct_bytes(&struct_type.node)
+                }
+                lang_c::ast::TypeSpecifier::Enum(_) => return Some(4),
+                lang_c::ast::TypeSpecifier::TypedefName(name) => {
+                    return self.type_sizes.get(&name.node.name).copied()
+                }
+                _ => {}
+            }
+        }
+
+        let has = |wanted: fn(&lang_c::ast::TypeSpecifier) -> bool| {
+            type_specifiers
+                .iter()
+                .any(|type_specifier| wanted(type_specifier))
+        };
+        if has(|type_specifier| matches!(type_specifier, lang_c::ast::TypeSpecifier::Short)) {
+            return Some(2);
+        }
+        if has(|type_specifier| matches!(type_specifier, lang_c::ast::TypeSpecifier::Char)) {
+            return Some(1);
+        }
+        if has(|type_specifier| matches!(type_specifier, lang_c::ast::TypeSpecifier::Long)) {
+            let is_long_double =
+                has(|type_specifier| matches!(type_specifier, lang_c::ast::TypeSpecifier::Double));
+            return Some(if is_long_double { 16 } else { POINTER_BYTES });
+        }
+        type_specifiers
+            .iter()
+            .find_map(|type_specifier| type_size(type_specifier))
+            .or_else(|| {
+                // `unsigned` and `signed` on their own are int
+                has(|type_specifier| {
+                    matches!(
+                        type_specifier,
+                        lang_c::ast::TypeSpecifier::Signed | lang_c::ast::TypeSpecifier::Unsigned
+                    )
+                })
+                .then_some(4)
+            })
+    }
+
+    // Size of a struct or union, as the sum or the largest of its members without
+    // padding. Without a member list the size of the tag defined earlier is used.
+    fn struct_bytes(&self, struct_type: &lang_c::ast::StructType) -> Option<u64> {
+        let Some(declarations) = &struct_type.declarations else {
+            return self.type_sizes.get(&struct_tag(struct_type)?).copied();
+        };
+
+        let mut member_bytes = Vec::new();
+        for declaration in declarations {
+            let lang_c::ast::StructDeclaration::Field(field) = &declaration.node else {
+                continue;
+            };
+            let type_specifiers: Vec<&lang_c::ast::TypeSpecifier> = field
+                .node
+                .specifiers
+                .iter()
+                .filter_map(|specifier| match &specifier.node {
+                    lang_c::ast::SpecifierQualifier::TypeSpecifier(type_specifier) => {
+                        Some(&type_specifier.node)
+                    }
+                    _ => None,
+                })
+                .collect();
+            let base = self.type_bytes(&type_specifiers);
+            // An anonymous struct or union member has no declarators
+            if field.node.declarators.is_empty() {
+                member_bytes.push(base?);
+            }
+            for struct_declarator in &field.node.declarators {
+                if let Some(declarator) = &struct_declarator.node.declarator {
+                    member_bytes.push(self.declarator_bytes(base, &declarator.node)?);
+                }
+            }
+        }
+
+        match struct_type.kind.node {
+            lang_c::ast::StructKind::Struct => Some(member_bytes.iter().sum()),
+            lang_c::ast::StructKind::Union => Some(member_bytes.into_iter().max().unwrap_or(0)),
+        }
+    }
+
+    // Size of what a declarator declares given the size of its base type: arrays
+    // multiply it, pointers replace it and functions take no storage
+    fn declarator_bytes(
+        &self,
+        base: Option<u64>,
+        declarator: &lang_c::ast::Declarator,
+    ) -> Option<u64> {
+        let mut count = 1;
+        for derived in derivations(declarator) {
+            match derived {
+                lang_c::ast::DerivedDeclarator::Array(array_declarator) => {
+                    count *= array_length(&array_declarator.node)?;
+                }
+                lang_c::ast::DerivedDeclarator::Pointer(_) => return Some(count * POINTER_BYTES),
+                _ => return Some(0),
+            }
+        }
+        Some(count * base?)
+    }
+
+    // Value of a size expression, evaluating sizeof of arrays declared in view
+    fn size_value(&self, expression: &lang_c::ast::Expression) -> Option<u64> {
+        match expression {
+            lang_c::ast::Expression::SizeOfVal(size_of) => {
+                let lang_c::ast::Expression::Identifier(identifier) = &size_of.node.0.node else {
+                    return None;
+                };
+                self.array_bytes(&identifier.node.name)
+            }
+            lang_c::ast::Expression::BinaryOperator(binary_operator_expression) => fold_binary(
+                &binary_operator_expression.node.operator.node,
+                self.size_value(&binary_operator_expression.node.lhs.node)?,
+                self.size_value(&binary_operator_expression.node.rhs.node)?,
+            ),
+            _ => integer_constant_value(expression),
+        }
+    }
+
+    // Whether an expression is a variable declared as plain char, whose signedness is
+    // implementation-defined, rather than explicitly signed or unsigned char
+    fn is_plain_char(&self, expression: &lang_c::ast::Expression) -> bool {
+        let lang_c::ast::Expression::Identifier(identifier) = expression else {
+            return false;
+        };
+        let Some(Symbol {
+            symbol_type: SymbolType::Variable {
+                type_specifiers, ..
+            },
+            ..
+        }) = self.symbol_table.get(&identifier.node.name)
+        else {
+            return false;
+        };
+        type_specifiers
+            .iter()
+            .any(|specifier| matches!(specifier, lang_c::ast::TypeSpecifier::Char))
+            && !type_specifiers.iter().any(|specifier| {
+                matches!(
+                    specifier,
+                    lang_c::ast::TypeSpecifier::Signed | lang_c::ast::TypeSpecifier::Unsigned
+                )
+            })
+    }
+
+    fn add_variables_to_symbol_table(&mut self, declaration: &lang_c::ast::Declaration) {
+        let Some(type_specifier) = self.extract_type_specifier(&declaration.specifiers) else {
+            return;
+        };
+
+        for init_declarator in &declaration.declarators {
+            let declarator = &init_declarator.node.declarator.node;
+            let symbol_type = match &declarator.derived[..] {
+                [] => SymbolType::Variable {
+                    type_specifier: type_specifier.clone(),
+                    type_specifiers: type_specifiers(&declaration.specifiers)
+                        .into_iter()
+                        .cloned()
+                        .collect(),
+                },
+                [lang_c::span::Node {
+                    node: lang_c::ast::DerivedDeclarator::Array(array_declarator),
+                    ..
+                }] => SymbolType::Array {
+                    element_type: type_specifier.clone(),
+                    type_specifiers: type_specifiers(&declaration.specifiers)
+                        .into_iter()
+                        .cloned()
+                        .collect(),
+                    length: array_length(&array_declarator.node),
+                },
+                [lang_c::span::Node {
+                    node: lang_c::ast::DerivedDeclarator::Pointer(_),
+                    ..
+                }] => SymbolType::Pointer {
+                    type_specifiers: type_specifiers(&declaration.specifiers)
+                        .into_iter()
+                        .cloned()
+                        .collect(),
+                },
+                // Pointers to pointers, functions and multidimensional arrays are not tracked
+                _ => continue,
+            };
+            if let lang_c::ast::DeclaratorKind::Identifier(identifier) = &declarator.kind.node {
+                self.symbol_table.insert(
+                    identifier.node.name.clone(),
+                    Symbol {
+                        _name: identifier.node.name.clone(),
+                        symbol_type,
+                    },
+                );
+            }
+        }
+    }
+
+    fn add_parameters_to_symbol_table(&mut self, declarator: &lang_c::ast::Declarator) {
+        for derived in &declarator.derived {
+            let lang_c::ast::DerivedDeclarator::Function(function_declarator) = &derived.node
+            else {
+                continue;
+            };
+            for parameter in &function_declarator.node.parameters {
+                let Some(type_specifier) = self.extract_type_specifier(&parameter.node.specifiers)
+                else {
+                    continue;
+                };
+                let Some(declarator) = &parameter.node.declarator else {
+                    continue;
+                };
+                let symbol_type = match &declarator.node.derived[..] {
+                    [] => SymbolType::Variable {
+                        type_specifier,
+                        type_specifiers: type_specifiers(&parameter.node.specifiers)
+                            .into_iter()
+                            .cloned()
+                            .collect(),
+                    },
+                    [lang_c::span::Node {
+                        node: lang_c::ast::DerivedDeclarator::Pointer(_),
+                        ..
+                    }] => SymbolType::Pointer {
+                        type_specifiers: type_specifiers(&parameter.node.specifiers)
+                            .into_iter()
+                            .cloned()
+                            .collect(),
+                    },
+                    _ => continue,
+                };
+                if let Some(name) = declarator_name(&declarator.node) {
+                    self.symbol_table.insert(
+                        name.to_string(),
+                        Symbol {
+                            _name: name.to_string(),
+                            symbol_type,
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    fn add_function_to_symbol_table(&mut self, declaration: &lang_c::ast::Declaration) {
+        for init_declarator in &declaration.declarators {
+            if let Some(lang_c::span::Node {
+                node: lang_c::ast::DerivedDeclarator::KRFunction(_),
+                ..
+            }) = init_declarator.node.declarator.node.derived.first()
+            {
+                if let lang_c::ast::DeclaratorKind::Identifier(identifier) =
+                    &init_declarator.node.declarator.node.kind.node
+                {
+                    // Extract the return type of the function
+                    let return_type = self.extract_return_type(declaration);
+
+                    // Insert the function into the symbol table with its return type
+                    self.symbol_table.insert(
+                        identifier.node.name.clone(),
+                        Symbol {
+                            _name: identifier.node.name.clone(),
+                            symbol_type: SymbolType::Function { return_type },
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    // Helper function to extract the return type
+    fn extract_return_type(
+        &self,
+        declaration: &lang_c::ast::Declaration,
+    ) -> lang_c::ast::TypeSpecifier {
+        match &declaration.specifiers[..] {
+            [lang_c::span::Node {
+                node: lang_c::ast::DeclarationSpecifier::TypeSpecifier(type_specifier),
+                ..
+            }] => type_specifier.node.clone(), // Clone the TypeSpecifier for storage
+            _ => lang_c::ast::TypeSpecifier::Void, // Default to void if unknown
+        }
+    }
+
+    // Helper function to extract the first type specifier, skipping storage classes and qualifiers
+    fn extract_type_specifier(
+        &self,
+        specifiers: &[lang_c::span::Node<lang_c::ast::DeclarationSpecifier>],
+    ) -> Option<lang_c::ast::TypeSpecifier> {
+        specifiers
+            .iter()
+            .find_map(|specifier| match &specifier.node {
+                lang_c::ast::DeclarationSpecifier::TypeSpecifier(type_specifier) => {
+                    Some(type_specifier.node.clone())
+                }
+                _ => None,
+            })
+    }
+
+    // This will only handle cases where the function call is the immediate child of an initializer
+    // It should handle cases where the function call is nested within other expressions
+    fn mark_function_type_checked(&mut self, initializer: &lang_c::ast::Initializer) {
+        if let lang_c::ast::Initializer::Expression(expression) = &initializer {
+            let node = &expression.node;
+            if let lang_c::ast::Expression::Call(call) = node {
+                let callee = &call.node.callee.node;
+                if let lang_c::ast::Expression::Identifier(identifier) = callee {
+                    let func_name = &identifier.node.name;
+                    // If function is in symbol table, set self.current_function_type_cast to return type of function
+                    if let Some(Symbol {
+                        symbol_type: SymbolType::Function { return_type },
+                        ..
+                    }) = self.symbol_table.get(func_name)
+                    {
+                        self.current_function_type_cast = Some(return_type.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_return_value(&mut self, call_expression: &lang_c::ast::CallExpression, span: &Span) {
+        if let lang_c::ast::Expression::Identifier(identifier) = &call_expression.callee.node {
+            if let Some(Symbol {
+                symbol_type: SymbolType::Function { return_type },
+                ..
+            }) = self.symbol_table.get(&identifier.node.name)
+            {
+                // Check if the return type is not void
+                // Ensure that current_function_type_cast is set
+                if *return_type != lang_c::ast::TypeSpecifier::Void
+                    && self.current_function_type_cast.is_none()
+                {
+                    let snippet = self.get_source_code_from_span(span);
+                    self.report(
+                        "check_return_value",
+                        span,
+                        "Call to non-void function does not handle return value",
+                        Some(snippet),
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for StaticAnalyzer {
+    fn visit_translation_unit(&mut self, translation_unit: &'ast lang_c::ast::TranslationUnit) {
+        visit_translation_unit(self, translation_unit);
+        // Cycles can only be found once every function's calls are known
+        if self.rule_set.restrict_recursion {
+            self.check_indirect_recursion();
+        }
+    }
+
+    fn visit_initializer(&mut self, initializer: &'ast lang_c::ast::Initializer, span: &'ast Span) {
+        self.mark_function_type_checked(initializer);
+        visit_initializer(self, initializer, span);
+        self.current_function_type_cast = None;
+    }
+
+    fn visit_declaration(&mut self, declaration: &'ast lang_c::ast::Declaration, span: &'ast Span) {
+        if self.rule_set.check_return_value {
+            self.add_function_to_symbol_table(declaration);
+        }
+        self.add_variables_to_symbol_table(declaration);
+        if self.rule_set.restrict_volatile_toctou {
+            self.add_volatile_objects(declaration);
+        }
+        if self.rule_set.restrict_variadic {
+            self.add_variadic_functions(declaration);
+        }
+        if self.rule_set.restrict_function_pointers {
+            self.check_function_pointer_declaration(declaration);
+        }
+        if self.rule_set.fixed_loop_bounds {
+            self.add_compile_time_constants(declaration);
+        }
+        if self.rule_set.restrict_stack_frame_size {
+            self.add_type_sizes(declaration);
+        }
+        if self.rule_set.require_exhaustive_enum_switch {
+            self.add_enum_types(declaration);
+        }
+        if self.rule_set.restrict_parameter_count {
+            for init_declarator in &declaration.declarators {
+                self.check_parameter_count(&init_declarator.node.declarator);
+            }
+        }
+        if self.rule_set.require_prototypes {
+            for init_declarator in &declaration.declarators {
+                self.check_prototype(&init_declarator.node.declarator);
+            }
+        }
+        if self.rule_set.restrict_implicit_narrowing {
+            for init_declarator in &declaration.declarators {
+                self.add_parameter_types(&init_declarator.node.declarator.node);
+            }
+            self.check_narrowing_initializers(declaration);
+        }
+        if self.rule_set.require_const_correctness {
+            for init_declarator in &declaration.declarators {
+                self.add_const_pointer(
+                    &declaration.specifiers,
+                    &init_declarator.node.declarator.node,
+                );
+            }
+        }
+        if self.rule_set.restrict_unions {
+            let type_specifiers: Vec<_> = declaration
+                .specifiers
+                .iter()
+                .filter_map(|specifier| match &specifier.node {
+                    lang_c::ast::DeclarationSpecifier::TypeSpecifier(type_specifier) => {
+                        Some(type_specifier)
+                    }
+                    _ => None,
+                })
+                .collect();
+            let declarators: Vec<_> = declaration
+                .declarators
+                .iter()
+                .map(|init_declarator| &init_declarator.node.declarator)
+                .collect();
+            let is_typedef = declaration.specifiers.iter().any(|specifier| {
+                matches!(
+                    &specifier.node,
+                    lang_c::ast::DeclarationSpecifier::StorageClass(storage_class)
+                        if storage_class.node == lang_c::ast::StorageClassSpecifier::Typedef
+                )
+            });
+            self.check_union(&type_specifiers, &declarators, is_typedef, "Variable");
+        }
+        visit_declaration(self, declaration, span);
+        self.current_function_type_cast = None;
+    }
+    fn visit_statement(&mut self, statement: &'ast lang_c::ast::Statement, span: &'ast Span) {
+        if self.rule_set.restrict_goto {
+            self.check_goto(statement, span);
+        }
+
+        if self.rule_set.require_state_fault_handling {
+            if let lang_c::ast::Statement::Switch(switch_statement) = statement {
+                self.check_state_switch(&switch_statement.node, span);
+            }
+        }
+
+        if self.rule_set.require_switch_default {
+            if let lang_c::ast::Statement::Switch(switch_statement) = statement {
+                self.check_switch_default(&switch_statement.node, span);
+            }
+        }
+
+        if self.rule_set.require_exhaustive_enum_switch {
+            if let lang_c::ast::Statement::Switch(switch_statement) = statement {
+                self.check_enum_switch(&switch_statement.node, span);
+            }
+        }
+
+        if self.rule_set.require_braces {
+            self.check_braces(statement);
+        }
+
+        // GNU C assert expands to `if (condition) ; else __assert_fail(...);`
+        if self.rule_set.restrict_assertion_side_effects {
+            if let lang_c::ast::Statement::If(if_statement) = statement {
+                if let Some(lang_c::ast::Statement::Expression(Some(expression))) = if_statement
+                    .node
+                    .else_statement
+                    .as_ref()
+                    .map(|else_statement| &else_statement.node)
+                {
+                    if is_assertion_failure(&expression.node) {
+                        self.check_assertion_side_effects(&if_statement.node.condition);
+                    }
+                }
+            }
+        }
+
+        visit_statement(self, statement, span);
+    }
+
+    fn visit_enumerator(&mut self, enumerator: &'ast lang_c::ast::Enumerator, span: &'ast Span) {
+        if self.rule_set.fixed_loop_bounds {
+            self.compile_time_constants
+                .insert(enumerator.identifier.node.name.clone());
+        }
+        if self.rule_set.restrict_variable_length_arrays {
+            self.enumeration_constants
+                .insert(enumerator.identifier.node.name.clone());
+        }
+        visit_enumerator(self, enumerator, span);
+    }
+
+    fn visit_array_declarator(
+        &mut self,
+        array_declarator: &'ast lang_c::ast::ArrayDeclarator,
+        span: &'ast Span,
+    ) {
+        if self.rule_set.restrict_variable_length_arrays {
+            self.check_variable_length_array(array_declarator, span);
+        }
+        visit_array_declarator(self, array_declarator, span);
+    }
+
+    fn visit_parameter_declaration(
+        &mut self,
+        parameter_declaration: &'ast lang_c::ast::ParameterDeclaration,
+        span: &'ast Span,
+    ) {
+        if self.rule_set.restrict_function_pointers {
+            self.check_function_pointer_parameter(parameter_declaration, span);
+        }
+        if self.rule_set.require_const_correctness {
+            if let Some(declarator) = &parameter_declaration.declarator {
+                self.add_const_pointer(&parameter_declaration.specifiers, &declarator.node);
+            }
+        }
+        visit_parameter_declaration(self, parameter_declaration, span);
+    }
+
+    fn visit_struct_field(
+        &mut self,
+        struct_field: &'ast lang_c::ast::StructField,
+        span: &'ast Span,
+    ) {
+        if self.rule_set.restrict_function_pointers {
+            self.check_function_pointer_member(struct_field);
+        }
+        if self.rule_set.restrict_bit_field_types && !self.line_index.is_system_header(span.start) {
+            self.check_bit_fields(struct_field);
+        }
+        if self.rule_set.restrict_unions {
+            let type_specifiers: Vec<_> = struct_field
+                .specifiers
+                .iter()
+                .filter_map(|specifier| match &specifier.node {
+                    lang_c::ast::SpecifierQualifier::TypeSpecifier(type_specifier) => {
+                        Some(type_specifier)
+                    }
+                    _ => None,
+                })
+                .collect();
+            let declarators: Vec<_> = struct_field
+                .declarators
+                .iter()
+                .filter_map(|struct_declarator| struct_declarator.node.declarator.as_ref())
+                .collect();
+            self.check_union(&type_specifiers, &declarators, false, "Member");
+        }
+        visit_struct_field(self, struct_field, span);
+    }
+
+    fn visit_external_declaration(
+        &mut self,
+        external_declaration: &'ast lang_c::ast::ExternalDeclaration,
+        span: &'ast Span,
+    ) {
+        if self.rule_set.restrict_magic_numbers {
+            self.check_magic_numbers(external_declaration, span);
+        }
+        if let lang_c::ast::ExternalDeclaration::Declaration(declaration) = external_declaration {
+            if self.rule_set.restrict_global_mutable_data {
+                self.check_global_declaration(&declaration.node);
+            }
+            if self.rule_set.restrict_shadowing {
+                self.add_file_scope_variables(&declaration.node);
+            }
+        }
+        visit_external_declaration(self, external_declaration, span);
+    }
+
+    fn visit_function_definition(
+        &mut self,
+        function_definition: &'ast lang_c::ast::FunctionDefinition,
+        span: &'ast Span,
+    ) {
+        if let lang_c::ast::DeclaratorKind::Identifier(identifier) =
+            &function_definition.declarator.node.kind.node
+        {
+            // Extract the return type of the function
+            let return_type = match &function_definition.specifiers[..] {
+                [lang_c::span::Node {
+                    node: lang_c::ast::DeclarationSpecifier::TypeSpecifier(type_specifier),
+                    ..
+                }] => {
+                    type_specifier.node.clone() // Clone the TypeSpecifier for storage
+                }
+                _ => lang_c::ast::TypeSpecifier::Void, // Default to void if unknown
+            };
+
+            // Insert the function into the symbol table with its return type
+            self.symbol_table.insert(
+                identifier.node.name.clone(),
+                Symbol {
+                    _name: identifier.node.name.clone(),
+                    symbol_type: SymbolType::Function { return_type },
+                },
+            );
+        }
+
+        self.add_parameters_to_symbol_table(&function_definition.declarator.node);
+        if self.rule_set.restrict_implicit_narrowing {
+            self.add_parameter_types(&function_definition.declarator.node);
+        }
+
+        if self.rule_set.restrict_recursion {
+            self.set_current_function(function_definition, span);
+        }
+
+        if self.rule_set.restrict_function_size {
+            let size = self.get_function_size(span);
+            let max_function_lines = self.rule_set.max_function_lines;
+
+            if size > max_function_lines {
+                self.report(
+                    "restrict_function_size",
+                    span,
+                    &format!("Function size exceeds {} lines", max_function_lines),
+                    None,
+                );
+            }
+        }
+
+        if self.rule_set.restrict_parameter_count {
+            self.check_parameter_count(&function_definition.declarator);
+        }
+
+        if self.rule_set.require_prototypes {
+            self.check_prototype(&function_definition.declarator);
+        }
+
+        if self.rule_set.require_assertions {
+            self.check_assertion_density(function_definition, span);
+        }
+
+        if self.rule_set.restrict_variadic {
+            self.check_variadic_definition(function_definition, span);
+        }
+
+        if self.rule_set.restrict_volatile_toctou {
+            self.check_volatile_toctou(function_definition);
+        }
+
+        if self.rule_set.require_parameter_validation {
+            self.check_parameter_validation(function_definition);
+        }
+
+        if self.rule_set.require_smallest_scope {
+            self.check_local_scopes(function_definition);
+        }
+
+        if self.rule_set.restrict_unreachable_code {
+            self.check_unreachable_code(function_definition);
+        }
+
+        if self.rule_set.restrict_unused_variables {
+            self.check_unused_variables(function_definition);
+        }
+
+        if self.rule_set.restrict_shadowing {
+            self.check_shadowing(function_definition);
+        }
+
+        if self.rule_set.restrict_uninitialized_reads {
+            self.check_uninitialized_reads(function_definition);
+        }
+
+        if self.rule_set.require_const_correctness {
+            self.check_const_pointer_parameters(function_definition);
+        }
+
+        if self.rule_set.restrict_implicit_fallthrough {
+            self.check_implicit_fallthrough(function_definition);
+        }
+
+        // Individual gotos are already reported when goto is restricted outright
+        if self.rule_set.restrict_unstructured_goto && !self.rule_set.restrict_goto {
+            self.check_unstructured_goto(function_definition);
+        }
+
+        visit_function_definition(self, function_definition, span);
+
+        // Checked after the body is visited, which defines the sizes of local types
+        if self.rule_set.restrict_stack_frame_size {
+            self.check_stack_frame_size(function_definition);
+        }
+
+        self.current_function = None;
+        self.current_function_parameters.clear();
+        self.current_recursion_annotation = None;
+        self.in_allowed_variadic_function = false;
+    }
+
+    fn visit_cast_expression(
+        &mut self,
+        cast_expression: &'ast lang_c::ast::CastExpression,
+        span: &'ast Span,
+    ) {
+        if self.rule_set.require_const_correctness {
+            self.check_const_cast(cast_expression, span);
+        }
+
+        let Some(specifier) = cast_expression.type_name.node.specifiers.first() else {
+            return;
+        };
+
+        let type_specifier: &lang_c::ast::SpecifierQualifier = &specifier.node;
+        if let lang_c::ast::SpecifierQualifier::TypeSpecifier(type_specifier) = type_specifier {
+            self.current_function_type_cast = Some(type_specifier.node.clone());
+        }
+        visit_cast_expression(self, cast_expression, span);
+        self.current_function_type_cast = None;
+    }
+
+    fn visit_expression(&mut self, expression: &'ast lang_c::ast::Expression, span: &'ast Span) {
+        // va_arg expands to a builtin that lang_c parses as its own expression kind
+        if self.rule_set.restrict_variadic {
+            if matches!(expression, lang_c::ast::Expression::VaArg(_))
+                && !self.in_allowed_variadic_function
+            {
+                self.report("restrict_variadic", span, "'va_arg' use found", None);
+            }
+        }
+
+        // Only the outermost dereference of a chain is checked, so `p->q->r` is
+        // reported once rather than again for `p->q`
+        let is_dereference = dereferenced_operand(expression).is_some();
+        if self.rule_set.restrict_dereference_depth && is_dereference && !self.in_dereference_chain
+        {
+            self.check_dereference_depth(expression, span);
+        }
+        if self.rule_set.restrict_assertion_side_effects {
+            self.check_assertion_expression(expression);
+        }
+
+        if self.rule_set.restrict_float_equality {
+            self.check_float_equality(expression, span);
+        }
+
+        if self.rule_set.restrict_signed_bitwise {
+            self.check_signed_bitwise(expression);
+        }
+
+        if self.rule_set.restrict_implicit_narrowing {
+            self.check_narrowing_assignment(expression);
+        }
+
+        if self.rule_set.restrict_octal_constants {
+            self.check_octal_constant(expression, span);
+        }
+
+        if self.rule_set.restrict_comma_operator
+            && matches!(expression, lang_c::ast::Expression::Comma(_))
+            && !self.for_clause_commas.contains(&span.start)
+        {
+            self.report(
+                "restrict_comma_operator",
+                span,
+                "Comma operator used outside a for statement clause",
+                None,
+            );
+        }
+
+        let is_conditional = matches!(expression, lang_c::ast::Expression::Conditional(_));
+        if self.rule_set.restrict_nested_conditionals {
+            self.check_nested_conditional(expression, span, is_conditional);
+        }
+
+        let in_dereference_chain = self.in_dereference_chain;
+        self.in_dereference_chain = is_dereference;
+        self.conditional_depth += usize::from(is_conditional);
+        visit_expression(self, expression, span);
+        self.conditional_depth -= usize::from(is_conditional);
+        self.in_dereference_chain = in_dereference_chain;
+    }
+
+    fn visit_call_expression(
+        &mut self,
+        call_expression: &'ast lang_c::ast::CallExpression,
+        span: &'ast Span,
+    ) {
+        if self.rule_set.restrict_recursion {
+            self.check_recursion(call_expression, span);
+        }
+
+        if self.rule_set.restrict_setjmp {
+            self.check_setjmp(call_expression, span);
+        }
+
+        if self.rule_set.restrict_longjmp {
+            self.check_longjmp(call_expression, span);
+        }
+
+        if self.rule_set.restrict_heap_allocation {
+            self.check_heap_usage(call_expression, span);
+        }
+
+        if self.rule_set.restrict_alloca {
+            self.check_stack_allocation(call_expression, span);
+        }
+
+        if self.rule_set.restrict_banned_functions {
+            self.check_banned_function(call_expression, span);
+        }
+
+        if self.rule_set.restrict_unsafe_string_functions {
+            self.check_unsafe_string_function(call_expression, span);
+        }
+
+        if self.rule_set.restrict_termination_calls {
+            self.check_termination_call(call_expression, span);
+        }
+
+        if self.rule_set.restrict_variadic {
+            self.check_variadic_call(call_expression, span);
+        }
+
+        if self.rule_set.restrict_locale_apis {
+            self.check_locale_api(call_expression, span);
+        }
+
+        if self.rule_set.restrict_format_mismatches {
+            self.check_format_arguments(call_expression, span);
+        }
+
+        if self.rule_set.restrict_implicit_narrowing {
+            self.check_narrowing_arguments(call_expression);
+        }
+
+        if self.rule_set.check_return_value {
+            self.check_return_value(call_expression, span);
+        }
+
+        if self.rule_set.require_sizeof_size_arguments {
+            self.check_size_argument(call_expression, span);
+        }
+
+        if self.rule_set.restrict_offset_buffer_overrun {
+            self.check_offset_buffer(call_expression);
+        }
+
+        if self.rule_set.restrict_function_pointers {
+            self.check_function_pointer_call(call_expression, span);
+        }
+
+        visit_call_expression(self, call_expression, span);
+    }
+
+    fn visit_while_statement(
+        &mut self,
+        while_statement: &'ast lang_c::ast::WhileStatement,
+        span: &'ast Span,
+    ) {
+        if self.rule_set.fixed_loop_bounds && self.declared_loop_bound(span).is_none() {
+            self.check_loop_condition_bounds(&while_statement.expression.node, span);
+            self.check_bound_counter_modifications(
+                &while_statement.expression.node,
+                &while_statement.statement,
+            );
+        }
+        if self.rule_set.restrict_float_loop_counters {
+            self.check_float_loop_condition(&while_statement.expression.node, span);
+        }
+        visit_while_statement(self, while_statement, span);
+    }
+
+    fn visit_do_while_statement(
+        &mut self,
+        do_while_statement: &'ast lang_c::ast::DoWhileStatement,
+        span: &'ast Span,
+    ) {
+        if self.rule_set.fixed_loop_bounds && self.declared_loop_bound(span).is_none() {
+            self.check_loop_condition_bounds(&do_while_statement.expression.node, span);
+            self.check_bound_counter_modifications(
+                &do_while_statement.expression.node,
+                &do_while_statement.statement,
+            );
+        }
+        if self.rule_set.restrict_float_loop_counters {
+            self.check_float_loop_condition(&do_while_statement.expression.node, span);
+        }
+        visit_do_while_statement(self, do_while_statement, span);
+    }
+
+    fn visit_for_statement(
+        &mut self,
+        for_statement: &'ast lang_c::ast::ForStatement,
+        span: &'ast Span,
+    ) {
+        if self.rule_set.fixed_loop_bounds && self.declared_loop_bound(span).is_none() {
+            self.check_for_loop_bounds(for_statement, span);
+            if let Some(condition) = &for_statement.condition {
+                self.check_bound_counter_modifications(&condition.node, &for_statement.statement);
+            }
+        }
+        if self.rule_set.restrict_float_loop_counters {
+            self.check_for_loop_float_counter(for_statement, span);
+        }
+        if self.rule_set.restrict_loop_counter_modification {
+            self.check_loop_counter_modification(for_statement);
+        }
+        // `for (i = 0, j = n; i < j; i++, j--)` steps two counters together
+        if self.rule_set.restrict_comma_operator {
+            let initializer = match &for_statement.initializer.node {
+                lang_c::ast::ForInitializer::Expression(expression) => Some(&**expression),
+                _ => None,
+            };
+            for clause in initializer.into_iter().chain(for_statement.step.as_deref()) {
+                if let lang_c::ast::Expression::Comma(_) = clause.node {
+                    self.for_clause_commas.insert(clause.span.start);
+                }
+            }
+        }
+        visit_for_statement(self, for_statement, span);
+    }
+}
+
+// Collects labels, gotos and initialized declarations of a function body along with
+// the chain of compound statements enclosing each of them
+#[derive(Default)]
+struct GotoCollector {
+    next_block: usize,
+    blocks: Vec<usize>,
+    labels: HashMap<String, (usize, Vec<usize>)>,
+    gotos: Vec<(String, Span, Vec<usize>)>,
+    initializations: Vec<(String, usize, Vec<usize>)>,
+}
+
+impl<'ast> Visit<'ast> for GotoCollector {
+    fn visit_statement(&mut self, statement: &'ast lang_c::ast::Statement, span: &'ast Span) {
+        match statement {
+            lang_c::ast::Statement::Compound(_) => {
+                self.next_block += 1;
+                self.blocks.push(self.next_block);
+                visit_statement(self, statement, span);
+                self.blocks.pop();
+                return;
+            }
+            lang_c::ast::Statement::Goto(label) => {
+                self.gotos
+                    .push((label.node.name.clone(), *span, self.blocks.clone()));
+            }
+            lang_c::ast::Statement::Labeled(labeled_statement) => {
+                if let lang_c::ast::Label::Identifier(label) = &labeled_statement.node.label.node {
+                    self.labels
+                        .insert(label.node.name.clone(), (span.start, self.blocks.clone()));
+                }
+            }
+            _ => {}
+        }
+        visit_statement(self, statement, span);
+    }
+
+    fn visit_declaration(&mut self, declaration: &'ast lang_c::ast::Declaration, span: &'ast Span) {
+        for init_declarator in &declaration.declarators {
+            if init_declarator.node.initializer.is_none() {
+                continue;
+            }
+            if let lang_c::ast::DeclaratorKind::Identifier(identifier) =
+                &init_declarator.node.declarator.node.kind.node
+            {
+                self.initializations.push((
+                    identifier.node.name.clone(),
+                    span.start,
+                    self.blocks.clone(),
+                ));
+            }
+        }
+        visit_declaration(self, declaration, span);
+    }
+}
+
+// Collects every assignment, increment or decrement of the given loop counters
+struct CounterModificationFinder<'a> {
+    counters: &'a [String],
+    modifications: Vec<(String, Span, Option<Direction>)>, // With the way a constant step moves the counter
+}
+
+impl CounterModificationFinder<'_> {
+    fn record(
+        &mut self,
+        target: &lang_c::ast::Expression,
+        span: &Span,
+        step: impl Fn(&str) -> Option<Direction>,
+    ) {
+        if let lang_c::ast::Expression::Identifier(identifier) = target {
+            if self.counters.contains(&identifier.node.name) {
+                let step = step(&identifier.node.name);
+                self.modifications
+                    .push((identifier.node.name.clone(), *span, step));
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for CounterModificationFinder<'_> {
+    fn visit_binary_operator_expression(
+        &mut self,
+        binary_operator_expression: &'ast lang_c::ast::BinaryOperatorExpression,
+        span: &'ast Span,
+    ) {
+        if is_assignment_operator(&binary_operator_expression.operator.node) {
+            self.record(&binary_operator_expression.lhs.node, span, |counter| {
+                binary_step(binary_operator_expression, counter)
+            });
+        }
+        visit_binary_operator_expression(self, binary_operator_expression, span);
+    }
+
+    fn visit_unary_operator_expression(
+        &mut self,
+        unary_operator_expression: &'ast lang_c::ast::UnaryOperatorExpression,
+        span: &'ast Span,
+    ) {
+        if matches!(
+            unary_operator_expression.operator.node,
+            lang_c::ast::UnaryOperator::PreIncrement
+                | lang_c::ast::UnaryOperator::PreDecrement
+                | lang_c::ast::UnaryOperator::PostIncrement
+                | lang_c::ast::UnaryOperator::PostDecrement
+        ) {
+            self.record(&unary_operator_expression.operand.node, span, |counter| {
+                unary_step(unary_operator_expression, counter)
+            });
+        }
+        visit_unary_operator_expression(self, unary_operator_expression, span);
+    }
+}
+
+// Finds volatile objects read again inside the statements guarded by a check of them,
+// and conditions testing a cached copy of a register that must be read fresh
+struct VolatileAccessChecker<'a> {
+    volatile_objects: HashSet<String>,
+    fresh_read_registers: &'a [String],
+    cached_registers: HashMap<String, String>, // Variable -> register it holds a copy of
+    findings: Vec<(Span, String)>,
+}
+
+impl VolatileAccessChecker<'_> {
+    fn check_guarded_reads(
+        &mut self,
+        condition: &lang_c::span::Node<lang_c::ast::Expression>,
+        guarded: &[&lang_c::span::Node<lang_c::ast::Statement>],
+    ) {
+        let mut condition_reads = ReadCollector::default();
+        condition_reads.visit_expression(&condition.node, &condition.span);
+        let mut checked: HashSet<String> = condition_reads
+            .reads
+            .into_iter()
+            .map(|(name, _)| name)
+            .filter(|name| self.volatile_objects.contains(name))
+            .collect();
+
+        for statement in guarded {
+            let mut reads = ReadCollector::default();
+            reads.visit_statement(&statement.node, &statement.span);
+            for (name, span) in reads.reads {
+                // Report the first re-read of each register only
+                if checked.remove(&name) {
+                    let message = format!(
+                        "Volatile '{}' is read again after being checked, use the value read for the check",
+                        name
+                    );
+                    self.findings.push((span, message));
+                }
+            }
+        }
+    }
+
+    fn check_cached_condition(&mut self, condition: &lang_c::span::Node<lang_c::ast::Expression>) {
+        let mut reads = ReadCollector::default();
+        reads.visit_expression(&condition.node, &condition.span);
+        for (name, span) in reads.reads {
+            if let Some(register) = self.cached_registers.get(&name) {
+                let message = format!(
+                    "Condition tests '{}', a cached copy of register '{}' which must be read fresh",
+                    name, register
+                );
+                self.findings.push((span, message));
+            }
+        }
+    }
+
+    // Track `variable = REGISTER` copies of the registers that must be read fresh
+    fn record_assignment(&mut self, variable: &str, value: &lang_c::ast::Expression) {
+        match value {
+            lang_c::ast::Expression::Identifier(register)
+                if self.fresh_read_registers.contains(&register.node.name) =>
+            {
+                self.cached_registers
+                    .insert(variable.to_string(), register.node.name.clone());
+            }
+            _ => {
+                self.cached_registers.remove(variable);
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for VolatileAccessChecker<'_> {
+    fn visit_declaration(&mut self, declaration: &'ast lang_c::ast::Declaration, span: &'ast Span) {
+        let volatile = is_volatile(&declaration.specifiers);
+        for init_declarator in &declaration.declarators {
+            let Some(name) = declarator_name(&init_declarator.node.declarator.node) else {
+                continue;
+            };
+            if volatile {
+                self.volatile_objects.insert(name.to_string());
+            }
+            match &init_declarator.node.initializer {
+                Some(lang_c::span::Node {
+                    node: lang_c::ast::Initializer::Expression(value),
+                    ..
+                }) => self.record_assignment(name, &value.node),
+                _ => {
+                    self.cached_registers.remove(name);
+                }
+            }
+        }
+        visit_declaration(self, declaration, span);
+    }
+
+    fn visit_binary_operator_expression(
+        &mut self,
+        binary_operator_expression: &'ast lang_c::ast::BinaryOperatorExpression,
+        span: &'ast Span,
+    ) {
+        if let lang_c::ast::Expression::Identifier(variable) = &binary_operator_expression.lhs.node
+        {
+            if matches!(
+                binary_operator_expression.operator.node,
+                lang_c::ast::BinaryOperator::Assign
+            ) {
+                self.record_assignment(&variable.node.name, &binary_operator_expression.rhs.node);
+            } else if is_assignment_operator(&binary_operator_expression.operator.node) {
+                self.cached_registers.remove(&variable.node.name);
+            }
+        }
+        visit_binary_operator_expression(self, binary_operator_expression, span);
+    }
+
+    fn visit_statement(&mut self, statement: &'ast lang_c::ast::Statement, span: &'ast Span) {
+        match statement {
+            lang_c::ast::Statement::If(if_statement) => {
+                let mut guarded = vec![&*if_statement.node.then_statement];
+                guarded.extend(if_statement.node.else_statement.as_deref());
+                self.check_cached_condition(&if_statement.node.condition);
+                self.check_guarded_reads(&if_statement.node.condition, &guarded);
+            }
+            lang_c::ast::Statement::While(while_statement) => {
+                self.check_cached_condition(&while_statement.node.expression);
+            }
+            lang_c::ast::Statement::DoWhile(do_while_statement) => {
+                self.check_cached_condition(&do_while_statement.node.expression);
+            }
+            lang_c::ast::Statement::For(for_statement) => {
+                if let Some(condition) = &for_statement.node.condition {
+                    self.check_cached_condition(condition);
+                }
+            }
+            _ => {}
+        }
+        visit_statement(self, statement, span);
+    }
+}
+
+// Collects the variables an expression or statement reads, skipping plain assignment targets
+#[derive(Default)]
+struct ReadCollector {
+    reads: Vec<(String, Span)>,
+}
+
+impl<'ast> Visit<'ast> for ReadCollector {
+    fn visit_expression(&mut self, expression: &'ast lang_c::ast::Expression, span: &'ast Span) {
+        if let lang_c::ast::Expression::Identifier(identifier) = expression {
+            self.reads.push((identifier.node.name.clone(), *span));
+        }
+        visit_expression(self, expression, span);
+    }
+
+    fn visit_binary_operator_expression(
+        &mut self,
+        binary_operator_expression: &'ast lang_c::ast::BinaryOperatorExpression,
+        span: &'ast Span,
+    ) {
+        let is_plain_assignment = matches!(
+            binary_operator_expression.operator.node,
+            lang_c::ast::BinaryOperator::Assign
+        ) && matches!(
+            binary_operator_expression.lhs.node,
+            lang_c::ast::Expression::Identifier(_)
+        );
+        if is_plain_assignment {
+            let rhs = &binary_operator_expression.rhs;
+            self.visit_expression(&rhs.node, &rhs.span);
+            return;
+        }
+        visit_binary_operator_expression(self, binary_operator_expression, span);
+    }
+}
+
+// Collects the declarations of a function body, in every nested block
+#[derive(Default)]
+struct DeclarationCollector<'ast> {
+    declarations: Vec<&'ast lang_c::ast::Declaration>,
+}
+
+impl<'ast> Visit<'ast> for DeclarationCollector<'ast> {
+    fn visit_declaration(&mut self, declaration: &'ast lang_c::ast::Declaration, span: &'ast Span) {
+        self.declarations.push(declaration);
+        visit_declaration(self, declaration, span);
+    }
+}
+
+// Collects the places a variable is mentioned, whether read or written
+struct UseCollector<'a> {
+    name: &'a str,
+    uses: Vec<Span>,
+}
+
+impl<'ast> Visit<'ast> for UseCollector<'_> {
+    fn visit_expression(&mut self, expression: &'ast lang_c::ast::Expression, span: &'ast Span) {
+        if let lang_c::ast::Expression::Identifier(identifier) = expression {
+            if identifier.node.name == self.name {
+                self.uses.push(*span);
+            }
+        }
+        visit_expression(self, expression, span);
+    }
+}
+
+// A parameter or local variable of a function
+struct Local<'ast> {
+    name: &'ast str,
+    span: Span,
+    is_parameter: bool,
+    is_read: bool,
+    shadows: Option<Span>, // Declaration of the same name in an enclosing scope
+}
+
+// Resolves each read of a function body to the innermost declaration of that name
+#[derive(Default)]
+struct UsageTracker<'ast> {
+    locals: Vec<Local<'ast>>, // Every parameter and local variable, in declaration order
+    scopes: Vec<Vec<usize>>,  // Locals declared in each open scope, innermost last
+}
+
+impl<'ast> UsageTracker<'ast> {
+    // Track the parameters and local variables of a function through its body
+    fn track(function_definition: &'ast lang_c::ast::FunctionDefinition) -> Self {
+        let mut tracker = UsageTracker::default();
+        tracker.scopes.push(Vec::new());
+        if let Some(lang_c::ast::DerivedDeclarator::Function(function_declarator)) =
+            derivations(&function_definition.declarator.node).first()
+        {
+            for parameter in &function_declarator.node.parameters {
+                if let Some(declarator) = &parameter.node.declarator {
+                    tracker.declare(declarator, true);
+                }
+            }
+        }
+        let body = &function_definition.statement;
+        tracker.visit_statement(&body.node, &body.span);
+        tracker
+    }
+
+    fn declare(
+        &mut self,
+        declarator: &'ast lang_c::span::Node<lang_c::ast::Declarator>,
+        is_parameter: bool,
+    ) {
+        let Some(name) = declarator_name(&declarator.node) else {
+            return;
+        };
+        let enclosing = self.scopes.len().saturating_sub(1);
+        let shadows = self.lookup(&self.scopes[..enclosing], name);
+        let shadows = shadows.map(|index| self.locals[index].span);
+
+        self.locals.push(Local {
+            name,
+            span: declarator.span,
+            is_parameter,
+            is_read: false,
+            shadows,
+        });
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(self.locals.len() - 1);
+        }
+    }
+
+    fn read(&mut self, name: &str) {
+        if let Some(index) = self.lookup(&self.scopes, name) {
+            self.locals[index].is_read = true;
+        }
+    }
+
+    // Innermost declaration of a name in the given scopes
+    fn lookup(&self, scopes: &[Vec<usize>], name: &str) -> Option<usize> {
+        scopes
+            .iter()
+            .rev()
+            .find_map(|scope| {
+                scope
+                    .iter()
+                    .rev()
+                    .find(|&&index| self.locals[index].name == name)
+            })
+            .copied()
+    }
+}
+
+impl<'ast> Visit<'ast> for UsageTracker<'ast> {
+    fn visit_statement(&mut self, statement: &'ast lang_c::ast::Statement, span: &'ast Span) {
+        if !matches!(
+            statement,
+            lang_c::ast::Statement::Compound(_) | lang_c::ast::Statement::For(_)
+        ) {
+            visit_statement(self, statement, span);
+            return;
+        }
+        self.scopes.push(Vec::new());
+        visit_statement(self, statement, span);
+        self.scopes.pop();
+    }
+
+    fn visit_declaration(&mut self, declaration: &'ast lang_c::ast::Declaration, span: &'ast Span) {
+        // Initializers and array sizes are read before the declared names are in scope
+        visit_declaration(self, declaration, span);
+        if is_extern_or_typedef(&declaration.specifiers) {
+            return;
+        }
+        for init_declarator in &declaration.declarators {
+            let declarator = &init_declarator.node.declarator;
+            if !matches!(
+                derivations(&declarator.node).first(),
+                Some(
+                    lang_c::ast::DerivedDeclarator::Function(_)
+                        | lang_c::ast::DerivedDeclarator::KRFunction(_)
+                )
+            ) {
+                self.declare(declarator, false);
+            }
+        }
+    }
+
+    fn visit_expression(&mut self, expression: &'ast lang_c::ast::Expression, span: &'ast Span) {
+        if let lang_c::ast::Expression::Identifier(identifier) = expression {
+            self.read(&identifier.node.name);
+        }
+        visit_expression(self, expression, span);
+    }
+
+    fn visit_binary_operator_expression(
+        &mut self,
+        binary_operator_expression: &'ast lang_c::ast::BinaryOperatorExpression,
+        span: &'ast Span,
+    ) {
+        let is_plain_assignment = matches!(
+            binary_operator_expression.operator.node,
+            lang_c::ast::BinaryOperator::Assign
+        ) && matches!(
+            binary_operator_expression.lhs.node,
+            lang_c::ast::Expression::Identifier(_)
+        );
+        if is_plain_assignment {
+            let rhs = &binary_operator_expression.rhs;
+            self.visit_expression(&rhs.node, &rhs.span);
+            return;
+        }
+        visit_binary_operator_expression(self, binary_operator_expression, span);
+    }
+}
+
+// Collects the side effects of an expression, leaving out unevaluated sizeof operands
+struct SideEffectFinder<'a> {
+    pure_functions: &'a [String],
+    side_effects: Vec<(Span, String)>,
+}
+
+impl<'ast> Visit<'ast> for SideEffectFinder<'_> {
+    fn visit_expression(&mut self, expression: &'ast lang_c::ast::Expression, span: &'ast Span) {
+        match expression {
+            lang_c::ast::Expression::SizeOfVal(_)
+            | lang_c::ast::Expression::SizeOfTy(_)
+            | lang_c::ast::Expression::AlignOf(_) => {}
+            _ => visit_expression(self, expression, span),
+        }
+    }
+
+    fn visit_binary_operator_expression(
+        &mut self,
+        binary_operator_expression: &'ast lang_c::ast::BinaryOperatorExpression,
+        span: &'ast Span,
+    ) {
+        if is_assignment_operator(&binary_operator_expression.operator.node) {
+            self.side_effects.push((*span, "assignment".to_string()));
+        }
+        visit_binary_operator_expression(self, binary_operator_expression, span);
+    }
+
+    fn visit_unary_operator_expression(
+        &mut self,
+        unary_operator_expression: &'ast lang_c::ast::UnaryOperatorExpression,
+        span: &'ast Span,
+    ) {
+        let side_effect = match unary_operator_expression.operator.node {
+            lang_c::ast::UnaryOperator::PreIncrement
+            | lang_c::ast::UnaryOperator::PostIncrement => Some("increment"),
+            lang_c::ast::UnaryOperator::PreDecrement
+            | lang_c::ast::UnaryOperator::PostDecrement => Some("decrement"),
+            _ => None,
+        };
+        if let Some(side_effect) = side_effect {
+            self.side_effects.push((*span, side_effect.to_string()));
+        }
+        visit_unary_operator_expression(self, unary_operator_expression, span);
+    }
+
+    fn visit_call_expression(
+        &mut self,
+        call_expression: &'ast lang_c::ast::CallExpression,
+        span: &'ast Span,
+    ) {
+        match &call_expression.callee.node {
+            lang_c::ast::Expression::Identifier(identifier)
+                if self.pure_functions.contains(&identifier.node.name) => {}
+            lang_c::ast::Expression::Identifier(identifier) => self
+                .side_effects
+                .push((*span, format!("call to '{}'", identifier.node.name))),
+            _ => self
+                .side_effects
+                .push((*span, "call through a function pointer".to_string())),
+        }
+        visit_call_expression(self, call_expression, span);
+    }
+}
+
+// Collects the numeric literals of a declaration or function, with whether they are
+// negated, leaving out named constants, enumerators, array sizes and bit-field widths
+#[derive(Default)]
+struct NumberCollector<'ast> {
+    numbers: Vec<(&'ast lang_c::ast::Constant, Span, bool)>,
+}
+
+impl<'ast> Visit<'ast> for NumberCollector<'ast> {
+    fn visit_declaration(
+        &mut self,
+        declaration: &'ast lang_c::ast::Declaration,
+        _span: &'ast Span,
+    ) {
+        for specifier in &declaration.specifiers {
+            self.visit_declaration_specifier(&specifier.node, &specifier.span);
+        }
+        for init_declarator in &declaration.declarators {
+            if is_mutable_data(
+                &declaration.specifiers,
+                &init_declarator.node.declarator.node,
+            ) {
+                self.visit_init_declarator(&init_declarator.node, &init_declarator.span);
+            }
+        }
+    }
+
+    fn visit_enumerator(&mut self, _enumerator: &'ast lang_c::ast::Enumerator, _span: &'ast Span) {}
+
+    fn visit_array_declarator(
+        &mut self,
+        _array_declarator: &'ast lang_c::ast::ArrayDeclarator,
+        _span: &'ast Span,
+    ) {
+    }
+
+    fn visit_struct_declarator(
+        &mut self,
+        _struct_declarator: &'ast lang_c::ast::StructDeclarator,
+        _span: &'ast Span,
+    ) {
+    }
+
+    fn visit_static_assert(
+        &mut self,
+        _static_assert: &'ast lang_c::ast::StaticAssert,
+        _span: &'ast Span,
+    ) {
+    }
+
+    fn visit_expression(&mut self, expression: &'ast lang_c::ast::Expression, span: &'ast Span) {
+        match expression {
+            lang_c::ast::Expression::Constant(constant) => {
+                self.numbers.push((&constant.node, constant.span, false));
+            }
+            lang_c::ast::Expression::UnaryOperator(unary_operator_expression)
+                if unary_operator_expression.node.operator.node
+                    == lang_c::ast::UnaryOperator::Minus =>
+            {
+                match &unary_operator_expression.node.operand.node {
+                    lang_c::ast::Expression::Constant(constant) => {
+                        self.numbers.push((&constant.node, constant.span, true));
+                    }
+                    _ => visit_expression(self, expression, span),
+                }
+            }
+            _ => visit_expression(self, expression, span),
+        }
+    }
+}
+
+// Records whether any of the given functions is called
+struct CallFinder<'a> {
+    names: &'a [String],
+    found: bool,
+}
+
+impl<'ast> Visit<'ast> for CallFinder<'_> {
+    fn visit_call_expression(
+        &mut self,
+        call_expression: &'ast lang_c::ast::CallExpression,
+        span: &'ast Span,
+    ) {
+        if let lang_c::ast::Expression::Identifier(identifier) = &call_expression.callee.node {
+            if self.names.contains(&identifier.node.name) {
+                self.found = true;
+            }
+        }
+        visit_call_expression(self, call_expression, span);
+    }
+}
+
+// Statements of a switch body from the default label up to the next case label
+fn default_case_statements(
+    body: &lang_c::ast::Statement,
+) -> Vec<&lang_c::span::Node<lang_c::ast::Statement>> {
+    let lang_c::ast::Statement::Compound(items) = body else {
+        return Vec::new();
+    };
+
+    let mut statements = Vec::new();
+    let mut in_default = false;
+    for item in items {
+        let lang_c::ast::BlockItem::Statement(statement) = &item.node else {
+            continue;
+        };
+
+        // A statement can carry several labels, e.g. `case 3: default:`
+        let mut statement = statement;
+        let mut labeled = false;
+        let mut is_default = false;
+        while let lang_c::ast::Statement::Labeled(labeled_statement) = &statement.node {
+            labeled = true;
+            is_default |= matches!(
+                labeled_statement.node.label.node,
+                lang_c::ast::Label::Default
+            );
+            statement = &*labeled_statement.node.statement;
+        }
+
+        if labeled {
+            if in_default && !is_default {
+                break;
+            }
+            in_default |= is_default;
+        }
+        if in_default {
+            statements.push(statement);
+        }
+    }
+
+    statements
+}
+
+// Name of an enum or typedef type, as listed in the state_types setting
+fn type_name(type_specifier: &lang_c::ast::TypeSpecifier) -> Option<&str> {
+    match type_specifier {
+        lang_c::ast::TypeSpecifier::Enum(enum_type) => enum_type
+            .node
+            .identifier
+            .as_ref()
+            .map(|identifier| identifier.node.name.as_str()),
+        lang_c::ast::TypeSpecifier::TypedefName(identifier) => Some(&identifier.node.name),
+        _ => None,
+    }
+}
+
+// Whether an expression calls the function assert reports a failed assertion with
+fn is_assertion_failure(expression: &lang_c::ast::Expression) -> bool {
+    matches!(
+        expression,
+        lang_c::ast::Expression::Call(call)
+            if matches!(
+                &call.node.callee.node,
+                lang_c::ast::Expression::Identifier(identifier)
+                    if ASSERTION_FAILURE_FUNCTIONS.contains(&identifier.node.name.as_str())
+            )
+    )
+}
+
+// Names of the counter variables declared or assigned in a for-loop initializer
+fn loop_counters(initializer: &lang_c::ast::ForInitializer) -> Vec<String> {
+    match initializer {
+        lang_c::ast::ForInitializer::Declaration(declaration) => declaration
+            .node
+            .declarators
+            .iter()
+            .filter_map(
+                |init_declarator| match &init_declarator.node.declarator.node.kind.node {
+                    lang_c::ast::DeclaratorKind::Identifier(identifier) => {
+                        Some(identifier.node.name.clone())
+                    }
+                    _ => None,
+                },
+            )
+            .collect(),
+        lang_c::ast::ForInitializer::Expression(expression) => match &expression.node {
+            lang_c::ast::Expression::BinaryOperator(binary_operator_expression)
+                if binary_operator_expression.node.operator.node
+                    == lang_c::ast::BinaryOperator::Assign =>
+            {
+                match &binary_operator_expression.node.lhs.node {
+                    lang_c::ast::Expression::Identifier(identifier) => {
+                        vec![identifier.node.name.clone()]
+                    }
+                    _ => Vec::new(),
+                }
+            }
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+// Whether a loop condition is bounded, given whether each of its comparisons is: a
+// conjunction such as `i < MAX && !done` is bounded by either side, a disjunction
+// only by both
+fn is_bounded_condition(
+    condition: &lang_c::ast::Expression,
+    is_bound: &dyn Fn(&lang_c::ast::Expression) -> bool,
+) -> bool {
+    let lang_c::ast::Expression::BinaryOperator(binary_operator_expression) = condition else {
+        return is_bound(condition);
+    };
+    let lhs = &binary_operator_expression.node.lhs.node;
+    let rhs = &binary_operator_expression.node.rhs.node;
+    match binary_operator_expression.node.operator.node {
+        lang_c::ast::BinaryOperator::LogicalAnd => {
+            is_bounded_condition(lhs, is_bound) || is_bounded_condition(rhs, is_bound)
+        }
+        lang_c::ast::BinaryOperator::LogicalOr => {
+            is_bounded_condition(lhs, is_bound) && is_bounded_condition(rhs, is_bound)
+        }
+        _ => is_bound(condition),
+    }
+}
+
+// Operands of a chain of && and || operators, in source order
+fn logical_operands(expression: &lang_c::ast::Expression) -> Vec<&lang_c::ast::Expression> {
+    match expression {
+        lang_c::ast::Expression::BinaryOperator(binary_operator_expression)
+            if matches!(
+                binary_operator_expression.node.operator.node,
+                lang_c::ast::BinaryOperator::LogicalAnd | lang_c::ast::BinaryOperator::LogicalOr
+            ) =>
+        {
+            let mut operands = logical_operands(&binary_operator_expression.node.lhs.node);
+            operands.extend(logical_operands(&binary_operator_expression.node.rhs.node));
+            operands
+        }
+        _ => vec![expression],
+    }
+}
+
+// Way a for-loop increment clause moves the counter, if it moves it by a constant
+// step: `i++`, `i += 2` and `i = i + 2` move it up, their decrementing forms down
+fn step_direction(step: &lang_c::ast::Expression, counter: &str) -> Option<Direction> {
+    match step {
+        lang_c::ast::Expression::Comma(expressions) => expressions
+            .iter()
+            .find_map(|expression| step_direction(&expression.node, counter)),
+        lang_c::ast::Expression::UnaryOperator(unary_operator_expression) => {
+            unary_step(&unary_operator_expression.node, counter)
+        }
+        lang_c::ast::Expression::BinaryOperator(binary_operator_expression) => {
+            binary_step(&binary_operator_expression.node, counter)
+        }
+        _ => None,
+    }
+}
+
+// Way an increment or decrement moves the counter, if it applies to the counter
+fn unary_step(
+    unary_operator_expression: &lang_c::ast::UnaryOperatorExpression,
+    counter: &str,
+) -> Option<Direction> {
+    if !is_variable(&unary_operator_expression.operand.node, counter) {
+        return None;
+    }
+    match unary_operator_expression.operator.node {
+        lang_c::ast::UnaryOperator::PreIncrement | lang_c::ast::UnaryOperator::PostIncrement => {
+            Some(Direction::Up)
+        }
+        lang_c::ast::UnaryOperator::PreDecrement | lang_c::ast::UnaryOperator::PostDecrement => {
+            Some(Direction::Down)
+        }
+        _ => None,
+    }
+}
+
+// Way an assignment to the counter moves it, if it adds or subtracts a positive constant
+fn binary_step(
+    binary_operator_expression: &lang_c::ast::BinaryOperatorExpression,
+    counter: &str,
+) -> Option<Direction> {
+    let is_positive = |expression: &lang_c::ast::Expression| {
+        constant_expression_value(expression).is_some_and(|value| value > 0)
+    };
+    if !is_variable(&binary_operator_expression.lhs.node, counter) {
+        return None;
+    }
+
+    let rhs = &binary_operator_expression.rhs.node;
+    match binary_operator_expression.operator.node {
+        lang_c::ast::BinaryOperator::AssignPlus if is_positive(rhs) => Some(Direction::Up),
+        lang_c::ast::BinaryOperator::AssignMinus if is_positive(rhs) => Some(Direction::Down),
+        lang_c::ast::BinaryOperator::Assign => {
+            let lang_c::ast::Expression::BinaryOperator(value) = rhs else {
+                return None;
+            };
+            if !is_variable(&value.node.lhs.node, counter) || !is_positive(&value.node.rhs.node) {
+                return None;
+            }
+            match value.node.operator.node {
+                lang_c::ast::BinaryOperator::Plus => Some(Direction::Up),
+                lang_c::ast::BinaryOperator::Minus => Some(Direction::Down),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+// Whether an expression is the named variable
+fn is_variable(expression: &lang_c::ast::Expression, name: &str) -> bool {
+    matches!(
+        expression,
+        lang_c::ast::Expression::Identifier(identifier) if identifier.node.name == name
+    )
+}
+
+pub(crate) fn is_assignment_operator(operator: &lang_c::ast::BinaryOperator) -> bool {
+    matches!(
+        operator,
+        lang_c::ast::BinaryOperator::Assign
+            | lang_c::ast::BinaryOperator::AssignMultiply
+            | lang_c::ast::BinaryOperator::AssignDivide
+            | lang_c::ast::BinaryOperator::AssignModulo
+            | lang_c::ast::BinaryOperator::AssignPlus
+            | lang_c::ast::BinaryOperator::AssignMinus
+            | lang_c::ast::BinaryOperator::AssignShiftLeft
+            | lang_c::ast::BinaryOperator::AssignShiftRight
+            | lang_c::ast::BinaryOperator::AssignBitwiseAnd
+            | lang_c::ast::BinaryOperator::AssignBitwiseXor
+            | lang_c::ast::BinaryOperator::AssignBitwiseOr
+    )
+}
+
+// Names of the parameters of a function declarator, empty for unnamed parameters
+fn function_parameters(declarator: &lang_c::ast::Declarator) -> Vec<String> {
+    declarator
+        .derived
+        .iter()
+        .find_map(|derived| match &derived.node {
+            lang_c::ast::DerivedDeclarator::Function(function_declarator) => Some(
+                function_declarator
+                    .node
+                    .parameters
+                    .iter()
+                    .map(|parameter| {
+                        parameter
+                            .node
+                            .declarator
+                            .as_ref()
+                            .and_then(|declarator| declarator_name(&declarator.node))
+                            .unwrap_or_default()
+                            .to_string()
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+// Names of the parameters declared as pointers or arrays
+fn pointer_parameters(declarator: &lang_c::ast::Declarator) -> Vec<String> {
+    let Some(function_declarator) =
+        declarator
+            .derived
+            .iter()
+            .find_map(|derived| match &derived.node {
+                lang_c::ast::DerivedDeclarator::Function(function_declarator) => {
+                    Some(function_declarator)
+                }
+                _ => None,
+            })
+    else {
+        return Vec::new();
+    };
+
+    function_declarator
+        .node
+        .parameters
+        .iter()
+        .filter_map(|parameter| parameter.node.declarator.as_ref())
+        .filter(|declarator| {
+            matches!(
+                derivations(&declarator.node).first(),
+                Some(
+                    lang_c::ast::DerivedDeclarator::Pointer(_)
+                        | lang_c::ast::DerivedDeclarator::Array(_)
+                )
+            )
+        })
+        .filter_map(|declarator| declarator_name(&declarator.node))
+        .map(str::to_string)
+        .collect()
+}
+
+// Whether the declaration specifiers include the static storage class
+pub(crate) fn is_static(
+    specifiers: &[lang_c::span::Node<lang_c::ast::DeclarationSpecifier>],
+) -> bool {
+    specifiers.iter().any(|specifier| {
+        matches!(
+            &specifier.node,
+            lang_c::ast::DeclarationSpecifier::StorageClass(storage_class)
+                if matches!(storage_class.node, lang_c::ast::StorageClassSpecifier::Static)
+        )
+    })
+}
+
+// Whether the declaration only refers to a variable defined elsewhere or names a type
+fn is_extern_or_typedef(
+    specifiers: &[lang_c::span::Node<lang_c::ast::DeclarationSpecifier>],
+) -> bool {
+    specifiers.iter().any(|specifier| {
+        matches!(
+            &specifier.node,
+            lang_c::ast::DeclarationSpecifier::StorageClass(storage_class)
+                if matches!(
+                    storage_class.node,
+                    lang_c::ast::StorageClassSpecifier::Extern
+                        | lang_c::ast::StorageClassSpecifier::Typedef
+                )
+        )
+    })
+}
+
+// Whether a declarator declares a variable that can be modified: not a function, and
+// neither a const object nor an array of them. For pointers the pointer itself has
+// to be const, `const char *p` can still be pointed elsewhere.
+fn is_mutable_data(
+    specifiers: &[lang_c::span::Node<lang_c::ast::DeclarationSpecifier>],
+    declarator: &lang_c::ast::Declarator,
+) -> bool {
+    for derived in derivations(declarator) {
+        match derived {
+            lang_c::ast::DerivedDeclarator::Array(_) => continue,
+            lang_c::ast::DerivedDeclarator::Pointer(qualifiers) => {
+                return !qualifiers.iter().any(|qualifier| {
+                    matches!(
+                        &qualifier.node,
+                        lang_c::ast::PointerQualifier::TypeQualifier(qualifier)
+                            if matches!(qualifier.node, lang_c::ast::TypeQualifier::Const)
+                    )
+                });
+            }
+            _ => return false,
+        }
+    }
+    !specifiers.iter().any(|specifier| {
+        matches!(
+            &specifier.node,
+            lang_c::ast::DeclarationSpecifier::TypeQualifier(qualifier)
+                if matches!(qualifier.node, lang_c::ast::TypeQualifier::Const)
+        )
+    })
+}
+
+// Whether an initializer only holds literals and constant expressions, including
+// initializer lists such as `{0}`
+fn is_constant_initializer(initializer: &lang_c::ast::Initializer) -> bool {
+    match initializer {
+        lang_c::ast::Initializer::Expression(expression) => {
+            matches!(expression.node, lang_c::ast::Expression::Constant(_))
+                || constant_expression_value(&expression.node).is_some()
+        }
+        lang_c::ast::Initializer::List(items) => items
+            .iter()
+            .all(|item| is_constant_initializer(&item.node.initializer.node)),
+    }
+}
+
+// Blocks nested in a statement that could declare a variable used nowhere else: a
+// compound statement, the braced branches of an if and a whole for statement
+fn nested_scopes(statement: &lang_c::span::Node<lang_c::ast::Statement>) -> Vec<Span> {
+    let braced = |branch: &lang_c::span::Node<lang_c::ast::Statement>| {
+        matches!(branch.node, lang_c::ast::Statement::Compound(_)).then_some(branch.span)
+    };
+    match &statement.node {
+        lang_c::ast::Statement::Compound(_) | lang_c::ast::Statement::For(_) => {
+            vec![statement.span]
+        }
+        lang_c::ast::Statement::If(if_statement) => {
+            let else_statement = if_statement.node.else_statement.as_deref();
+            braced(&*if_statement.node.then_statement)
+                .into_iter()
+                .chain(else_statement.and_then(braced))
+                .collect()
+        }
+        lang_c::ast::Statement::Labeled(labeled_statement) => {
+            nested_scopes(&labeled_statement.node.statement)
+        }
+        _ => Vec::new(),
+    }
+}
+
+// Whether the declaration specifiers include the volatile qualifier
+fn is_volatile(specifiers: &[lang_c::span::Node<lang_c::ast::DeclarationSpecifier>]) -> bool {
+    specifiers.iter().any(|specifier| {
+        matches!(
+            &specifier.node,
+            lang_c::ast::DeclarationSpecifier::TypeQualifier(qualifier)
+                if matches!(qualifier.node, lang_c::ast::TypeQualifier::Volatile)
+        )
+    })
+}
+
+// Whether declaration specifiers make the declared object, or the pointee of a
+// pointer, const
+pub(crate) fn is_const(
+    specifiers: &[lang_c::span::Node<lang_c::ast::DeclarationSpecifier>],
+) -> bool {
+    specifiers.iter().any(|specifier| {
+        matches!(
+            &specifier.node,
+            lang_c::ast::DeclarationSpecifier::TypeQualifier(qualifier)
+                if matches!(qualifier.node, lang_c::ast::TypeQualifier::Const)
+        )
+    })
+}
+
+// Whether a declarator declares a function taking a variable number of arguments
+fn is_variadic_declarator(declarator: &lang_c::ast::Declarator) -> bool {
+    declarator.derived.iter().any(|derived| {
+        matches!(
+            &derived.node,
+            lang_c::ast::DerivedDeclarator::Function(function_declarator)
+                if matches!(function_declarator.node.ellipsis, lang_c::ast::Ellipsis::Some)
+        )
+    })
+}
+
+// Name declared by a declarator, looking through parenthesized declarators
+pub(crate) fn declarator_name(declarator: &lang_c::ast::Declarator) -> Option<&str> {
+    match &declarator.kind.node {
+        lang_c::ast::DeclaratorKind::Identifier(identifier) => Some(&identifier.node.name),
+        lang_c::ast::DeclaratorKind::Declarator(inner) => declarator_name(&inner.node),
+        lang_c::ast::DeclaratorKind::Abstract => None,
+    }
+}
+
+// Shortest cycle of two or more functions through the given one, as each caller with
+// the site of its call to the next function, found breadth-first
+fn shortest_call_cycle(
+    call_graph: &BTreeMap<String, Vec<(String, Span)>>,
+    function: &str,
+) -> Option<Vec<(String, Span)>> {
+    let mut callers: HashMap<&str, (&str, Span)> = HashMap::new();
+    let mut queue = VecDeque::from([function]);
+    while let Some(caller) = queue.pop_front() {
+        for (callee, span) in call_graph.get(caller).into_iter().flatten() {
+            if callee == function && caller != function {
+                // Walk back from the last call to the function the cycle starts at
+                let mut cycle = vec![(caller.to_string(), *span)];
+                let mut current = caller;
+                while current != function {
+                    let (previous, span) = callers[current];
+                    cycle.push((previous.to_string(), span));
+                    current = previous;
+                }
+                cycle.reverse();
+                return Some(cycle);
+            }
+            if callee == function || callers.contains_key(callee.as_str()) {
+                continue;
+            }
+            callers.insert(callee, (caller, *span));
+            queue.push_back(callee);
+        }
+    }
+    None
+}
+
+// Parse `@recursion max_depth=N measure=param` out of a comment block
+fn parse_recursion_annotation(comment: &str) -> Option<RecursionAnnotation> {
+    let (_, annotation) = comment.split_once("@recursion")?;
+
+    let mut max_depth = None;
+    let mut measure = None;
+    for token in annotation.split(|c: char| c.is_whitespace() || c == ',') {
+        match token.trim_end_matches("*/").split_once('=') {
+            Some(("max_depth", value)) => max_depth = value.parse().ok(),
+            Some(("measure", value)) => measure = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    Some(RecursionAnnotation {
+        _max_depth: max_depth?,
+        measure: measure?,
+    })
+}
+
+// Iteration count declared after the marker in a comment, e.g. 128 for `loop-bound: 128`
+pub(crate) fn parse_loop_bound(comment: &str, marker: &str) -> Option<u64> {
+    let (_, annotation) = comment.split_once(marker)?;
+    let bound: String = annotation
+        .trim_start()
+        .strip_prefix(':')?
+        .trim_start()
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect();
+    bound.parse().ok()
+}
+
+// Whether an expression is `parameter - k`, `parameter >> k` (k >= 1) or `parameter / k` (k >= 2)
+fn is_strictly_reduced(expression: &lang_c::ast::Expression, parameter: &str) -> bool {
+    let lang_c::ast::Expression::BinaryOperator(binary_operator_expression) = expression else {
+        return false;
+    };
+    let lang_c::ast::Expression::Identifier(identifier) = &binary_operator_expression.node.lhs.node
+    else {
+        return false;
+    };
+    if identifier.node.name != parameter {
+        return false;
+    }
+    let Some(value) = integer_constant_value(&binary_operator_expression.node.rhs.node) else {
+        return false;
+    };
+
+    match binary_operator_expression.node.operator.node {
+        lang_c::ast::BinaryOperator::Minus | lang_c::ast::BinaryOperator::ShiftRight => value >= 1,
+        lang_c::ast::BinaryOperator::Divide => value >= 2,
+        _ => false,
+    }
+}
+
+// Value of an integer literal, if the expression is one
+fn integer_constant_value(expression: &lang_c::ast::Expression) -> Option<u64> {
+    let lang_c::ast::Expression::Constant(constant) = expression else {
+        return None;
+    };
+    let lang_c::ast::Constant::Integer(integer) = &constant.node else {
+        return None;
+    };
+
+    let radix = match integer.base {
+        lang_c::ast::IntegerBase::Decimal => 10,
+        lang_c::ast::IntegerBase::Octal => 8,
+        lang_c::ast::IntegerBase::Hexadecimal => 16,
+        lang_c::ast::IntegerBase::Binary => 2,
+    };
+    if integer.number.is_empty() {
+        return Some(0);
+    }
+    u64::from_str_radix(&integer.number, radix).ok()
+}
+
+// Value of an integer constant expression made of literals and arithmetic, e.g. `4 * 16`
+fn constant_expression_value(expression: &lang_c::ast::Expression) -> Option<u64> {
+    let lang_c::ast::Expression::BinaryOperator(binary_operator_expression) = expression else {
+        return integer_constant_value(expression);
+    };
+    fold_binary(
+        &binary_operator_expression.node.operator.node,
+        constant_expression_value(&binary_operator_expression.node.lhs.node)?,
+        constant_expression_value(&binary_operator_expression.node.rhs.node)?,
+    )
+}
+
+// Apply an arithmetic operator to two constants, None on overflow or other operators
+fn fold_binary(operator: &lang_c::ast::BinaryOperator, lhs: u64, rhs: u64) -> Option<u64> {
+    match operator {
+        lang_c::ast::BinaryOperator::Plus => lhs.checked_add(rhs),
+        lang_c::ast::BinaryOperator::Minus => lhs.checked_sub(rhs),
+        lang_c::ast::BinaryOperator::Multiply => lhs.checked_mul(rhs),
+        lang_c::ast::BinaryOperator::Divide => lhs.checked_div(rhs),
+        lang_c::ast::BinaryOperator::ShiftLeft => lhs.checked_shl(u32::try_from(rhs).ok()?),
+        _ => None,
+    }
+}
+
+// Array and constant element offset of a `&array[k]` or `array + k` expression
+fn offset_into_array(expression: &lang_c::ast::Expression) -> Option<(&str, u64)> {
+    let offset_expression = match expression {
+        lang_c::ast::Expression::UnaryOperator(unary_operator_expression)
+            if matches!(
+                unary_operator_expression.node.operator.node,
+                lang_c::ast::UnaryOperator::Address
+            ) =>
+        {
+            let lang_c::ast::Expression::BinaryOperator(index_expression) =
+                &unary_operator_expression.node.operand.node
+            else {
+                return None;
+            };
+            if !matches!(
+                index_expression.node.operator.node,
+                lang_c::ast::BinaryOperator::Index
+            ) {
+                return None;
+            }
+            index_expression
+        }
+        lang_c::ast::Expression::BinaryOperator(binary_operator_expression)
+            if matches!(
+                binary_operator_expression.node.operator.node,
+                lang_c::ast::BinaryOperator::Plus
+            ) =>
+        {
+            binary_operator_expression
+        }
+        _ => return None,
+    };
+
+    let lang_c::ast::Expression::Identifier(array) = &offset_expression.node.lhs.node else {
+        return None;
+    };
+    let offset = constant_expression_value(&offset_expression.node.rhs.node)?;
+    Some((&array.node.name, offset))
+}
+
+// Number of elements of an array declarator with a constant length
+fn array_length(array_declarator: &lang_c::ast::ArrayDeclarator) -> Option<u64> {
+    match &array_declarator.size {
+        lang_c::ast::ArraySize::VariableExpression(expression)
+        | lang_c::ast::ArraySize::StaticExpression(expression) => {
+            constant_expression_value(&expression.node)
+        }
+        _ => None,
+    }
+}
+
+// Type specifiers of a declaration, e.g. `unsigned` and `long` for `unsigned long x`
+pub(crate) fn type_specifiers(
+    specifiers: &[lang_c::span::Node<lang_c::ast::DeclarationSpecifier>],
+) -> Vec<&lang_c::ast::TypeSpecifier> {
+    specifiers
+        .iter()
+        .filter_map(|specifier| match &specifier.node {
+            lang_c::ast::DeclarationSpecifier::TypeSpecifier(type_specifier) => {
+                Some(&type_specifier.node)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+// Key of a struct or union tag in the type sizes, e.g. "struct packet"
+fn struct_tag(struct_type: &lang_c::ast::StructType) -> Option<String> {
+    let kind = match struct_type.kind.node {
+        lang_c::ast::StructKind::Struct => "struct",
+        lang_c::ast::StructKind::Union => "union",
+    };
+    let identifier = struct_type.identifier.as_ref()?;
+    Some(format!("{} {}", kind, identifier.node.name))
+}
+
+// Size in bytes of a basic type on the usual 32 and 64-bit targets, None where it varies
+fn type_size(type_specifier: &lang_c::ast::TypeSpecifier) -> Option<u64> {
+    match type_specifier {
+        lang_c::ast::TypeSpecifier::Char | lang_c::ast::TypeSpecifier::Bool => Some(1),
+        lang_c::ast::TypeSpecifier::Short => Some(2),
+        lang_c::ast::TypeSpecifier::Int | lang_c::ast::TypeSpecifier::Float => Some(4),
+        lang_c::ast::TypeSpecifier::Double => Some(8),
+        _ => None,
+    }
+}
+
+// Whether an expression takes sizeof of the named variable anywhere, e.g. `sizeof(buf) - 1`
+fn mentions_size_of(expression: &lang_c::ast::Expression, name: &str) -> bool {
+    match expression {
+        lang_c::ast::Expression::SizeOfVal(size_of) => matches!(
+            &size_of.node.0.node,
+            lang_c::ast::Expression::Identifier(identifier) if identifier.node.name == name
+        ),
+        lang_c::ast::Expression::BinaryOperator(binary_operator_expression) => {
+            mentions_size_of(&binary_operator_expression.node.lhs.node, name)
+                || mentions_size_of(&binary_operator_expression.node.rhs.node, name)
+        }
+        _ => false,
+    }
+}
+
+// Variable a `sizeof(variable)` expression takes the size of
+fn size_of_operand(expression: &lang_c::ast::Expression) -> Option<&str> {
+    let lang_c::ast::Expression::SizeOfVal(size_of) = expression else {
+        return None;
+    };
+    let lang_c::ast::Expression::Identifier(identifier) = &size_of.node.0.node else {
+        return None;
+    };
+    Some(&identifier.node.name)
+}
+
+// Derived declarators in the order they apply to the declared name, from the
+// innermost parenthesized declarator outwards, e.g. pointer then function for `(*f)(int)`
+fn derivations(declarator: &lang_c::ast::Declarator) -> Vec<&lang_c::ast::DerivedDeclarator> {
+    let mut derivations = match &declarator.kind.node {
+        lang_c::ast::DeclaratorKind::Declarator(inner) => derivations(&inner.node),
+        _ => Vec::new(),
+    };
+    derivations.extend(declarator.derived.iter().map(|derived| &derived.node));
+    derivations
+}
+
+// Pointer an expression dereferences with * or ->, looking through `.` member
+// accesses so `p->q.r->s` counts as two levels
+fn dereferenced_operand(expression: &lang_c::ast::Expression) -> Option<&lang_c::ast::Expression> {
+    match expression {
+        lang_c::ast::Expression::UnaryOperator(unary)
+            if matches!(
+                unary.node.operator.node,
+                lang_c::ast::UnaryOperator::Indirection
+            ) =>
+        {
+            Some(&unary.node.operand.node)
+        }
+        lang_c::ast::Expression::Member(member) => match member.node.operator.node {
+            lang_c::ast::MemberOperator::Indirect => Some(&member.node.expression.node),
+            lang_c::ast::MemberOperator::Direct => {
+                dereferenced_operand(&member.node.expression.node)
+            }
+        },
+        _ => None,
+    }
+}
+
+// Count calls to assert and the given assertion macros, skipping comments and literals
+fn count_assertion_calls(source: &str, assertion_macros: &[String]) -> usize {
+    let bytes = source.as_bytes();
+    let mut count = 0;
+    let mut index = 0;
+    while index < bytes.len() {
+        let rest = &bytes[index..];
+        if rest.starts_with(b"//") {
+            index += rest
+                .iter()
+                .position(|&byte| byte == b'\n')
+                .unwrap_or(rest.len());
+        } else if rest.starts_with(b"/*") {
+            index += rest
+                .windows(2)
+                .skip(2)
+                .position(|window| window == b"*/")
+                .map_or(rest.len(), |end| end + 4);
+        } else if rest[0] == b'"' || rest[0] == b'\'' {
+            // Skip to the closing quote, stepping over escaped characters
+            let quote = rest[0];
+            index += 1;
+            while index < bytes.len() && bytes[index] != quote && bytes[index] != b'\n' {
+                index += if bytes[index] == b'\\' { 2 } else { 1 };
+            }
+            index += 1;
+        } else if rest[0].is_ascii_alphabetic() || rest[0] == b'_' {
+            let length = rest
+                .iter()
+                .position(|byte| !(byte.is_ascii_alphanumeric() || *byte == b'_'))
+                .unwrap_or(rest.len());
+            let identifier = &source[index..index + length];
+            index += length;
+            let is_call = source[index..].trim_start().starts_with('(');
+            if is_call
+                && (identifier == "assert"
+                    || assertion_macros.iter().any(|name| name == identifier))
+            {
+                count += 1;
+            }
+        } else {
+            index += 1;
+        }
+    }
+    count
+}
+
+// Count source lines, optionally skipping blank lines and lines that only contain comments
+pub(crate) fn count_source_lines(
+    lines: &[&str],
+    count_blank_lines: bool,
+    count_comment_lines: bool,
+) -> usize {
+    let mut in_block_comment = false;
+    let mut count = 0;
+
+    for line in lines {
+        if line.trim().is_empty() {
+            count += usize::from(count_blank_lines);
+            continue;
+        }
+
+        let mut has_code = false;
+        let mut rest: &str = line;
+        loop {
+            if in_block_comment {
+                let Some(end) = rest.find("*/") else {
+                    break;
+                };
+                in_block_comment = false;
+                rest = &rest[end + 2..];
+                continue;
+            }
+
+            rest = rest.trim_start();
+            if rest.is_empty() || rest.starts_with("//") {
+                break;
+            }
+            if let Some(comment) = rest.strip_prefix("/*") {
+                in_block_comment = true;
+                rest = comment;
+                continue;
+            }
+
+            // Skip over the code to the next block comment on this line, if any
+            has_code = true;
+            match (rest.find("//"), rest.find("/*")) {
+                (Some(line_comment), Some(block_comment)) if line_comment < block_comment => break,
+                (_, Some(block_comment)) => rest = &rest[block_comment..],
+                (_, None) => break,
+            }
+        }
+
+        if has_code || count_comment_lines {
+            count += 1;
+        }
+    }
+
+    count
+}
+
+fn is_floating_type(type_specifier: &lang_c::ast::TypeSpecifier) -> bool {
+    matches!(
+        type_specifier,
+        lang_c::ast::TypeSpecifier::Float
+            | lang_c::ast::TypeSpecifier::Double
+            | lang_c::ast::TypeSpecifier::TS18661Float(_)
+    )
+}
+
+// Whether the type specifiers of a declaration name a signed integer type, in any
+// order, e.g. not `long unsigned`. Plain char counts, whether it is signed is up to
+// the compiler.
+fn is_signed_type(type_specifiers: &[&lang_c::ast::TypeSpecifier]) -> bool {
+    if scalar_type(type_specifiers) == Some(ScalarType::Floating)
+        || type_specifiers
+            .iter()
+            .any(|type_specifier| matches!(type_specifier, lang_c::ast::TypeSpecifier::Unsigned))
+    {
+        return false;
+    }
+    type_specifiers
+        .iter()
+        .any(|type_specifier| match type_specifier {
+            lang_c::ast::TypeSpecifier::Char
+            | lang_c::ast::TypeSpecifier::Short
+            | lang_c::ast::TypeSpecifier::Int
+            | lang_c::ast::TypeSpecifier::Long
+            | lang_c::ast::TypeSpecifier::Signed => true,
+            lang_c::ast::TypeSpecifier::TypedefName(identifier) => {
+                let name = identifier.node.name.as_str();
+                (name.starts_with("int") && name.ends_with("_t"))
+                    || name == "ssize_t"
+                    || name == "ptrdiff_t"
+            }
+            _ => false,
+        })
+}
+
+// Size in bytes of an integer typedef name from stdint.h or stddef.h
+fn integer_typedef_bytes(name: &str) -> Option<u64> {
+    INTEGER_TYPEDEFS
+        .iter()
+        .find(|(typedef_name, _)| *typedef_name == name)
+        .map(|&(_, bytes)| bytes)
+}
+
+// Size in bytes and name of an integer type, None for other types and for _Bool and
+// enums, whose values do not get narrowed in practice
+fn integer_type_of(type_specifiers: &[&lang_c::ast::TypeSpecifier]) -> Option<(u64, String)> {
+    let mut bytes = 4; // int, and signed or unsigned on their own
+    for type_specifier in type_specifiers {
+        match type_specifier {
+            lang_c::ast::TypeSpecifier::Char => bytes = 1,
+            lang_c::ast::TypeSpecifier::Short => bytes = 2,
+            lang_c::ast::TypeSpecifier::Long => bytes = POINTER_BYTES,
+            lang_c::ast::TypeSpecifier::Int
+            | lang_c::ast::TypeSpecifier::Signed
+            | lang_c::ast::TypeSpecifier::Unsigned => {}
+            lang_c::ast::TypeSpecifier::TypedefName(identifier) => {
+                bytes = integer_typedef_bytes(&identifier.node.name)?;
+            }
+            _ => return None,
+        }
+    }
+    if type_specifiers.is_empty() {
+        return None;
+    }
+    let name: Vec<String> = type_specifiers
+        .iter()
+        .map(|type_specifier| type_specifier_text(type_specifier))
+        .collect();
+    Some((bytes, name.join(" ")))
+}
+
+// The wider of two integer types, or the one that is known
+fn wider_integer_type(
+    lhs: Option<(u64, String)>,
+    rhs: Option<(u64, String)>,
+) -> Option<(u64, String)> {
+    match (lhs, rhs) {
+        (Some(lhs), Some(rhs)) => Some(if rhs.0 > lhs.0 { rhs } else { lhs }),
+        (lhs, rhs) => lhs.or(rhs),
+    }
+}
+
+// Arithmetic type named by all the type specifiers of a declaration, e.g. `long double`,
+// None for other and unknown types
+pub(crate) fn scalar_type(type_specifiers: &[&lang_c::ast::TypeSpecifier]) -> Option<ScalarType> {
+    if type_specifiers
+        .iter()
+        .any(|type_specifier| is_floating_type(type_specifier))
+    {
+        return Some(ScalarType::Floating);
+    }
+    match type_specifiers {
+        [lang_c::ast::TypeSpecifier::Bool | lang_c::ast::TypeSpecifier::Enum(_)] => {
+            Some(ScalarType::Integer)
+        }
+        // char in any signedness, and 1 byte typedefs such as uint8_t
+        _ => match integer_type_of(type_specifiers)? {
+            (1, _) => Some(ScalarType::Character),
+            _ => Some(ScalarType::Integer),
+        },
+    }
+}
+
+// Type specifier as written in the source, e.g. "unsigned" or "enum mode"
+pub(crate) fn type_specifier_text(type_specifier: &lang_c::ast::TypeSpecifier) -> String {
+    match type_specifier {
+        lang_c::ast::TypeSpecifier::Void => "void".to_string(),
+        lang_c::ast::TypeSpecifier::Char => "char".to_string(),
+        lang_c::ast::TypeSpecifier::Short => "short".to_string(),
+        lang_c::ast::TypeSpecifier::Int => "int".to_string(),
+        lang_c::ast::TypeSpecifier::Long => "long".to_string(),
+        lang_c::ast::TypeSpecifier::Float => "float".to_string(),
+        lang_c::ast::TypeSpecifier::Double => "double".to_string(),
+        lang_c::ast::TypeSpecifier::Signed => "signed".to_string(),
+        lang_c::ast::TypeSpecifier::Unsigned => "unsigned".to_string(),
+        lang_c::ast::TypeSpecifier::Bool => "_Bool".to_string(),
+        lang_c::ast::TypeSpecifier::Enum(enum_type) => match &enum_type.node.identifier {
+            Some(identifier) => format!("enum {}", identifier.node.name),
+            None => "enum".to_string(),
+        },
+        lang_c::ast::TypeSpecifier::TypedefName(identifier) => identifier.node.name.clone(),
+        _ => "<type>".to_string(),
+    }
+}
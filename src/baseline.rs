@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::diagnostic::Diagnostic;
+use crate::error::AnalyzerError;
+
+// A previously accepted finding. Line numbers are kept for readability only,
+// matching is done on rule, file and message so that unrelated edits which
+// shift code around do not resurface old findings.
+#[derive(Serialize, Deserialize)]
+pub struct BaselineEntry {
+    pub rule: String,
+    pub file: String,
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Baseline {
+    pub findings: Vec<BaselineEntry>,
+}
+
+impl Baseline {
+    pub fn from_diagnostics(diagnostics: &[Diagnostic]) -> Self {
+        let findings = diagnostics
+            .iter()
+            .map(|diagnostic| BaselineEntry {
+                rule: diagnostic.rule.clone(),
+                file: diagnostic.file.clone(),
+                line: diagnostic.line,
+                message: diagnostic.message.clone(),
+            })
+            .collect();
+        Baseline { findings }
+    }
+
+    // Drop every diagnostic that is accounted for by the baseline. Each baseline
+    // entry suppresses at most one diagnostic, so adding a second identical
+    // violation to a file is still reported.
+    pub fn filter_new(&self, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        let mut remaining: HashMap<(String, String, String), usize> = HashMap::new();
+        for entry in &self.findings {
+            let key = (
+                entry.rule.clone(),
+                entry.file.clone(),
+                entry.message.clone(),
+            );
+            *remaining.entry(key).or_insert(0) += 1;
+        }
+
+        diagnostics
+            .into_iter()
+            .filter(|diagnostic| {
+                let key = (
+                    diagnostic.rule.clone(),
+                    diagnostic.file.clone(),
+                    diagnostic.message.clone(),
+                );
+                match remaining.get_mut(&key) {
+                    Some(count) if *count > 0 => {
+                        *count -= 1;
+                        false
+                    }
+                    _ => true,
+                }
+            })
+            .collect()
+    }
+}
+
+pub fn load_baseline(file_path: &str) -> Result<Baseline, AnalyzerError> {
+    let file_content = fs::read_to_string(file_path).map_err(|error| AnalyzerError::ConfigIo {
+        path: Path::new(file_path).to_path_buf(),
+        error,
+    })?;
+    serde_json::from_str(&file_content).map_err(|error| AnalyzerError::ConfigParse {
+        location: file_path.to_string(),
+        message: error.to_string(),
+    })
+}
+
+pub fn write_baseline(file_path: &str, diagnostics: &[Diagnostic]) -> Result<(), AnalyzerError> {
+    let baseline = Baseline::from_diagnostics(diagnostics);
+    serde_json::to_string_pretty(&baseline)
+        .map_err(io::Error::other)
+        .and_then(|file_content| fs::write(file_path, file_content))
+        .map_err(|error| AnalyzerError::OutputWrite {
+            path: Path::new(file_path).to_path_buf(),
+            error,
+        })
+}
@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+use std::process::Command;
+
+use crate::diagnostic::Diagnostic;
+
+// Commit that last changed a source line, according to git blame
+#[derive(Debug, Clone)]
+pub struct BlameLine {
+    pub commit: String,
+    pub author: String,
+    pub time: u64, // Author time in seconds since the Unix epoch
+}
+
+// Blame information of the files findings were reported in, loaded on first use.
+// Files outside a git repository have no blame information.
+#[derive(Default)]
+pub struct BlameIndex {
+    files: HashMap<String, Option<Vec<BlameLine>>>,
+}
+
+impl BlameIndex {
+    pub fn line(&mut self, file: &str, line: usize) -> Option<&BlameLine> {
+        self.files
+            .entry(file.to_string())
+            .or_insert_with(|| blame_file(file))
+            .as_ref()?
+            .get(line.checked_sub(1)?)
+    }
+
+    pub fn diagnostic(&mut self, diagnostic: &Diagnostic) -> Option<&BlameLine> {
+        self.line(&diagnostic.file, diagnostic.line)
+    }
+}
+
+// Run git blame on a file, one entry per line of the file
+fn blame_file(file: &str) -> Option<Vec<BlameLine>> {
+    let output = Command::new("git")
+        .args(["blame", "--line-porcelain", "--", file])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let mut lines = Vec::new();
+    let mut commit = None;
+    let mut author = String::new();
+    let mut time = 0;
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if line.starts_with('\t') {
+            // The source line itself ends each entry
+            lines.push(BlameLine {
+                commit: commit.take().unwrap_or_default(),
+                author: std::mem::take(&mut author),
+                time,
+            });
+        } else if let Some(name) = line.strip_prefix("author ") {
+            author = name.to_string();
+        } else if let Some(seconds) = line.strip_prefix("author-time ") {
+            time = seconds.parse().unwrap_or_default();
+        } else if commit.is_none() {
+            commit = line.split_whitespace().next().map(str::to_string);
+        }
+    }
+
+    Some(lines)
+}
+
+// Seconds since the Unix epoch at the start of a YYYY-MM-DD date, in UTC
+pub fn parse_date(date: &str) -> Option<u64> {
+    let mut parts = date.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (parts.next()??, parts.next()??, parts.next()??);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // Days from civil, counting years from March so leap days fall at the end
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = (month + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    u64::try_from(days * 86_400).ok()
+}
@@ -0,0 +1,128 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::RuleSet;
+use crate::diagnostic::Diagnostic;
+use crate::error::AnalyzerError;
+use crate::index::FileIndex;
+use crate::preprocessor::parse_directives;
+
+// Directory the cache is kept in by default, relative to the working directory
+pub const DEFAULT_CACHE_DIRECTORY: &str = ".nasa-sa-cache";
+
+// Results of analyzing one file, reused while the file, the local headers it
+// includes, the effective ruleset and the analyzer version stay the same
+#[derive(Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub diagnostics: Vec<Diagnostic>,
+    pub file_index: FileIndex,
+}
+
+// On-disk cache of analysis results, one JSON file per cache key
+pub struct AnalysisCache {
+    directory: PathBuf,
+}
+
+impl AnalysisCache {
+    pub fn new(directory: &Path) -> Self {
+        AnalysisCache {
+            directory: directory.to_path_buf(),
+        }
+    }
+
+    // Key of a file's results. Headers included with quotes are resolved relative to
+    // the including file and hashed too; system headers are assumed not to change.
+    pub fn key(&self, path: &Path, raw_source: &str, rule_set: &RuleSet) -> String {
+        let mut hasher = Fnv1a::default();
+        hasher.write(env!("CARGO_PKG_VERSION").as_bytes());
+        // serde_json sorts object keys, so the ruleset's maps hash the same every run
+        let rule_set = serde_json::to_value(rule_set)
+            .map(|value| value.to_string())
+            .unwrap_or_default();
+        hasher.write(rule_set.as_bytes());
+        hasher.write(path.to_string_lossy().as_bytes());
+        hasher.write(raw_source.as_bytes());
+
+        let mut visited = HashSet::new();
+        hash_local_headers(&mut hasher, path, raw_source, &mut visited);
+
+        format!("{:016x}", hasher.0)
+    }
+
+    // Cached results for a key. Unreadable or outdated entries count as misses.
+    pub fn load(&self, key: &str) -> Option<CacheEntry> {
+        let content = fs::read_to_string(self.entry_path(key)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    pub fn store(&self, key: &str, entry: &CacheEntry) -> Result<(), AnalyzerError> {
+        let path = self.entry_path(key);
+        let write_error = |error| AnalyzerError::OutputWrite {
+            path: path.clone(),
+            error,
+        };
+        fs::create_dir_all(&self.directory).map_err(write_error)?;
+        let content = serde_json::to_string(entry).map_err(|error| write_error(error.into()))?;
+        fs::write(&path, content).map_err(write_error)
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.directory.join(format!("{}.json", key))
+    }
+}
+
+// Hash the contents of every header the file includes with quotes, recursively
+fn hash_local_headers(
+    hasher: &mut Fnv1a,
+    path: &Path,
+    raw_source: &str,
+    visited: &mut HashSet<PathBuf>,
+) {
+    let directory = path.parent().unwrap_or(Path::new(""));
+    for directive in parse_directives(raw_source) {
+        if !directive.arguments.trim_start().starts_with('"') {
+            continue;
+        }
+        let Some(target) = directive.include_target() else {
+            continue;
+        };
+        let header = directory.join(target);
+        if !visited.insert(header.clone()) {
+            continue;
+        }
+
+        hasher.write(header.to_string_lossy().as_bytes());
+        match fs::read_to_string(&header) {
+            Ok(content) => {
+                hasher.write(content.as_bytes());
+                hash_local_headers(hasher, &header, &content, visited);
+            }
+            // Found through an include path instead, or missing, either way keyed by name only
+            Err(_) => hasher.write(b"unresolved"),
+        }
+    }
+}
+
+// 64-bit FNV-1a, stable across runs and Rust versions unlike the std hashers
+pub(crate) struct Fnv1a(pub(crate) u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv1a {
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+        // Separate consecutive inputs so "ab" + "c" and "a" + "bc" differ
+        self.0 ^= 0xff;
+        self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+    }
+}
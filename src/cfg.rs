@@ -0,0 +1,607 @@
+use std::collections::HashMap;
+
+use lang_c::ast::{
+    BinaryOperator, BlockItem, Declaration, Expression, ForInitializer, FunctionDefinition, Label,
+    Statement, UnaryOperator,
+};
+use lang_c::span::{Node, Span};
+
+// Functions that never return, so nothing after a call to them runs
+const NORETURN_FUNCTIONS: [&str; 6] = [
+    "abort",
+    "exit",
+    "_Exit",
+    "quick_exit",
+    "longjmp",
+    "siglongjmp",
+];
+
+pub type BlockId = usize;
+
+// Something a basic block evaluates, in order
+#[derive(Debug, Clone, Copy)]
+pub enum Element<'ast> {
+    Declaration(&'ast Node<Declaration>),
+    // A statement without nested statements: expressions, return, goto, break, continue and asm
+    Statement(&'ast Node<Statement>),
+    // Controlling expression of an if, switch or loop, ending the block it is in
+    Condition(&'ast Node<Expression>),
+    // Initializer or step expression of a for loop
+    Expression(&'ast Node<Expression>),
+}
+
+impl Element<'_> {
+    pub fn span(&self) -> Span {
+        match self {
+            Element::Declaration(declaration) => declaration.span,
+            Element::Statement(statement) => statement.span,
+            Element::Condition(condition) => condition.span,
+            Element::Expression(expression) => expression.span,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct BasicBlock<'ast> {
+    pub elements: Vec<Element<'ast>>,
+    pub successors: Vec<BlockId>,
+}
+
+// Control-flow graph of a function body, for the rules that need to know which
+// statements can follow which rather than how they are nested. Conditions that are
+// integer constants only get the edges that can be taken, so the body of `if (0)`
+// and the code after `while (1)` without a break are unreachable.
+#[derive(Debug)]
+pub struct ControlFlowGraph<'ast> {
+    pub blocks: Vec<BasicBlock<'ast>>,
+    pub case_labels: Vec<CaseLabel>,
+}
+
+// A case or default label of a switch, starting its own block. The block before it
+// falls through into it unless it ends in a jump.
+#[derive(Debug)]
+pub struct CaseLabel {
+    pub span: Span, // Labeled statement
+    pub block: BlockId,
+    pub previous: BlockId,
+}
+
+impl<'ast> ControlFlowGraph<'ast> {
+    // Block the function starts in
+    pub const ENTRY: BlockId = 0;
+    // Empty block every return, and the end of the body, leads to
+    pub const EXIT: BlockId = 1;
+
+    pub fn build(function_definition: &'ast FunctionDefinition) -> Self {
+        let mut builder = Builder {
+            blocks: vec![BasicBlock::default(), BasicBlock::default()],
+            current: Self::ENTRY,
+            break_targets: Vec::new(),
+            continue_targets: Vec::new(),
+            switches: Vec::new(),
+            labels: HashMap::new(),
+            case_labels: Vec::new(),
+        };
+        builder.statement(&function_definition.statement);
+        builder.edge(builder.current, Self::EXIT);
+
+        ControlFlowGraph {
+            blocks: builder.blocks,
+            case_labels: builder.case_labels,
+        }
+    }
+
+    // Whether each block can be reached from the entry
+    pub fn reachable(&self) -> Vec<bool> {
+        let mut reachable = vec![false; self.blocks.len()];
+        let mut pending = vec![Self::ENTRY];
+        while let Some(block) = pending.pop() {
+            if reachable[block] {
+                continue;
+            }
+            reachable[block] = true;
+            pending.extend(&self.blocks[block].successors);
+        }
+        reachable
+    }
+
+    pub fn predecessors(&self, block: BlockId) -> Vec<BlockId> {
+        (0..self.blocks.len())
+            .filter(|&predecessor| self.blocks[predecessor].successors.contains(&block))
+            .collect()
+    }
+}
+
+struct Builder<'ast> {
+    blocks: Vec<BasicBlock<'ast>>,
+    current: BlockId, // Block elements are added to, without predecessors after a jump
+    break_targets: Vec<BlockId>,
+    continue_targets: Vec<BlockId>,
+    // Blocks evaluating the expressions of the enclosing switches, with the value of a
+    // constant expression and whether a case label matches it
+    switches: Vec<(BlockId, Option<i64>, bool)>,
+    labels: HashMap<&'ast str, BlockId>, // Created by the label or by the first goto to it
+    case_labels: Vec<CaseLabel>,
+}
+
+impl<'ast> Builder<'ast> {
+    fn new_block(&mut self) -> BlockId {
+        self.blocks.push(BasicBlock::default());
+        self.blocks.len() - 1
+    }
+
+    fn edge(&mut self, from: BlockId, to: BlockId) {
+        if !self.blocks[from].successors.contains(&to) {
+            self.blocks[from].successors.push(to);
+        }
+    }
+
+    // Add an edge from the current block and continue in a fresh block, which code
+    // after the jump ends up in
+    fn jump(&mut self, to: BlockId) {
+        self.edge(self.current, to);
+        self.current = self.new_block();
+    }
+
+    // End the current block with a condition and continue in a block for each outcome
+    // that can occur
+    fn branch(
+        &mut self,
+        condition: &'ast Node<Expression>,
+        when_true: BlockId,
+        when_false: BlockId,
+    ) {
+        self.blocks[self.current]
+            .elements
+            .push(Element::Condition(condition));
+        let value = constant_condition(&condition.node);
+        if value != Some(false) {
+            self.edge(self.current, when_true);
+        }
+        if value != Some(true) {
+            self.edge(self.current, when_false);
+        }
+    }
+
+    fn label_block(&mut self, name: &'ast str) -> BlockId {
+        if let Some(&block) = self.labels.get(name) {
+            return block;
+        }
+        let block = self.new_block();
+        self.labels.insert(name, block);
+        block
+    }
+
+    fn push(&mut self, element: Element<'ast>) {
+        self.blocks[self.current].elements.push(element);
+    }
+
+    fn statement(&mut self, statement: &'ast Node<Statement>) {
+        match &statement.node {
+            Statement::Compound(items) => {
+                for item in items {
+                    match &item.node {
+                        BlockItem::Declaration(declaration) => {
+                            self.push(Element::Declaration(declaration))
+                        }
+                        BlockItem::Statement(statement) => self.statement(statement),
+                        BlockItem::StaticAssert(_) => {}
+                    }
+                }
+            }
+            Statement::Labeled(labeled_statement) => {
+                let block = match &labeled_statement.node.label.node {
+                    Label::Identifier(identifier) => self.label_block(&identifier.node.name),
+                    // Case labels are entered from the switch as well as by falling through
+                    label @ (Label::Case(_) | Label::CaseRange(_) | Label::Default) => {
+                        let block = self.new_block();
+                        if let Some(&(switch, value, has_matching_case)) = self.switches.last() {
+                            // A switch on a constant only enters the case matching it
+                            let entered = match (label, value) {
+                                (_, None) => true,
+                                (Label::Default, Some(_)) => !has_matching_case,
+                                (label, Some(value)) => case_matches(label, value) != Some(false),
+                            };
+                            if entered {
+                                self.edge(switch, block);
+                            }
+                        }
+                        self.case_labels.push(CaseLabel {
+                            span: statement.span,
+                            block,
+                            previous: self.current,
+                        });
+                        block
+                    }
+                };
+                self.edge(self.current, block);
+                self.current = block;
+                self.statement(&labeled_statement.node.statement);
+            }
+            Statement::If(if_statement) => {
+                let then_block = self.new_block();
+                let else_block = self.new_block();
+                let join = self.new_block();
+                self.branch(&if_statement.node.condition, then_block, else_block);
+
+                self.current = then_block;
+                self.statement(&if_statement.node.then_statement);
+                self.edge(self.current, join);
+
+                self.current = else_block;
+                if let Some(else_statement) = &if_statement.node.else_statement {
+                    self.statement(else_statement);
+                }
+                self.edge(self.current, join);
+                self.current = join;
+            }
+            Statement::Switch(switch_statement) => {
+                self.push(Element::Condition(&switch_statement.node.expression));
+                let switch = self.current;
+                let after = self.new_block();
+                let labels = switch_labels(&switch_statement.node.statement.node);
+                let value = constant_value(&switch_statement.node.expression.node);
+                let has_matching_case = value.is_some_and(|value| {
+                    labels
+                        .iter()
+                        .any(|label| case_matches(label, value) == Some(true))
+                });
+
+                // Statements before the first case label are never executed
+                self.current = self.new_block();
+                self.switches.push((switch, value, has_matching_case));
+                self.break_targets.push(after);
+                self.statement(&switch_statement.node.statement);
+                self.break_targets.pop();
+                self.switches.pop();
+                self.edge(self.current, after);
+
+                // Without a default label the switch can skip its body
+                let has_default = labels.iter().any(|label| matches!(label, Label::Default));
+                if !has_default && (value.is_none() || !has_matching_case) {
+                    self.edge(switch, after);
+                }
+                self.current = after;
+            }
+            Statement::While(while_statement) => {
+                let condition = self.new_block();
+                let body = self.new_block();
+                let after = self.new_block();
+                self.edge(self.current, condition);
+                self.current = condition;
+                self.branch(&while_statement.node.expression, body, after);
+
+                self.current = body;
+                self.loop_body(&while_statement.node.statement, after, condition);
+                self.edge(self.current, condition);
+                self.current = after;
+            }
+            Statement::DoWhile(do_while_statement) => {
+                let body = self.new_block();
+                let condition = self.new_block();
+                let after = self.new_block();
+                self.edge(self.current, body);
+
+                self.current = body;
+                self.loop_body(&do_while_statement.node.statement, after, condition);
+                self.edge(self.current, condition);
+                self.current = condition;
+                self.branch(&do_while_statement.node.expression, body, after);
+                self.current = after;
+            }
+            Statement::For(for_statement) => {
+                match &for_statement.node.initializer.node {
+                    ForInitializer::Declaration(declaration) => {
+                        self.push(Element::Declaration(declaration))
+                    }
+                    ForInitializer::Expression(expression) => {
+                        self.push(Element::Expression(expression))
+                    }
+                    ForInitializer::Empty | ForInitializer::StaticAssert(_) => {}
+                }
+                let condition = self.new_block();
+                let body = self.new_block();
+                let step = self.new_block();
+                let after = self.new_block();
+                self.edge(self.current, condition);
+                self.current = condition;
+                match &for_statement.node.condition {
+                    Some(expression) => self.branch(expression, body, after),
+                    // for (;;) only ends through a jump
+                    None => self.edge(condition, body),
+                }
+
+                self.current = body;
+                self.loop_body(&for_statement.node.statement, after, step);
+                self.edge(self.current, step);
+                if let Some(expression) = &for_statement.node.step {
+                    self.blocks[step]
+                        .elements
+                        .push(Element::Expression(expression));
+                }
+                self.edge(step, condition);
+                self.current = after;
+            }
+            Statement::Goto(label) => {
+                self.push(Element::Statement(statement));
+                let target = self.label_block(&label.node.name);
+                self.jump(target);
+            }
+            Statement::Continue => {
+                self.push(Element::Statement(statement));
+                if let Some(&target) = self.continue_targets.last() {
+                    self.jump(target);
+                }
+            }
+            Statement::Break => {
+                self.push(Element::Statement(statement));
+                if let Some(&target) = self.break_targets.last() {
+                    self.jump(target);
+                }
+            }
+            Statement::Return(_) => {
+                self.push(Element::Statement(statement));
+                self.jump(ControlFlowGraph::EXIT);
+            }
+            Statement::Expression(expression) => {
+                self.push(Element::Statement(statement));
+                if expression
+                    .as_ref()
+                    .is_some_and(|expression| is_noreturn_call(&expression.node))
+                {
+                    self.jump(ControlFlowGraph::EXIT);
+                }
+            }
+            Statement::Asm(_) => self.push(Element::Statement(statement)),
+        }
+    }
+
+    // The body of a loop, where break leaves to `after` and continue goes to `next`
+    fn loop_body(&mut self, body: &'ast Node<Statement>, after: BlockId, next: BlockId) {
+        self.break_targets.push(after);
+        self.continue_targets.push(next);
+        self.statement(body);
+        self.continue_targets.pop();
+        self.break_targets.pop();
+    }
+}
+
+// Value of a condition that is an integer constant expression, e.g. `1` or `!0`
+fn constant_condition(condition: &Expression) -> Option<bool> {
+    match condition {
+        Expression::Constant(_) | Expression::BinaryOperator(_) => {
+            constant_value(condition).map(|value| value != 0)
+        }
+        Expression::UnaryOperator(unary_operator_expression)
+            if unary_operator_expression.node.operator.node == UnaryOperator::Negate =>
+        {
+            constant_condition(&unary_operator_expression.node.operand.node).map(|value| !value)
+        }
+        _ => None,
+    }
+}
+
+fn constant_value(expression: &Expression) -> Option<i64> {
+    match expression {
+        Expression::Constant(constant) => match &constant.node {
+            lang_c::ast::Constant::Integer(integer) => {
+                let radix = match integer.base {
+                    lang_c::ast::IntegerBase::Decimal => 10,
+                    lang_c::ast::IntegerBase::Octal => 8,
+                    lang_c::ast::IntegerBase::Hexadecimal => 16,
+                    lang_c::ast::IntegerBase::Binary => 2,
+                };
+                if integer.number.is_empty() {
+                    return Some(0);
+                }
+                i64::from_str_radix(&integer.number, radix).ok()
+            }
+            _ => None,
+        },
+        Expression::BinaryOperator(binary_operator_expression) => {
+            let lhs = constant_value(&binary_operator_expression.node.lhs.node)?;
+            let rhs = constant_value(&binary_operator_expression.node.rhs.node)?;
+            match binary_operator_expression.node.operator.node {
+                BinaryOperator::Plus => lhs.checked_add(rhs),
+                BinaryOperator::Minus => lhs.checked_sub(rhs),
+                BinaryOperator::Multiply => lhs.checked_mul(rhs),
+                BinaryOperator::Equals => Some(i64::from(lhs == rhs)),
+                BinaryOperator::NotEquals => Some(i64::from(lhs != rhs)),
+                BinaryOperator::Less => Some(i64::from(lhs < rhs)),
+                BinaryOperator::LessOrEqual => Some(i64::from(lhs <= rhs)),
+                BinaryOperator::Greater => Some(i64::from(lhs > rhs)),
+                BinaryOperator::GreaterOrEqual => Some(i64::from(lhs >= rhs)),
+                BinaryOperator::LogicalAnd => Some(i64::from(lhs != 0 && rhs != 0)),
+                BinaryOperator::LogicalOr => Some(i64::from(lhs != 0 || rhs != 0)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+// Whether an expression calls a function that does not return, e.g. `exit(1)`
+fn is_noreturn_call(expression: &Expression) -> bool {
+    let Expression::Call(call_expression) = expression else {
+        return false;
+    };
+    matches!(
+        &call_expression.node.callee.node,
+        Expression::Identifier(identifier)
+            if NORETURN_FUNCTIONS.contains(&identifier.node.name.as_str())
+    )
+}
+
+// Whether a case label matches a value, None when the label is not a constant
+fn case_matches(label: &Label, value: i64) -> Option<bool> {
+    match label {
+        Label::Case(expression) => Some(constant_value(&expression.node)? == value),
+        Label::CaseRange(case_range) => {
+            let low = constant_value(&case_range.node.low.node)?;
+            let high = constant_value(&case_range.node.high.node)?;
+            Some(low <= value && value <= high)
+        }
+        _ => Some(false),
+    }
+}
+
+// Case and default labels of a switch body, leaving out those of nested switches
+pub(crate) fn switch_labels(statement: &Statement) -> Vec<&Label> {
+    match statement {
+        Statement::Compound(items) => items
+            .iter()
+            .flat_map(|item| match &item.node {
+                BlockItem::Statement(statement) => switch_labels(&statement.node),
+                _ => Vec::new(),
+            })
+            .collect(),
+        Statement::Labeled(labeled_statement) => {
+            let mut labels = switch_labels(&labeled_statement.node.statement.node);
+            if !matches!(labeled_statement.node.label.node, Label::Identifier(_)) {
+                labels.insert(0, &labeled_statement.node.label.node);
+            }
+            labels
+        }
+        Statement::If(if_statement) => {
+            let mut labels = switch_labels(&if_statement.node.then_statement.node);
+            if let Some(else_statement) = &if_statement.node.else_statement {
+                labels.extend(switch_labels(&else_statement.node));
+            }
+            labels
+        }
+        Statement::While(while_statement) => switch_labels(&while_statement.node.statement.node),
+        Statement::DoWhile(do_while_statement) => {
+            switch_labels(&do_while_statement.node.statement.node)
+        }
+        Statement::For(for_statement) => switch_labels(&for_statement.node.statement.node),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lang_c::ast::ExternalDeclaration;
+    use lang_c::driver::{parse_preprocessed, Config, Parse};
+
+    use super::*;
+
+    fn parse(source: &str) -> Parse {
+        parse_preprocessed(&Config::default(), source.to_string()).expect("test source parses")
+    }
+
+    fn function(parse: &Parse) -> &FunctionDefinition {
+        parse
+            .unit
+            .0
+            .iter()
+            .find_map(|declaration| match &declaration.node {
+                ExternalDeclaration::FunctionDefinition(function_definition) => {
+                    Some(&function_definition.node)
+                }
+                _ => None,
+            })
+            .expect("test source defines a function")
+    }
+
+    // Block holding the element with the given source text
+    fn block_of(graph: &ControlFlowGraph, source: &str, text: &str) -> BlockId {
+        graph
+            .blocks
+            .iter()
+            .position(|block| {
+                block.elements.iter().any(|element| {
+                    let span = element.span();
+                    source[span.start..span.end].trim_end_matches(';') == text
+                })
+            })
+            .unwrap_or_else(|| panic!("no block holds {}", text))
+    }
+
+    #[test]
+    fn switch_cases_fall_through_unless_they_break() {
+        let parse = parse(
+            "void f(int x) {
+                switch (x) {
+                case 1:
+                    first();
+                case 2:
+                    second();
+                    break;
+                default:
+                    other();
+                }
+            }",
+        );
+        let graph = ControlFlowGraph::build(function(&parse));
+        let reachable = graph.reachable();
+        let switch = block_of(&graph, &parse.source, "x");
+
+        assert_eq!(graph.case_labels.len(), 3);
+        for label in &graph.case_labels {
+            assert!(graph.blocks[switch].successors.contains(&label.block));
+        }
+        // With a default label the switch always enters its body
+        assert_eq!(graph.blocks[switch].successors.len(), 3);
+
+        let second = &graph.case_labels[1];
+        assert!(reachable[second.previous]);
+        assert!(graph.blocks[second.previous]
+            .successors
+            .contains(&second.block));
+        let default = &graph.case_labels[2];
+        assert!(!reachable[default.previous]);
+    }
+
+    #[test]
+    fn break_leaves_the_loop_and_continue_reevaluates_the_condition() {
+        let parse = parse(
+            "void f(int running) {
+                while (running) {
+                    if (running == 1)
+                        break;
+                    if (running == 2)
+                        continue;
+                    step();
+                }
+                done();
+            }",
+        );
+        let graph = ControlFlowGraph::build(function(&parse));
+        let source = &parse.source;
+
+        let after = block_of(&graph, source, "done()");
+        let condition = block_of(&graph, source, "running");
+        assert_eq!(
+            graph.blocks[block_of(&graph, source, "break")].successors,
+            [after]
+        );
+        assert_eq!(
+            graph.blocks[block_of(&graph, source, "continue")].successors,
+            [condition]
+        );
+        assert!(graph.blocks[block_of(&graph, source, "step()")]
+            .successors
+            .contains(&condition));
+    }
+
+    #[test]
+    fn nothing_after_a_noreturn_call_is_reachable() {
+        let parse = parse(
+            "int f(int x) {
+                if (x) {
+                    exit(1);
+                    cleanup();
+                }
+                return x;
+            }",
+        );
+        let graph = ControlFlowGraph::build(function(&parse));
+        let reachable = graph.reachable();
+        let source = &parse.source;
+
+        let exit = block_of(&graph, source, "exit(1)");
+        assert_eq!(graph.blocks[exit].successors, [ControlFlowGraph::EXIT]);
+        assert!(!reachable[block_of(&graph, source, "cleanup()")]);
+        assert!(reachable[block_of(&graph, source, "return x")]);
+    }
+}
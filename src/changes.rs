@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::error::AnalyzerError;
+
+// Files changed in the working tree or the index compared to a commit, HEAD by
+// default, and new files git does not track yet, relative to the working directory.
+// Files outside the working directory, ignored files and deleted files are left out.
+pub fn changed_files(since: Option<&str>) -> Result<Vec<String>, AnalyzerError> {
+    let mut output = git(&[
+        "diff",
+        "--name-only",
+        "--relative",
+        "--diff-filter=d",
+        "-z",
+        since.unwrap_or("HEAD"),
+        "--",
+    ])?;
+    output.push('\0');
+    output.push_str(&git(&["ls-files", "--others", "--exclude-standard", "-z"])?);
+    Ok(output
+        .split('\0')
+        .filter(|file| !file.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+// Run git in the working directory and return what it printed
+fn git(args: &[&str]) -> Result<String, AnalyzerError> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|error| AnalyzerError::Git(error.to_string()))?;
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(AnalyzerError::Git(message));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// Lines added or modified compared to a commit, by file
+#[derive(Debug, Default)]
+pub struct ChangedLines {
+    // Inclusive line ranges in the current version, by canonical path
+    files: HashMap<PathBuf, Vec<(usize, usize)>>,
+}
+
+impl ChangedLines {
+    // Lines of the working tree that differ from a commit, according to the hunks of git diff
+    pub fn since(base: &str) -> Result<Self, AnalyzerError> {
+        let output = git(&[
+            "diff",
+            "--unified=0",
+            "--no-prefix",
+            "--no-color",
+            "--relative",
+            base,
+            "--",
+        ])?;
+
+        let mut changed = ChangedLines::default();
+        let mut ranges = None;
+        let mut previous = "";
+        for line in output.lines() {
+            // File headers come in pairs, which tells them apart from changed lines
+            let header = previous.starts_with("--- ");
+            previous = line;
+            if let Some(file) = line.strip_prefix("+++ ").filter(|_| header) {
+                // Deleted files have no lines left to report on
+                let file = file.trim_end();
+                ranges = match file {
+                    "/dev/null" => None,
+                    file => fs::canonicalize(file)
+                        .ok()
+                        .map(|path| changed.files.entry(path).or_default()),
+                };
+            } else if let (Some(hunk), Some(ranges)) = (line.strip_prefix("@@ "), &mut ranges) {
+                if let Some(range) = hunk_range(hunk) {
+                    ranges.push(range);
+                }
+            }
+        }
+        Ok(changed)
+    }
+
+    pub fn contains(&self, file: &str, line: usize) -> bool {
+        let Ok(path) = fs::canonicalize(file) else {
+            return false;
+        };
+        self.files.get(&path).is_some_and(|ranges| {
+            ranges
+                .iter()
+                .any(|&(start, end)| (start..=end).contains(&line))
+        })
+    }
+}
+
+// Lines a hunk header like "-10,2 +12,3 @@" covers in the new version. Lines
+// around a pure deletion count as changed, as removing code can break them.
+fn hunk_range(hunk: &str) -> Option<(usize, usize)> {
+    let new = hunk
+        .split_whitespace()
+        .find_map(|part| part.strip_prefix('+'))?;
+    let (start, count) = match new.split_once(',') {
+        Some((start, count)) => (start.parse::<usize>().ok()?, count.parse::<usize>().ok()?),
+        None => (new.parse().ok()?, 1),
+    };
+    if count == 0 {
+        return Some((start, start + 1));
+    }
+    Some((start, start + count - 1))
+}
@@ -0,0 +1,174 @@
+use std::env;
+
+use nasa_static_analyzer::blame::parse_date;
+use nasa_static_analyzer::error::AnalyzerError;
+
+// Command line options for a single run of the analyzer
+#[derive(Debug, Default)]
+pub struct Options {
+    pub files: Vec<String>,                 // C source files to analyze
+    pub baseline: Option<String>, // Only report findings not recorded in this baseline file
+    pub write_baseline: Option<String>, // Record all current findings to this baseline file
+    pub enable: Vec<String>,      // Rules to enable for this run regardless of the ruleset
+    pub disable: Vec<String>,     // Rules to disable for this run regardless of the ruleset
+    pub plugins: Vec<String>,     // Dynamic libraries providing additional rules
+    pub append_history: Option<String>, // CSV file to append this run's statistics to
+    pub preset: Option<String>,   // Built-in preset the base ruleset is applied on top of
+    pub explain: Option<String>,  // Rule to print the documentation of, then exit
+    pub compare: Option<(String, String)>, // Old and new report to compare, then exit
+    pub author: Option<String>,   // Only report findings on lines last changed by this author
+    pub since: Option<u64>, // Only report findings on lines changed since this time, in epoch seconds
+    pub blame: bool,        // Print the commit and author of each finding's line
+    pub cache: bool,        // Reuse results of unchanged files from the analysis cache
+    pub watch: bool,        // Keep running and re-analyze files as they change
+    pub changed_only: bool, // Only analyze files git reports as modified or staged
+    pub changed_since: Option<String>, // Commit set by --changed-since, HEAD when not given
+    pub diff_base: Option<String>, // Only report findings on lines changed since this commit
+    pub daemon: bool,       // Serve JSON-RPC analysis requests instead of analyzing files
+    pub socket: Option<String>, // Unix socket the daemon listens on, stdio when not given
+    pub workspace: Option<String>, // Workspace file listing the modules to analyze, in place of files
+    pub list_rules: bool,          // Print every known rule and exit
+    pub metrics: bool,             // Print the metrics report after the findings
+    pub metrics_json: Option<String>, // Write the file and function metrics to this JSON file
+    pub duplicate_threshold: Option<usize>, // Uses at which a literal counts as duplicated
+    pub jobs: Option<usize>,       // Worker threads analyzing files, defaults to one per CPU
+}
+
+// Value following a flag, e.g. the path after --baseline
+fn flag_value(
+    args: &mut impl Iterator<Item = String>,
+    flag: &str,
+    expected: &str,
+) -> Result<String, AnalyzerError> {
+    args.next()
+        .ok_or_else(|| AnalyzerError::Usage(format!("{} requires {}", flag, expected)))
+}
+
+pub fn parse_args() -> Result<Options, AnalyzerError> {
+    let mut options = Options::default();
+    let mut args = env::args().skip(1).peekable();
+
+    if args.peek().map(String::as_str) == Some("explain") {
+        args.next();
+        options.explain = Some(flag_value(&mut args, "explain", "a rule ID")?);
+        return Ok(options);
+    }
+
+    // Reports are files written by --write-baseline
+    if args.peek().map(String::as_str) == Some("compare") {
+        args.next();
+        let old = flag_value(&mut args, "compare", "an old and a new report")?;
+        let new = flag_value(&mut args, "compare", "an old and a new report")?;
+        options.compare = Some((old, new));
+        return Ok(options);
+    }
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--baseline" => {
+                options.baseline = Some(flag_value(&mut args, &arg, "a file path")?);
+            }
+            "--write-baseline" => {
+                options.write_baseline = Some(flag_value(&mut args, &arg, "a file path")?);
+            }
+            "--enable" => options
+                .enable
+                .push(flag_value(&mut args, &arg, "a rule name")?),
+            "--disable" => options
+                .disable
+                .push(flag_value(&mut args, &arg, "a rule name")?),
+            "--plugin" => options
+                .plugins
+                .push(flag_value(&mut args, &arg, "a library path")?),
+            "--preset" => {
+                options.preset = Some(flag_value(&mut args, &arg, "a preset name")?);
+            }
+            "--append-history" => {
+                options.append_history = Some(flag_value(&mut args, &arg, "a file path")?);
+            }
+            "--author" => {
+                options.author = Some(flag_value(&mut args, &arg, "an author name")?);
+            }
+            "--since" => {
+                let since = flag_value(&mut args, &arg, "a date")?;
+                let since = parse_date(&since).ok_or_else(|| {
+                    AnalyzerError::Usage("--since requires a date as YYYY-MM-DD".to_string())
+                })?;
+                options.since = Some(since);
+            }
+            "--blame" => options.blame = true,
+            "--cache" => options.cache = true,
+            "--watch" => options.watch = true,
+            "--changed-only" => options.changed_only = true,
+            // Implies --changed-only, comparing against the given commit instead of HEAD
+            "--changed-since" => {
+                options.changed_since = Some(flag_value(&mut args, &arg, "a commit")?);
+                options.changed_only = true;
+            }
+            "--diff-base" => {
+                options.diff_base = Some(flag_value(&mut args, &arg, "a commit")?);
+            }
+            "--daemon" => options.daemon = true,
+            "--socket" => {
+                options.socket = Some(flag_value(&mut args, &arg, "a socket path")?);
+            }
+            "--workspace" => {
+                options.workspace = Some(flag_value(&mut args, &arg, "a file path")?);
+            }
+            "--list-rules" => options.list_rules = true,
+            "--metrics" => options.metrics = true,
+            "--metrics-json" => {
+                options.metrics_json = Some(flag_value(&mut args, &arg, "a file path")?);
+            }
+            "--duplicate-threshold" => {
+                let threshold = flag_value(&mut args, &arg, "a number")?;
+                let threshold = threshold
+                    .parse()
+                    .map_err(|_| AnalyzerError::Usage(format!("{} requires a number", arg)))?;
+                options.duplicate_threshold = Some(threshold);
+            }
+            "--jobs" => {
+                let jobs = flag_value(&mut args, &arg, "a number")?;
+                let jobs = jobs
+                    .parse()
+                    .map_err(|_| AnalyzerError::Usage(format!("{} requires a number", arg)))?;
+                options.jobs = Some(jobs);
+            }
+            _ if arg.starts_with("--") => {
+                return Err(AnalyzerError::Usage(format!("Unknown argument: {}", arg)));
+            }
+            _ => options.files.push(arg),
+        }
+    }
+
+    if options.socket.is_some() && !options.daemon {
+        return Err(AnalyzerError::Usage(
+            "--socket requires --daemon".to_string(),
+        ));
+    }
+    if options.daemon {
+        if !options.files.is_empty() || options.workspace.is_some() {
+            return Err(AnalyzerError::Usage(
+                "--daemon takes the files to analyze from its requests".to_string(),
+            ));
+        }
+        return Ok(options);
+    }
+
+    if options.workspace.is_some() {
+        if !options.files.is_empty() {
+            return Err(AnalyzerError::Usage(
+                "--workspace takes the source files from its modules".to_string(),
+            ));
+        }
+        return Ok(options);
+    }
+
+    // Keep the original behaviour of analyzing example.c when no files are given.
+    // With --changed-only, no files means every changed file.
+    if options.files.is_empty() && !options.changed_only {
+        options.files.push("example.c".to_string());
+    }
+
+    Ok(options)
+}
@@ -0,0 +1,80 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::baseline::{Baseline, BaselineEntry};
+use crate::cache::Fnv1a;
+
+// A finding with an ID that stays the same from run to run until it is fixed
+pub struct Fingerprinted<'a> {
+    pub fingerprint: String,
+    pub finding: &'a BaselineEntry,
+}
+
+// Findings of two reports, matched by fingerprint. Unchanged findings are taken
+// from the new report so their line numbers are current.
+pub struct Comparison<'a> {
+    pub fixed: Vec<Fingerprinted<'a>>,
+    pub new: Vec<Fingerprinted<'a>>,
+    pub unchanged: Vec<Fingerprinted<'a>>,
+}
+
+pub fn compare<'a>(old: &'a Baseline, new: &'a Baseline) -> Comparison<'a> {
+    let old = fingerprints(old);
+    let new = fingerprints(new);
+    let old_fingerprints: HashSet<&str> = old
+        .iter()
+        .map(|finding| finding.fingerprint.as_str())
+        .collect();
+    let new_fingerprints: HashSet<&str> = new
+        .iter()
+        .map(|finding| finding.fingerprint.as_str())
+        .collect();
+
+    let fixed = old
+        .iter()
+        .filter(|finding| !new_fingerprints.contains(finding.fingerprint.as_str()))
+        .map(|finding| Fingerprinted {
+            fingerprint: finding.fingerprint.clone(),
+            finding: finding.finding,
+        })
+        .collect();
+    let (unchanged, new) = new
+        .into_iter()
+        .partition(|finding| old_fingerprints.contains(finding.fingerprint.as_str()));
+
+    Comparison {
+        fixed,
+        new,
+        unchanged,
+    }
+}
+
+// Fingerprint every finding of a report. Like baseline matching, the line is left
+// out so that edits shifting code around keep the fingerprints; identical findings
+// in one file are told apart by the order they were reported in.
+pub fn fingerprints(report: &Baseline) -> Vec<Fingerprinted<'_>> {
+    let mut occurrences: HashMap<(&str, &str, &str), usize> = HashMap::new();
+    report
+        .findings
+        .iter()
+        .map(|finding| {
+            let key = (
+                finding.rule.as_str(),
+                finding.file.as_str(),
+                finding.message.as_str(),
+            );
+            let occurrence = occurrences.entry(key).or_insert(0);
+            *occurrence += 1;
+
+            let mut hasher = Fnv1a::default();
+            hasher.write(finding.rule.as_bytes());
+            hasher.write(finding.file.as_bytes());
+            hasher.write(finding.message.as_bytes());
+            // Fixed width so fingerprints match across 32 and 64 bit builds
+            hasher.write(&(*occurrence as u64).to_le_bytes());
+            Fingerprinted {
+                fingerprint: format!("{:016x}", hasher.0),
+                finding,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,236 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use lang_c::driver::{parse, Config, Error};
+use lang_c::loc::get_location_for_offset;
+
+use crate::config::RuleSet;
+use crate::diagnostic::Diagnostic;
+use crate::error::AnalyzerError;
+use crate::preprocessor::{code_only, parse_directives, Directive};
+
+// Numbers the temporary variant files, so files checked in parallel do not collide
+static VARIANT_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+// One branch of an #if group: the directive opening it and the line range it covers
+struct ConditionalBranch {
+    directive: String, // e.g. "#ifdef FOO" or "#else"
+    line: usize,       // Line of the directive opening the branch
+    end: usize,        // Line of the directive closing the branch
+    disabled: bool,    // Opened with a condition that is always false, e.g. "#if 0"
+}
+
+impl ConditionalBranch {
+    // Whether another branch lies within this one, e.g. a nested #ifdef
+    fn encloses(&self, other: &ConditionalBranch) -> bool {
+        self.line < other.line && other.line < self.end
+    }
+}
+
+// Whether an #if or #elif condition is the constant 0, however it is written,
+// e.g. "#if 0", "#if (0)" or "#if 0 /* disabled */"
+fn is_disabled(directive: &Directive) -> bool {
+    if directive.name != "if" && directive.name != "elif" {
+        return false;
+    }
+    let condition: String = code_only(&directive.arguments)
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    let mut condition = condition.as_str();
+    while let Some(inner) = condition
+        .strip_prefix('(')
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        condition = inner;
+    }
+    condition == "0"
+}
+
+// Temporary file holding the variant being parsed, removed however the check ends
+struct VariantFile(PathBuf);
+
+impl Drop for VariantFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+// Branches of every complete #if ... #endif group, each group listed with all its branches
+fn conditional_groups(directives: &[Directive]) -> Vec<Vec<ConditionalBranch>> {
+    let mut open: Vec<Vec<ConditionalBranch>> = Vec::new();
+    let mut groups = Vec::new();
+
+    for directive in directives {
+        let text = format!("#{} {}", directive.name, directive.arguments)
+            .trim_end()
+            .to_string();
+        match directive.name.as_str() {
+            "if" | "ifdef" | "ifndef" => open.push(vec![ConditionalBranch {
+                directive: text,
+                line: directive.line,
+                end: directive.line,
+                disabled: is_disabled(directive),
+            }]),
+            "elif" | "else" | "endif" => {
+                let Some(mut branches) = open.pop() else {
+                    continue;
+                };
+                if let Some(last) = branches.last_mut() {
+                    last.end = directive.line;
+                }
+                if directive.name == "endif" {
+                    groups.push(branches);
+                } else {
+                    branches.push(ConditionalBranch {
+                        directive: text,
+                        line: directive.line,
+                        end: directive.line,
+                        disabled: is_disabled(directive),
+                    });
+                    open.push(branches);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    groups
+}
+
+// Replace a line, and the lines it continues onto, with empty lines so line numbers are kept
+fn blank_line(lines: &mut [String], line: usize) {
+    let mut index = line - 1;
+    while index < lines.len() {
+        let continues = lines[index].trim_end().ends_with('\\');
+        lines[index].clear();
+        if !continues {
+            break;
+        }
+        index += 1;
+    }
+}
+
+// Source with a single branch of each given group forced on and the other branches
+// of those groups removed
+fn select_branches(raw_source: &str, selections: &[(&[ConditionalBranch], usize)]) -> String {
+    let mut lines: Vec<String> = raw_source.lines().map(str::to_string).collect();
+    for &(group, selected) in selections {
+        for (index, branch) in group.iter().enumerate() {
+            blank_line(&mut lines, branch.line);
+            if index != selected {
+                for line in branch.line + 1..branch.end {
+                    lines[line - 1].clear();
+                }
+            }
+        }
+        if let Some(last) = group.last() {
+            blank_line(&mut lines, last.end);
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+// The branch of each group that encloses `target`, so a nested branch can be forced
+// on together with everything around it. None when an enclosing branch is disabled.
+fn enclosing_selections<'a>(
+    groups: &'a [Vec<ConditionalBranch>],
+    target: &ConditionalBranch,
+) -> Option<Vec<(&'a [ConditionalBranch], usize)>> {
+    let mut selections = Vec::new();
+    for group in groups {
+        if let Some(index) = group.iter().position(|branch| branch.encloses(target)) {
+            if group[index].disabled {
+                return None;
+            }
+            selections.push((group.as_slice(), index));
+        }
+    }
+    Some(selections)
+}
+
+// Code in branches that the current configuration does not compile is never seen
+// by the parser and silently rots. Every branch of every #if group is forced on in
+// turn, along with the branches enclosing it and with the other branches of those
+// groups removed, and run through the C preprocessor and parser. Branches disabled
+// with '#if 0', or nested in one, are left alone, as are branches stopped by the
+// preprocessor, e.g. by an #error directive.
+pub fn check_conditional_branches(
+    path: &Path,
+    raw_source: &str,
+    rule_set: &RuleSet,
+) -> Result<Vec<Diagnostic>, AnalyzerError> {
+    let rule = "require_parsable_conditional_branches";
+    let file = path.to_string_lossy();
+    let directives = parse_directives(raw_source);
+
+    // Included headers are resolved relative to the original file
+    let mut config = Config::default();
+    if let Some(directory) = path
+        .parent()
+        .filter(|directory| !directory.as_os_str().is_empty())
+    {
+        config
+            .cpp_options
+            .push(format!("-I{}", directory.display()));
+    }
+    let file_name = path
+        .file_name()
+        .map_or("source.c".into(), |name| name.to_string_lossy());
+    let variant = VariantFile(env::temp_dir().join(format!(
+        "nasa-analyzer-{}-{}-{}",
+        process::id(),
+        VARIANT_COUNTER.fetch_add(1, Ordering::Relaxed),
+        file_name
+    )));
+    let variant_path = &variant.0;
+
+    let mut diagnostics = Vec::new();
+    let groups = conditional_groups(&directives);
+    for group in &groups {
+        for (index, branch) in group.iter().enumerate() {
+            if branch.disabled {
+                continue;
+            }
+            let Some(mut selections) = enclosing_selections(&groups, branch) else {
+                continue;
+            };
+            selections.push((group.as_slice(), index));
+
+            fs::write(variant_path, select_branches(raw_source, &selections)).map_err(|error| {
+                AnalyzerError::OutputWrite {
+                    path: variant_path.clone(),
+                    error,
+                }
+            })?;
+            let result = parse(&config, variant_path);
+            let Err(Error::SyntaxError(error)) = result else {
+                continue;
+            };
+
+            // Point at the offending line when the error is in the file itself rather than a header
+            let (location, _) = get_location_for_offset(&error.source, error.offset);
+            let snippet = (location.file == variant_path.to_string_lossy())
+                .then(|| raw_source.lines().nth(location.line.saturating_sub(1)))
+                .flatten()
+                .map(|line| line.trim().to_string());
+            diagnostics.push(Diagnostic {
+                rule: rule.to_string(),
+                severity: rule_set.severity(rule),
+                file: file.to_string(),
+                line: branch.line,
+                column: 0,
+                message: format!(
+                    "Code under '{}' does not parse when enabled",
+                    branch.directive
+                ),
+                snippet,
+            });
+        }
+    }
+
+    Ok(diagnostics)
+}
@@ -1,34 +1,1264 @@
-use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::diagnostic::{default_severity, Severity};
+use crate::error::AnalyzerError;
+use crate::rules::{RuleMetadata, RULES};
+use std::collections::HashMap;
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+// Config files searched for in every directory from an analyzed file up to the
+// filesystem root, the first one present in a directory is used
+pub const CONFIG_FILE_NAMES: [&str; 4] = [
+    ".nasa-analyzer.toml",
+    ".nasa-analyzer.yaml",
+    ".nasa-analyzer.yml",
+    ".nasa-analyzer.json",
+];
+
+// Project-wide ruleset in the working directory, applied before any discovered config
+const BASE_RULESET_PATHS: [&str; 4] = [
+    "ruleset.toml",
+    "ruleset.yaml",
+    "ruleset.yml",
+    "ruleset.json",
+];
+
+// Ruleset compiled into the binary, used in place of a missing ruleset.toml so
+// the analyzer works out of the box with the full Power of Ten rule set
+const DEFAULT_RULESET: &str = include_str!("../ruleset.toml");
+
+// Formats a config file can be written in, selected by its extension
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ConfigFormat {
+    Toml,
+    Yaml,
+    Json,
+}
+
+impl ConfigFormat {
+    pub(crate) fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("yaml" | "yml") => ConfigFormat::Yaml,
+            Some("json") => ConfigFormat::Json,
+            _ => ConfigFormat::Toml,
+        }
+    }
+
+    // Errors of all formats include the line and column they occurred at
+    pub(crate) fn deserialize<T: DeserializeOwned>(self, content: &str) -> Result<T, String> {
+        match self {
+            ConfigFormat::Toml => toml::from_str(content).map_err(|error| error.to_string()),
+            ConfigFormat::Yaml => serde_yaml::from_str(content).map_err(|error| error.to_string()),
+            ConfigFormat::Json => serde_json::from_str(content).map_err(|error| error.to_string()),
+        }
+    }
+}
+
+// Highest min_assertions accepted, more than a function of the default size has lines
+const MAX_MIN_ASSERTIONS: usize = 60;
+
+// Built-in presets, selected with `preset = "..."` at the top of a config file or --preset
+pub const PRESETS: [&str; 3] = ["power-of-ten", "jpl-c", "minimal"];
+
+// Rules enabled by the jpl-c preset: those covering the JPL Institutional Coding
+// Standard for C, its LOC-1 to LOC-4 rules and the MISRA C rules it adopts
+const JPL_C_PRESET_RULES: [&str; 50] = [
+    "restrict_goto",
+    "restrict_setjmp",
+    "restrict_longjmp",
+    "restrict_recursion",
+    "restrict_unstructured_goto",
+    "restrict_computed_goto",
+    "fixed_loop_bounds",
+    "restrict_float_loop_counters",
+    "restrict_loop_counter_modification",
+    "restrict_heap_allocation",
+    "restrict_variable_length_arrays",
+    "restrict_alloca",
+    "restrict_stack_frame_size",
+    "restrict_function_size",
+    "restrict_parameter_count",
+    "require_prototypes",
+    "require_assertions",
+    "restrict_assertion_side_effects",
+    "require_static_functions",
+    "require_smallest_scope",
+    "restrict_unused_variables",
+    "restrict_shadowing",
+    "check_return_value",
+    "require_parameter_validation",
+    "require_switch_default",
+    "restrict_implicit_fallthrough",
+    "restrict_unreachable_code",
+    "restrict_uninitialized_reads",
+    "require_braces",
+    "restrict_comma_operator",
+    "restrict_complex_macros",
+    "restrict_undef_and_redefinition",
+    "restrict_include_placement",
+    "restrict_pragmas",
+    "restrict_dereference_depth",
+    "restrict_function_pointers",
+    "restrict_banned_functions",
+    "restrict_unsafe_string_functions",
+    "restrict_termination_calls",
+    "restrict_banned_headers",
+    "restrict_variadic",
+    "restrict_format_mismatches",
+    "restrict_octal_constants",
+    "restrict_signed_bitwise",
+    "restrict_implicit_narrowing",
+    "restrict_unions",
+    "restrict_float_equality",
+    "restrict_bit_field_types",
+    "require_sizeof_size_arguments",
+    "restrict_offset_buffer_overrun",
+];
+
+// Rules enabled by the minimal preset
+const MINIMAL_PRESET_RULES: [&str; 7] = [
+    "restrict_goto",
+    "restrict_setjmp",
+    "restrict_longjmp",
+    "restrict_recursion",
+    "fixed_loop_bounds",
+    "restrict_heap_allocation",
+    "check_return_value",
+];
 
 #[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct RulesConfig {
+    // Config file inherited from, relative to this one; resolved while loading
+    #[serde(default)]
+    pub extends: Option<String>,
+    // Expanded while loading, before the keys of the same file are applied
+    #[serde(default)]
+    pub preset: Option<String>,
+    // Rule settings for files matching glob patterns, e.g. paths = ["drivers/**"],
+    // applied after all config files in the order they are loaded
+    #[serde(default)]
+    pub overrides: Vec<toml::Table>,
+    #[serde(default)]
     pub rule_set: RuleSet,
 }
 
-#[derive(Debug, Deserialize, Default)]
+// Rules missing from a config file are disabled, so a ruleset written before a rule
+// was added keeps loading and keeps checking what it did
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
 pub struct RuleSet {
     // Avoid complex flow constructs
     pub restrict_goto: bool,
     pub restrict_setjmp: bool,
     pub restrict_longjmp: bool,
     pub restrict_recursion: bool,
+    // Recursive functions permitted when annotated with `@recursion max_depth=N measure=param`
+    #[serde(default)]
+    pub allowed_recursive_functions: Vec<String>,
+    // Only applies when goto itself is permitted
+    pub restrict_unstructured_goto: bool,
+    // GNU computed gotos and label addresses, reported even where goto is permitted
+    pub restrict_computed_goto: bool,
+    // Restrict statements that can never execute, found on the control-flow graph
+    pub restrict_unreachable_code: bool,
+    // Restrict reads of locals on paths where they have not been assigned yet
+    pub restrict_uninitialized_reads: bool,
+    // Require braces around the bodies of if, else, while, do and for
+    pub require_braces: bool,
+    // Restrict the comma operator outside for statement initializations and increments
+    pub restrict_comma_operator: bool,
+    // Restrict conditional expressions nested in more than max_conditional_nesting
+    // others, and conditional expressions as operands of && and ||
+    pub restrict_nested_conditionals: bool,
+    #[serde(default = "default_max_conditional_nesting")]
+    pub max_conditional_nesting: usize,
 
     // Enforce loop bounds
     pub fixed_loop_bounds: bool,
+    // Marker of comments declaring the most iterations of the loop below, e.g. /* loop-bound: 128 */
+    #[serde(default = "default_loop_bound_marker")]
+    pub loop_bound_marker: String,
+    pub restrict_float_loop_counters: bool,
+    pub restrict_loop_counter_modification: bool,
 
     // Restrict heap allocation, e.g. malloc
     pub restrict_heap_allocation: bool,
+    // Project allocators checked in addition to the standard ones
+    #[serde(default)]
+    pub additional_heap_functions: Vec<String>,
+    // Standard allocators that should not be reported
+    #[serde(default)]
+    pub excluded_heap_functions: Vec<String>,
+    // Restrict variable-length arrays, which allocate on the stack at run time
+    pub restrict_variable_length_arrays: bool,
+    // Restrict alloca and other functions allocating a run-time size on the stack
+    pub restrict_alloca: bool,
+    // Project equivalents of alloca checked in addition to the standard ones
+    #[serde(default)]
+    pub additional_stack_allocation_functions: Vec<String>,
+    // Restrict the estimated stack space a function needs for its local variables
+    pub restrict_stack_frame_size: bool,
+    #[serde(default = "default_max_stack_frame_bytes")]
+    pub max_stack_frame_bytes: u64,
 
     // Restrict function size
     pub restrict_function_size: bool,
+    #[serde(default = "default_max_function_lines")]
+    pub max_function_lines: usize,
+    #[serde(default = "default_true")]
+    pub count_blank_lines: bool,
+    #[serde(default = "default_true")]
+    pub count_comment_lines: bool,
+
+    // Restrict the number of parameters of function definitions and prototypes
+    pub restrict_parameter_count: bool,
+    #[serde(default = "default_max_parameters")]
+    pub max_parameters: usize,
+    // Require prototypes, restricting K&R identifier lists and empty parentheses
+    pub require_prototypes: bool,
+
+    // Require a minimum number of assertions in every function that is not trivially short
+    pub require_assertions: bool,
+    #[serde(default = "default_min_assertions")]
+    pub min_assertions: usize,
+    // Project assertion macros counted in addition to assert, e.g. "ASSERT_OR_FAULT"
+    #[serde(default)]
+    pub assertion_macros: Vec<String>,
+    // Functions spanning fewer lines are exempt
+    #[serde(default = "default_min_assertion_function_lines")]
+    pub min_assertion_function_lines: usize,
+    // Restrict assignments, increments and impure calls in asserted conditions
+    pub restrict_assertion_side_effects: bool,
+    // Functions without side effects that assertions may call
+    #[serde(default = "default_pure_functions")]
+    pub pure_functions: Vec<String>,
 
     // Check return value of functions
     pub check_return_value: bool,
+    // Require pointer and array parameters to be checked before their first use
+    pub require_parameter_validation: bool,
+
+    // Require the default case of switches over state machine types to call a fault handler
+    pub require_state_fault_handling: bool,
+    // Enum tags or typedef names of the state machine types
+    #[serde(default)]
+    pub state_types: Vec<String>,
+    #[serde(default = "default_fault_handlers")]
+    pub fault_handlers: Vec<String>,
+    // Require a default label in every switch
+    pub require_switch_default: bool,
+    // Also require the default to be the last label and to assert or call a fault handler
+    #[serde(default)]
+    pub strict_switch_default: bool,
+    // Restrict cases falling through into the next one without a marker comment
+    pub restrict_implicit_fallthrough: bool,
+    #[serde(default = "default_fallthrough_marker")]
+    pub fallthrough_marker: String,
+    // Require switches over an enum variable to only use and, without a default, to
+    // handle every enumerator of the enum
+    pub require_exhaustive_enum_switch: bool,
+
+    // Restrict project specific forbidden APIs
+    pub restrict_banned_functions: bool,
+    #[serde(default)]
+    pub banned_functions: Vec<String>,
+    // Suggested replacement for a banned function, e.g. gets = "fgets"
+    #[serde(default)]
+    pub banned_function_replacements: HashMap<String, String>,
+    // Restrict string and memory functions that cannot bound their writes, each mapped
+    // to the bounded function to use instead
+    pub restrict_unsafe_string_functions: bool,
+    #[serde(default = "default_unsafe_string_functions")]
+    pub unsafe_string_functions: HashMap<String, String>,
+    // Restrict calls that terminate the program, suggesting the project's fault handler
+    pub restrict_termination_calls: bool,
+    #[serde(default = "default_termination_functions")]
+    pub termination_functions: Vec<String>,
+    #[serde(default)]
+    pub fault_handler: Option<String>,
+
+    // Restrict variadic function definitions and calls
+    pub restrict_variadic: bool,
+    // Library variadic functions whose calls are still allowed, e.g. the printf family
+    #[serde(default = "default_variadic_library_functions")]
+    pub variadic_library_functions: Vec<String>,
+    // Project functions allowed to be defined with an ellipsis and called, and to use
+    // va_start and va_arg in their body, e.g. the sanctioned logging function
+    #[serde(default)]
+    pub allowed_variadic_functions: Vec<String>,
+    // Restrict printf and scanf format strings that do not match the arguments passed
+    pub restrict_format_mismatches: bool,
+    // Position of the format argument of each printf and scanf style function, from 0
+    #[serde(default = "default_printf_functions")]
+    pub printf_functions: HashMap<String, usize>,
+    #[serde(default = "default_scanf_functions")]
+    pub scanf_functions: HashMap<String, usize>,
+
+    // Restrict inclusion of headers whose whole API is forbidden
+    pub restrict_banned_headers: bool,
+    #[serde(default = "default_banned_headers")]
+    pub banned_headers: Vec<String>,
+
+    // Require static on functions that are not declared in a header or used by other files
+    pub require_static_functions: bool,
+    // Functions with external linkage no project header declares, e.g. entry points a
+    // loader looks up by name. Patterns such as "app_*" are allowed.
+    #[serde(default)]
+    pub exported_functions: Vec<String>,
+    // Require data at the smallest scope: no locals declared at function level that only
+    // one nested block uses
+    pub require_smallest_scope: bool,
+    // Restrict non-const file-scope variables
+    pub restrict_global_mutable_data: bool,
+    // Exempt static file-scope variables from restrict_global_mutable_data
+    #[serde(default)]
+    pub allow_static_globals: bool,
+    // Names of file-scope variables exempt from restrict_global_mutable_data, e.g. "g_*"
+    #[serde(default)]
+    pub global_allowed_patterns: Vec<String>,
+    // Restrict parameters and local variables that are never read
+    pub restrict_unused_variables: bool,
+    // Names exempt from restrict_unused_variables, e.g. parameters a callback signature requires
+    #[serde(default = "default_unused_allowed_patterns")]
+    pub unused_allowed_patterns: Vec<String>,
+    // Restrict locals that reuse the name of an enclosing block's variable or a global
+    pub restrict_shadowing: bool,
+
+    // Require the size argument of bounded buffer functions to derive from sizeof(destination)
+    pub require_sizeof_size_arguments: bool,
+    // Restrict sizes that overrun a buffer passed as `&array[k]` or `array + k`
+    pub restrict_offset_buffer_overrun: bool,
+
+    // Restrict expressions dereferencing more pointer levels than allowed, e.g. **p or p->q->r
+    pub restrict_dereference_depth: bool,
+    #[serde(default = "default_max_dereference_depth")]
+    pub max_dereference_depth: usize,
+
+    // Restrict function pointer variables, parameters and members, and calls through pointers
+    pub restrict_function_pointers: bool,
+    // Require pointer parameters that are never written through to point to const, and
+    // restrict casts that discard const from a pointer
+    pub require_const_correctness: bool,
+
+    // Restrict re-reading a checked volatile register and testing stale register copies
+    pub restrict_volatile_toctou: bool,
+    // Registers whose conditions must read them directly rather than through a copy
+    #[serde(default)]
+    pub fresh_read_registers: Vec<String>,
+
+    // Restrict integer constants written in octal, other than 0
+    pub restrict_octal_constants: bool,
+    // Restrict shifts and bitwise operators on operands of signed type
+    pub restrict_signed_bitwise: bool,
+    // Restrict assigning, initializing and passing integers to a narrower integer type
+    // without a cast
+    pub restrict_implicit_narrowing: bool,
+    // Restrict union definitions and variables and members of union type
+    pub restrict_unions: bool,
+    // Union tags and typedef names approved, e.g. for register overlays
+    #[serde(default)]
+    pub allowed_unions: Vec<String>,
+
+    // Restrict == and != between floating-point values
+    pub restrict_float_equality: bool,
+    // Restrict numeric literals outside named constants, enumerators and array sizes
+    pub restrict_magic_numbers: bool,
+    #[serde(default = "default_allowed_numbers")]
+    pub allowed_numbers: Vec<f64>,
+    // Exempt hexadecimal literals, which are usually bit masks and register values
+    #[serde(default = "default_true")]
+    pub allow_hex_numbers: bool,
+
+    // Portability: restrict locale-dependent and wide-character APIs
+    pub restrict_locale_apis: bool,
+    // Restrict bit-fields of other types than the listed fixed-width types, and signed
+    // bit-fields of a single bit
+    pub restrict_bit_field_types: bool,
+    #[serde(default = "default_bit_field_types")]
+    pub bit_field_types: Vec<String>,
+
+    // Preprocessor minimization: suggest enum or static const over constant macros
+    pub prefer_typed_constants: bool,
+    // Restrict statement macros, token pasting, variadic and recursively expanding macros
+    pub restrict_complex_macros: bool,
+    // Restrict #undef and redefining a macro with a different expansion
+    pub restrict_undef_and_redefinition: bool,
+    // Restrict #include directives to the top of the file, optionally in a fixed order
+    pub restrict_include_placement: bool,
+    #[serde(default)]
+    pub enforce_include_order: bool,
+    // Restrict absolute, parent-relative and backslash separated include paths
+    pub restrict_include_paths: bool,
+    // Restrict #pragma directives to the allow-listed kinds, e.g. "once" or "GCC diagnostic"
+    pub restrict_pragmas: bool,
+    #[serde(default = "default_allowed_pragmas")]
+    pub allowed_pragmas: Vec<String>,
+    // Require every branch of an #if group to parse, not just the configured one
+    pub require_parsable_conditional_branches: bool,
+    // Also check the bodies of function-like macros against the expression rules
+    #[serde(default = "default_true")]
+    pub analyze_macro_bodies: bool,
+
+    // Severity overrides per rule, e.g. restrict_goto = "warning"
+    #[serde(default)]
+    pub severities: HashMap<String, Severity>,
+    // Toggles of the rules loaded from plugins, by rule name. Plugin rules are on
+    // unless turned off here or with --disable.
+    #[serde(default)]
+    pub plugin_rules: HashMap<String, bool>,
+}
+
+fn default_loop_bound_marker() -> String {
+    "loop-bound".to_string()
+}
+
+fn default_max_stack_frame_bytes() -> u64 {
+    1024
+}
+
+fn default_unused_allowed_patterns() -> Vec<String> {
+    vec!["unused_*".to_string()]
+}
+
+fn default_max_function_lines() -> usize {
+    60
+}
+
+fn default_max_parameters() -> usize {
+    6
+}
+
+fn default_max_conditional_nesting() -> usize {
+    1
+}
+
+fn default_min_assertions() -> usize {
+    2
+}
+
+fn default_min_assertion_function_lines() -> usize {
+    10
+}
+
+fn default_pure_functions() -> Vec<String> {
+    [
+        "strlen", "strnlen", "strcmp", "strncmp", "memcmp", "abs", "labs", "fabs", "fabsf",
+    ]
+    .iter()
+    .map(|name| name.to_string())
+    .collect()
+}
+
+fn default_allowed_numbers() -> Vec<f64> {
+    vec![0.0, 1.0, -1.0]
+}
+
+fn default_max_dereference_depth() -> usize {
+    1
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_unsafe_string_functions() -> HashMap<String, String> {
+    [
+        ("gets", "fgets"),
+        ("strcpy", "strncpy"),
+        ("strcat", "strncat"),
+        ("stpcpy", "stpncpy"),
+        ("sprintf", "snprintf"),
+        ("vsprintf", "vsnprintf"),
+        ("wcscpy", "wcsncpy"),
+        ("wcscat", "wcsncat"),
+    ]
+    .iter()
+    .map(|(function, replacement)| (function.to_string(), replacement.to_string()))
+    .collect()
+}
+
+fn default_termination_functions() -> Vec<String> {
+    ["exit", "abort", "_Exit", "quick_exit"]
+        .iter()
+        .map(|name| name.to_string())
+        .collect()
 }
 
-pub fn load_ruleset(file_path: &str) -> RuleSet {
-    let file_content = fs::read_to_string(file_path).expect("Failed to read config file");
-    let config: RulesConfig = toml::from_str(&file_content).expect("Failed to parse config file");
-    config.rule_set
+fn default_printf_functions() -> HashMap<String, usize> {
+    [
+        ("printf", 0),
+        ("fprintf", 1),
+        ("dprintf", 1),
+        ("sprintf", 1),
+        ("snprintf", 2),
+    ]
+    .iter()
+    .map(|&(function, position)| (function.to_string(), position))
+    .collect()
+}
+
+fn default_scanf_functions() -> HashMap<String, usize> {
+    [("scanf", 0), ("fscanf", 1), ("sscanf", 1)]
+        .iter()
+        .map(|&(function, position)| (function.to_string(), position))
+        .collect()
+}
+
+fn default_bit_field_types() -> Vec<String> {
+    [
+        "uint8_t", "uint16_t", "uint32_t", "uint64_t", "int8_t", "int16_t", "int32_t", "int64_t",
+    ]
+    .iter()
+    .map(|name| name.to_string())
+    .collect()
+}
+
+fn default_banned_headers() -> Vec<String> {
+    vec!["setjmp.h".to_string(), "stdlib.h".to_string()]
+}
+
+fn default_fault_handlers() -> Vec<String> {
+    vec!["fault_report".to_string()]
+}
+
+fn default_fallthrough_marker() -> String {
+    "fallthrough".to_string()
+}
+
+fn default_allowed_pragmas() -> Vec<String> {
+    vec!["once".to_string()]
+}
+
+fn default_variadic_library_functions() -> Vec<String> {
+    [
+        "printf", "fprintf", "sprintf", "snprintf", "scanf", "fscanf", "sscanf",
+    ]
+    .iter()
+    .map(|name| name.to_string())
+    .collect()
+}
+
+// Heap functions from the C standard library checked unless excluded
+const DEFAULT_HEAP_FUNCTIONS: [&str; 4] = ["malloc", "calloc", "realloc", "free"];
+
+// Functions allocating a run-time size on the stack, as provided by glibc and GCC
+const DEFAULT_STACK_ALLOCATION_FUNCTIONS: [&str; 3] =
+    ["alloca", "__builtin_alloca", "__builtin_alloca_with_align"];
+
+impl RuleSet {
+    pub fn is_heap_function(&self, name: &str) -> bool {
+        let is_default = DEFAULT_HEAP_FUNCTIONS.contains(&name)
+            && !self
+                .excluded_heap_functions
+                .iter()
+                .any(|excluded| excluded == name);
+        is_default
+            || self
+                .additional_heap_functions
+                .iter()
+                .any(|additional| additional == name)
+    }
+
+    pub fn is_stack_allocation_function(&self, name: &str) -> bool {
+        DEFAULT_STACK_ALLOCATION_FUNCTIONS.contains(&name)
+            || self
+                .additional_stack_allocation_functions
+                .iter()
+                .any(|additional| additional == name)
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.rule(name).unwrap_or(false)
+    }
+
+    pub fn severity(&self, rule: &str) -> Severity {
+        self.severities
+            .get(rule)
+            .copied()
+            .unwrap_or_else(|| default_severity(rule))
+    }
+
+    // Check values that deserialize fine but make no sense for their rule. The upper
+    // limits on parameters and pointer levels are those every C compiler supports.
+    fn validate(&self, location: &str) -> Result<(), AnalyzerError> {
+        let problem = if self.max_function_lines == 0 {
+            "max_function_lines must be greater than zero"
+        } else if !(1..=127).contains(&self.max_parameters) {
+            "max_parameters must be between 1 and 127"
+        } else if self.max_stack_frame_bytes == 0 {
+            "max_stack_frame_bytes must be greater than zero"
+        } else if !(1..=12).contains(&self.max_dereference_depth) {
+            "max_dereference_depth must be between 1 and 12"
+        } else if self.min_assertions > MAX_MIN_ASSERTIONS {
+            "min_assertions must be at most 60"
+        } else if self.loop_bound_marker.trim().is_empty() {
+            "loop_bound_marker must not be empty"
+        } else if self.fallthrough_marker.trim().is_empty() {
+            "fallthrough_marker must not be empty"
+        } else if self
+            .fault_handler
+            .as_ref()
+            .is_some_and(|fault_handler| fault_handler.trim().is_empty())
+        {
+            "fault_handler must not be empty"
+        } else {
+            return Ok(());
+        };
+        Err(config_error(location, problem))
+    }
+
+    // Make the rules of the loaded plugins known, so they can be turned on and off like
+    // the built-in rules. Rules the config does not mention are on.
+    pub fn register_plugin_rules<'a>(&mut self, names: impl IntoIterator<Item = &'a str>) {
+        for name in names {
+            self.plugin_rules.entry(name.to_string()).or_insert(true);
+        }
+    }
+
+    // Apply --enable and --disable overrides from the command line, disables win over enables
+    pub fn apply_overrides(
+        &mut self,
+        enable: &[String],
+        disable: &[String],
+    ) -> Result<(), AnalyzerError> {
+        for (names, enabled) in [(enable, true), (disable, false)] {
+            for name in names {
+                let Some(rule) = self.rule_mut(name) else {
+                    return Err(AnalyzerError::Usage(format!("Unknown rule: {}", name)));
+                };
+                *rule = enabled;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Accessors for the rule toggles by their key in the ruleset, generated from the one
+// list of toggle fields below so the lookups cannot drift apart
+macro_rules! rule_toggles {
+    ($($rule:ident),* $(,)?) => {
+        // Keys of every rule toggle
+        pub const RULE_TOGGLES: &[&str] = &[$(stringify!($rule)),*];
+
+        impl RuleSet {
+            // Look up a rule toggle by its key in the ruleset
+            pub fn rule_mut(&mut self, name: &str) -> Option<&mut bool> {
+                match name {
+                    $(stringify!($rule) => Some(&mut self.$rule),)*
+                    _ => self.plugin_rules.get_mut(name),
+                }
+            }
+
+            // Value of a rule toggle by its key in the ruleset
+            pub fn rule(&self, name: &str) -> Option<bool> {
+                match name {
+                    $(stringify!($rule) => Some(self.$rule),)*
+                    _ => self.plugin_rules.get(name).copied(),
+                }
+            }
+        }
+    };
+}
+
+rule_toggles!(
+    restrict_goto,
+    restrict_setjmp,
+    restrict_longjmp,
+    restrict_recursion,
+    restrict_unstructured_goto,
+    restrict_computed_goto,
+    restrict_unreachable_code,
+    restrict_uninitialized_reads,
+    require_braces,
+    restrict_comma_operator,
+    restrict_nested_conditionals,
+    fixed_loop_bounds,
+    restrict_float_loop_counters,
+    restrict_loop_counter_modification,
+    restrict_heap_allocation,
+    restrict_alloca,
+    restrict_variable_length_arrays,
+    restrict_stack_frame_size,
+    restrict_function_size,
+    restrict_parameter_count,
+    require_prototypes,
+    require_assertions,
+    restrict_assertion_side_effects,
+    check_return_value,
+    require_parameter_validation,
+    require_state_fault_handling,
+    require_switch_default,
+    restrict_implicit_fallthrough,
+    require_exhaustive_enum_switch,
+    restrict_banned_functions,
+    restrict_unsafe_string_functions,
+    restrict_termination_calls,
+    restrict_variadic,
+    restrict_format_mismatches,
+    restrict_banned_headers,
+    require_sizeof_size_arguments,
+    restrict_offset_buffer_overrun,
+    restrict_dereference_depth,
+    restrict_function_pointers,
+    require_const_correctness,
+    restrict_volatile_toctou,
+    restrict_octal_constants,
+    restrict_signed_bitwise,
+    restrict_implicit_narrowing,
+    restrict_unions,
+    restrict_float_equality,
+    restrict_magic_numbers,
+    restrict_locale_apis,
+    restrict_bit_field_types,
+    require_static_functions,
+    require_smallest_scope,
+    restrict_global_mutable_data,
+    restrict_unused_variables,
+    restrict_shadowing,
+    prefer_typed_constants,
+    restrict_complex_macros,
+    restrict_undef_and_redefinition,
+    restrict_include_placement,
+    restrict_include_paths,
+    restrict_pragmas,
+    require_parsable_conditional_branches,
+);
+
+// Resolve the ruleset that applies to a source file. The base ruleset, or the
+// embedded default when there is none, is applied first, followed by every
+// .nasa-analyzer config from the outermost directory inwards, so subsystems can
+// tighten or relax individual rules. Each file may be TOML, YAML or JSON. The
+// ruleset of the workspace module the file belongs to takes the place of the base
+// ruleset, and a preset given on the command line goes underneath either.
+pub fn resolve_ruleset(
+    source_path: &Path,
+    preset: Option<&str>,
+    module_ruleset: Option<&Path>,
+) -> Result<RuleSet, AnalyzerError> {
+    let merged = merge_config_layers(source_path, preset, module_ruleset)?;
+    ruleset_for_path(merged, source_path)
+}
+
+// Merged config layers by source directory, preset and module ruleset. Files in the
+// same directory share every layer, so the config files are read and parsed once per
+// directory; only the [[overrides]] are matched for each file. Shared between the
+// threads analyzing files in parallel.
+#[derive(Default)]
+pub struct RulesetCache {
+    layers: Mutex<HashMap<LayersKey, toml::Table>>,
+}
+
+type LayersKey = (PathBuf, Option<String>, Option<PathBuf>);
+
+impl RulesetCache {
+    // Same as resolve_ruleset, reusing the layers merged for an earlier file
+    pub fn resolve(
+        &self,
+        source_path: &Path,
+        preset: Option<&str>,
+        module_ruleset: Option<&Path>,
+    ) -> Result<RuleSet, AnalyzerError> {
+        let directory = fs::canonicalize(source_path)
+            .unwrap_or_else(|_| source_path.to_path_buf())
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let key = (
+            directory,
+            preset.map(str::to_string),
+            module_ruleset.map(Path::to_path_buf),
+        );
+
+        let cached = self.lock().get(&key).cloned();
+        let merged = match cached {
+            Some(merged) => merged,
+            None => {
+                let merged = merge_config_layers(source_path, preset, module_ruleset)?;
+                self.lock().insert(key, merged.clone());
+                merged
+            }
+        };
+        ruleset_for_path(merged, source_path)
+    }
+
+    // Forget the merged layers, so edited config files are read again
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<LayersKey, toml::Table>> {
+        // A thread that panicked mid-insert leaves nothing half-written behind
+        self.layers.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+// Every config layer that applies to a source file merged in order, before the
+// [[overrides]] are matched against its path
+fn merge_config_layers(
+    source_path: &Path,
+    preset: Option<&str>,
+    module_ruleset: Option<&Path>,
+) -> Result<toml::Table, AnalyzerError> {
+    let base_ruleset = module_ruleset.or_else(|| {
+        BASE_RULESET_PATHS
+            .iter()
+            .map(Path::new)
+            .find(|path| path.is_file())
+    });
+    let mut merged = match preset {
+        Some(preset) => preset_table(preset, "--preset")?,
+        None => toml::Table::new(),
+    };
+    match base_ruleset {
+        Some(path) => merge_tables(&mut merged, load_config_table(path)?),
+        // The embedded default only stands in for a missing base ruleset without a preset
+        None if preset.is_none() => merge_tables(
+            &mut merged,
+            parse_config_layer("the default ruleset", DEFAULT_RULESET, ConfigFormat::Toml)?,
+        ),
+        None => {}
+    }
+
+    for layer in &discover_config_files(source_path) {
+        merge_tables(&mut merged, load_config_table(layer)?);
+    }
+    Ok(merged)
+}
+
+// Apply the [[overrides]] matching a source file to the merged layers and check the result
+fn ruleset_for_path(mut merged: toml::Table, source_path: &Path) -> Result<RuleSet, AnalyzerError> {
+    let location = format!("the ruleset for {}", source_path.display());
+    apply_path_overrides(&mut merged, source_path, &location)?;
+
+    let config: RulesConfig = toml::Value::Table(merged)
+        .try_into()
+        .map_err(|error: toml::de::Error| config_error(&location, error.message()))?;
+    config.rule_set.validate(&location)?;
+    Ok(config.rule_set)
+}
+
+fn config_error(location: &str, message: &str) -> AnalyzerError {
+    AnalyzerError::ConfigParse {
+        location: location.to_string(),
+        message: message.to_string(),
+    }
+}
+
+// Find every config file that applies to a source file, ordered from the outermost directory inwards
+fn discover_config_files(source_path: &Path) -> Vec<PathBuf> {
+    let source_path = fs::canonicalize(source_path).unwrap_or_else(|_| source_path.to_path_buf());
+
+    let mut config_files = Vec::new();
+    let mut directory = source_path.parent();
+    while let Some(current) = directory {
+        let candidate = CONFIG_FILE_NAMES
+            .iter()
+            .map(|name| current.join(name))
+            .find(|candidate| candidate.is_file());
+        config_files.extend(candidate);
+        directory = current.parent();
+    }
+
+    config_files.reverse();
+    config_files
+}
+
+fn load_config_table(file_path: &Path) -> Result<toml::Table, AnalyzerError> {
+    load_config_chain(file_path, &mut Vec::new())
+}
+
+// Load a config file on top of the file it extends, if any. `chain` holds the files
+// currently being loaded, so a file that ends up extending itself is reported.
+fn load_config_chain(
+    file_path: &Path,
+    chain: &mut Vec<PathBuf>,
+) -> Result<toml::Table, AnalyzerError> {
+    let location = file_path.display().to_string();
+    let canonical_path = fs::canonicalize(file_path).unwrap_or_else(|_| file_path.to_path_buf());
+    if chain.contains(&canonical_path) {
+        let cycle: Vec<String> = chain
+            .iter()
+            .chain([&canonical_path])
+            .map(|path| path.display().to_string())
+            .collect();
+        let message = format!(
+            "config files extend each other in a cycle: {}",
+            cycle.join(" -> ")
+        );
+        return Err(config_error(&location, &message));
+    }
+
+    let file_content = fs::read_to_string(file_path).map_err(|error| AnalyzerError::ConfigIo {
+        path: file_path.to_path_buf(),
+        error,
+    })?;
+    let mut table =
+        parse_config_layer(&location, &file_content, ConfigFormat::from_path(file_path))?;
+    anchor_override_paths(&mut table, file_path);
+    let extends = table.remove("extends");
+    let table = expand_preset(table, &location)?;
+
+    let Some(extends) = extends else {
+        return Ok(table);
+    };
+    let Some(extends) = extends.as_str() else {
+        return Err(config_error(&location, "extends must be a path"));
+    };
+    let base_path = file_path.parent().unwrap_or(Path::new("")).join(extends);
+    chain.push(canonical_path);
+    let mut merged = load_config_chain(&base_path, chain)?;
+    chain.pop();
+
+    merge_tables(&mut merged, table);
+    Ok(merged)
+}
+
+// Replace the preset key of a config file with the preset's rule settings, which
+// the keys set in the file itself then override
+fn expand_preset(mut table: toml::Table, location: &str) -> Result<toml::Table, AnalyzerError> {
+    let Some(preset) = table.remove("preset") else {
+        return Ok(table);
+    };
+    let Some(preset) = preset.as_str() else {
+        return Err(config_error(location, "preset must be a string"));
+    };
+    let mut expanded = preset_table(preset, location)?;
+    merge_tables(&mut expanded, table);
+    Ok(expanded)
+}
+
+// Config table enabling exactly the rules of a preset and disabling all others
+fn preset_table(preset: &str, location: &str) -> Result<toml::Table, AnalyzerError> {
+    let enabled: fn(&RuleMetadata) -> bool = match preset {
+        "power-of-ten" => |rule| rule.power_of_ten.is_some(),
+        "jpl-c" => |rule| JPL_C_PRESET_RULES.contains(&rule.name),
+        "minimal" => |rule| MINIMAL_PRESET_RULES.contains(&rule.name),
+        _ => {
+            let message = format!(
+                "unknown preset {}, expected one of {}",
+                preset,
+                PRESETS.join(", ")
+            );
+            return Err(config_error(location, &message));
+        }
+    };
+
+    let rule_set: toml::Table = RULES
+        .iter()
+        .map(|rule| (rule.name.to_string(), toml::Value::Boolean(enabled(rule))))
+        .collect();
+    let mut table = toml::Table::new();
+    table.insert("rule_set".to_string(), toml::Value::Table(rule_set));
+    Ok(table)
+}
+
+// Parse a single config file. Deserializing it on its own reports the line and key
+// of syntax errors, unknown keys and mistyped values, which are lost once the
+// layers are merged. Every key has a default, so a layer may override only some.
+// YAML and JSON layers are converted to TOML tables so all layers merge the same way.
+fn parse_config_layer(
+    location: &str,
+    content: &str,
+    format: ConfigFormat,
+) -> Result<toml::Table, AnalyzerError> {
+    format
+        .deserialize::<RulesConfig>(content)
+        .map_err(|error| config_error(location, &error))?;
+    let table = format
+        .deserialize(content)
+        .map_err(|error| config_error(location, &error))?;
+    validate_overrides(&table, location)?;
+    Ok(table)
+}
+
+// Check every [[overrides]] entry of a config file, rather than only those that
+// happen to match an analyzed file
+fn validate_overrides(table: &toml::Table, location: &str) -> Result<(), AnalyzerError> {
+    let Some(toml::Value::Array(entries)) = table.get("overrides") else {
+        return Ok(());
+    };
+    for entry in entries {
+        let Some(entry) = entry.as_table() else {
+            return Err(config_error(
+                location,
+                "[[overrides]] entries must be tables",
+            ));
+        };
+        let mut rule_set = entry.clone();
+        let Some(toml::Value::Array(paths)) = rule_set.remove("paths") else {
+            return Err(config_error(
+                location,
+                "[[overrides]] entry without a paths list",
+            ));
+        };
+        if !paths.iter().all(toml::Value::is_str) {
+            return Err(config_error(
+                location,
+                "[[overrides]] paths must be strings",
+            ));
+        }
+        let rule_set: RuleSet =
+            toml::Value::Table(rule_set)
+                .try_into()
+                .map_err(|error: toml::de::Error| {
+                    config_error(
+                        location,
+                        &format!("[[overrides]] entry: {}", error.message()),
+                    )
+                })?;
+        rule_set.validate(location)?;
+    }
+    Ok(())
+}
+
+// Make the patterns of [[overrides]] entries absolute, as they are relative to the
+// directory of the config file that defines them
+fn anchor_override_paths(table: &mut toml::Table, file_path: &Path) {
+    let Some(toml::Value::Array(entries)) = table.get_mut("overrides") else {
+        return;
+    };
+    let directory = fs::canonicalize(file_path)
+        .ok()
+        .and_then(|path| path.parent().map(Path::to_path_buf))
+        .unwrap_or_default();
+
+    for entry in entries.iter_mut().filter_map(toml::Value::as_table_mut) {
+        let Some(toml::Value::Array(paths)) = entry.get_mut("paths") else {
+            continue;
+        };
+        for path in paths.iter_mut() {
+            if let Some(pattern) = path.as_str() {
+                *path = toml::Value::String(directory.join(pattern).to_string_lossy().to_string());
+            }
+        }
+    }
+}
+
+// Merge the rule settings of every [[overrides]] entry matching the source file into the ruleset
+fn apply_path_overrides(
+    merged: &mut toml::Table,
+    source_path: &Path,
+    location: &str,
+) -> Result<(), AnalyzerError> {
+    let Some(toml::Value::Array(entries)) = merged.remove("overrides") else {
+        return Ok(());
+    };
+    let source_path = fs::canonicalize(source_path).unwrap_or_else(|_| source_path.to_path_buf());
+    let source_path = source_path.to_string_lossy();
+
+    for entry in entries {
+        let toml::Value::Table(mut entry) = entry else {
+            return Err(config_error(
+                location,
+                "[[overrides]] entries must be tables",
+            ));
+        };
+        let Some(toml::Value::Array(paths)) = entry.remove("paths") else {
+            return Err(config_error(
+                location,
+                "[[overrides]] entry without a paths list",
+            ));
+        };
+        let matches = paths
+            .iter()
+            .filter_map(toml::Value::as_str)
+            .any(|pattern| glob_matches(pattern, &source_path));
+        if !matches {
+            continue;
+        }
+        if let Some(toml::Value::Table(rule_set)) = merged.get_mut("rule_set") {
+            merge_tables(rule_set, entry);
+        }
+    }
+    Ok(())
+}
+
+// Match a path against a glob pattern. `*` and `?` match within a path segment,
+// `**` matches any number of whole segments.
+pub(crate) fn glob_matches(pattern: &str, path: &str) -> bool {
+    if let Some(rest) = pattern.strip_prefix("**") {
+        let rest = rest.strip_prefix('/').unwrap_or(rest);
+        if rest.is_empty() {
+            return true;
+        }
+        return path
+            .char_indices()
+            .filter(|&(index, _)| index == 0 || path[..index].ends_with('/'))
+            .any(|(index, _)| glob_matches(rest, &path[index..]));
+    }
+
+    let mut chars = pattern.chars();
+    match chars.next() {
+        None => path.is_empty(),
+        Some('*') => {
+            let rest = chars.as_str();
+            let segment_end = path.find('/').unwrap_or(path.len());
+            (0..=segment_end)
+                .filter(|&index| path.is_char_boundary(index))
+                .any(|index| glob_matches(rest, &path[index..]))
+        }
+        Some('?') => path
+            .chars()
+            .next()
+            .is_some_and(|c| c != '/' && glob_matches(chars.as_str(), &path[c.len_utf8()..])),
+        Some(expected) => path
+            .strip_prefix(expected)
+            .is_some_and(|rest| glob_matches(chars.as_str(), rest)),
+    }
+}
+
+// Recursively merge `overrides` into `base`, with values from `overrides` taking
+// precedence. [[overrides]] lists are concatenated so every file's entries apply.
+fn merge_tables(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match value {
+            toml::Value::Array(entries) if key == "overrides" => match base.get_mut(&key) {
+                Some(toml::Value::Array(base_entries)) => base_entries.extend(entries),
+                _ => {
+                    base.insert(key, toml::Value::Array(entries));
+                }
+            },
+            toml::Value::Table(override_table) => match base.get_mut(&key) {
+                Some(toml::Value::Table(base_table)) => merge_tables(base_table, override_table),
+                _ => {
+                    base.insert(key, toml::Value::Table(override_table));
+                }
+            },
+            value => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Files written under the system temp directory, removed again on drop
+    struct TempTree(PathBuf);
+
+    impl TempTree {
+        fn new(name: &str, files: &[(&str, &str)]) -> Self {
+            let root =
+                std::env::temp_dir().join(format!("nasa-analyzer-{}-{}", name, std::process::id()));
+            for (path, content) in files {
+                let path = root.join(path);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(path, content).unwrap();
+            }
+            TempTree(fs::canonicalize(root).unwrap())
+        }
+
+        fn path(&self, path: &str) -> PathBuf {
+            self.0.join(path)
+        }
+    }
+
+    impl Drop for TempTree {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn layered_tree(name: &str) -> TempTree {
+        TempTree::new(
+            name,
+            &[
+                (
+                    "base.toml",
+                    "[rule_set]\nrestrict_goto = false\nmax_function_lines = 40\nmax_parameters = 5\n",
+                ),
+                (
+                    "project/.nasa-analyzer.toml",
+                    "[rule_set]\nmax_function_lines = 50\n\n\
+                     [[overrides]]\npaths = [\"driver/uart.c\"]\nmax_function_lines = 80\n",
+                ),
+                (
+                    "project/driver/.nasa-analyzer.toml",
+                    "[rule_set]\nmax_function_lines = 60\nmax_parameters = 8\n",
+                ),
+                ("project/driver/uart.c", ""),
+                ("project/driver/spi.c", ""),
+                ("project/main.c", ""),
+            ],
+        )
+    }
+
+    #[test]
+    fn layers_apply_from_preset_to_overrides() {
+        let tree = layered_tree("layers");
+        let base = tree.path("base.toml");
+        let resolve = |file: &str| {
+            resolve_ruleset(&tree.path(file), Some("jpl-c"), Some(base.as_path())).unwrap()
+        };
+
+        let uart = resolve("project/driver/uart.c");
+        // The base ruleset goes over the preset, keys it leaves out keep the preset's value
+        assert!(!uart.restrict_goto);
+        assert!(uart.restrict_setjmp);
+        // Directory configs go over the base ruleset, inner directories over outer ones
+        assert_eq!(uart.max_parameters, 8);
+        // Overrides go over every config file, wherever they are declared
+        assert_eq!(uart.max_function_lines, 80);
+
+        let main = resolve("project/main.c");
+        assert_eq!(main.max_function_lines, 50);
+        assert_eq!(main.max_parameters, 5);
+    }
+
+    #[test]
+    fn cached_layers_still_match_overrides_per_file() {
+        let tree = layered_tree("cache");
+        let base = tree.path("base.toml");
+        let rulesets = RulesetCache::default();
+        let resolve = |file: &str| {
+            rulesets
+                .resolve(&tree.path(file), None, Some(base.as_path()))
+                .unwrap()
+        };
+
+        assert_eq!(resolve("project/driver/uart.c").max_function_lines, 80);
+        assert_eq!(resolve("project/driver/spi.c").max_function_lines, 60);
+    }
+
+    #[test]
+    fn invalid_overrides_entries_are_rejected() {
+        let layer = |content: &str| parse_config_layer("test", content, ConfigFormat::Toml);
+        assert!(layer("[[overrides]]\npaths = [\"*.c\"]\nmax_parameters = 4\n").is_ok());
+        assert!(layer("[[overrides]]\npaths = [\"*.c\"]\nmax_parameters = 0\n").is_err());
+        assert!(layer("[[overrides]]\nmax_parameters = 4\n").is_err());
+    }
+
+    #[test]
+    fn every_rule_has_a_toggle() {
+        let rule_set = RuleSet::default();
+        for rule in RULES {
+            assert!(
+                rule_set.rule(rule.name).is_some(),
+                "{} has no toggle",
+                rule.name
+            );
+        }
+        assert_eq!(RULE_TOGGLES.len(), RULES.len());
+    }
+
+    #[test]
+    fn plugin_rules_can_be_disabled() {
+        let mut rule_set = RuleSet::default();
+        assert!(rule_set
+            .apply_overrides(&[], &["no_printf".to_string()])
+            .is_err());
+
+        rule_set.register_plugin_rules(["no_printf"]);
+        assert!(rule_set.is_enabled("no_printf"));
+        rule_set
+            .apply_overrides(&[], &["no_printf".to_string()])
+            .unwrap();
+        assert!(!rule_set.is_enabled("no_printf"));
+    }
 }
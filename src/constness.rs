@@ -0,0 +1,214 @@
+use std::collections::HashSet;
+
+use lang_c::ast::{
+    BinaryOperator, BinaryOperatorExpression, CallExpression, DerivedDeclarator, Expression,
+    FunctionDefinition, Initializer, MemberOperator, Statement, UnaryOperator,
+    UnaryOperatorExpression,
+};
+use lang_c::span::Span;
+use lang_c::visit::{
+    visit_binary_operator_expression, visit_call_expression, visit_initializer, visit_statement,
+    visit_unary_operator_expression, Visit,
+};
+
+use crate::analyzer::{declarator_name, is_assignment_operator, is_const};
+
+// Pointer parameters of a function whose pointee is not const but is never written
+// through in the body, with the span of their declarator. A parameter counts as
+// written once its pointee is assigned, incremented or decremented, or once the
+// pointer itself may reach code that could write through it: passed to a function,
+// returned, or stored, e.g. `cursor = buffer + 1`. Only parameters declared with a
+// single pointer level, such as `struct frame *frame`, are checked.
+pub(crate) fn unwritten_pointer_parameters(
+    function_definition: &FunctionDefinition,
+) -> Vec<(&str, Span)> {
+    let mut parameters = Vec::new();
+    for derived in &function_definition.declarator.node.derived {
+        let DerivedDeclarator::Function(function_declarator) = &derived.node else {
+            continue;
+        };
+        for parameter in &function_declarator.node.parameters {
+            let Some(declarator) = &parameter.node.declarator else {
+                continue;
+            };
+            let is_single_pointer = matches!(
+                &declarator.node.derived[..],
+                [pointer] if matches!(pointer.node, DerivedDeclarator::Pointer(_))
+            );
+            if !is_single_pointer || is_const(&parameter.node.specifiers) {
+                continue;
+            }
+            if let Some(name) = declarator_name(&declarator.node) {
+                parameters.push((name, declarator.span));
+            }
+        }
+    }
+    if parameters.is_empty() {
+        return parameters;
+    }
+
+    let mut uses = PointerUses {
+        tracked: parameters.iter().map(|&(name, _)| name).collect(),
+        written: HashSet::new(),
+    };
+    let statement = &function_definition.statement;
+    uses.visit_statement(&statement.node, &statement.span);
+    parameters.retain(|(name, _)| !uses.written.contains(name));
+    parameters
+}
+
+// Finds the tracked pointers that are written through, or that escape to code that
+// might write through them
+struct PointerUses<'ast> {
+    tracked: HashSet<&'ast str>,
+    written: HashSet<&'ast str>,
+}
+
+impl<'ast> PointerUses<'ast> {
+    fn mark(&mut self, name: Option<&'ast str>) {
+        if let Some(name) = name.filter(|name| self.tracked.contains(name)) {
+            self.written.insert(name);
+        }
+    }
+
+    // Mark the pointers whose value an expression may hand on. Member accesses through
+    // a pointer count when `members` is set, since an array member decays to a pointer
+    // into the pointee, e.g. `memset(frame->data, 0, n)`.
+    fn mark_exposed(&mut self, expression: &'ast Expression, members: bool) {
+        match expression {
+            Expression::Identifier(identifier) => self.mark(Some(&identifier.node.name)),
+            Expression::Cast(cast_expression) => {
+                self.mark_exposed(&cast_expression.node.expression.node, members)
+            }
+            Expression::Conditional(conditional_expression) => {
+                let conditional_expression = &conditional_expression.node;
+                self.mark_exposed(&conditional_expression.then_expression.node, members);
+                self.mark_exposed(&conditional_expression.else_expression.node, members);
+            }
+            Expression::Comma(expressions) => {
+                if let Some(last) = expressions.last() {
+                    self.mark_exposed(&last.node, members);
+                }
+            }
+            Expression::BinaryOperator(binary_operator_expression) => {
+                let binary_operator_expression = &binary_operator_expression.node;
+                match binary_operator_expression.operator.node {
+                    BinaryOperator::Plus | BinaryOperator::Minus => {
+                        self.mark_exposed(&binary_operator_expression.lhs.node, members);
+                        self.mark_exposed(&binary_operator_expression.rhs.node, members);
+                    }
+                    BinaryOperator::Assign => {
+                        self.mark_exposed(&binary_operator_expression.rhs.node, members)
+                    }
+                    _ => {}
+                }
+            }
+            Expression::UnaryOperator(unary_operator_expression)
+                if unary_operator_expression.node.operator.node == UnaryOperator::Address =>
+            {
+                let operand = &unary_operator_expression.node.operand.node;
+                self.mark(pointer_name(operand).or_else(|| written_pointer(operand)));
+            }
+            Expression::Member(_) if members => self.mark(written_pointer(expression)),
+            _ => {}
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for PointerUses<'ast> {
+    fn visit_statement(&mut self, statement: &'ast Statement, span: &'ast Span) {
+        if let Statement::Return(Some(expression)) = statement {
+            self.mark_exposed(&expression.node, true);
+        }
+        visit_statement(self, statement, span);
+    }
+
+    fn visit_initializer(&mut self, initializer: &'ast Initializer, span: &'ast Span) {
+        if let Initializer::Expression(expression) = initializer {
+            self.mark_exposed(&expression.node, false);
+        }
+        visit_initializer(self, initializer, span);
+    }
+
+    fn visit_call_expression(&mut self, call_expression: &'ast CallExpression, span: &'ast Span) {
+        for argument in &call_expression.arguments {
+            self.mark_exposed(&argument.node, true);
+        }
+        visit_call_expression(self, call_expression, span);
+    }
+
+    fn visit_binary_operator_expression(
+        &mut self,
+        binary_operator_expression: &'ast BinaryOperatorExpression,
+        span: &'ast Span,
+    ) {
+        if is_assignment_operator(&binary_operator_expression.operator.node) {
+            self.mark(written_pointer(&binary_operator_expression.lhs.node));
+            self.mark_exposed(&binary_operator_expression.rhs.node, false);
+        }
+        visit_binary_operator_expression(self, binary_operator_expression, span);
+    }
+
+    fn visit_unary_operator_expression(
+        &mut self,
+        unary_operator_expression: &'ast UnaryOperatorExpression,
+        span: &'ast Span,
+    ) {
+        if matches!(
+            unary_operator_expression.operator.node,
+            UnaryOperator::PreIncrement
+                | UnaryOperator::PreDecrement
+                | UnaryOperator::PostIncrement
+                | UnaryOperator::PostDecrement
+        ) {
+            self.mark(written_pointer(&unary_operator_expression.operand.node));
+        }
+        visit_unary_operator_expression(self, unary_operator_expression, span);
+    }
+}
+
+// Pointer an lvalue is reached through, e.g. `frame` for `*frame`, `frame->length`,
+// `frame[i]` or `frame->header.crc`. Indexing a member counts as going through the
+// pointer the member is reached through, the member may be an array.
+fn written_pointer(expression: &Expression) -> Option<&str> {
+    match expression {
+        Expression::UnaryOperator(unary_operator_expression)
+            if unary_operator_expression.node.operator.node == UnaryOperator::Indirection =>
+        {
+            pointer_name(&unary_operator_expression.node.operand.node)
+        }
+        Expression::Member(member_expression) => {
+            let member_expression = &member_expression.node;
+            match member_expression.operator.node {
+                MemberOperator::Indirect => pointer_name(&member_expression.expression.node),
+                MemberOperator::Direct => written_pointer(&member_expression.expression.node),
+            }
+        }
+        Expression::BinaryOperator(binary_operator_expression)
+            if binary_operator_expression.node.operator.node == BinaryOperator::Index =>
+        {
+            let lhs = &binary_operator_expression.node.lhs.node;
+            pointer_name(lhs).or_else(|| written_pointer(lhs))
+        }
+        _ => None,
+    }
+}
+
+// Variable a pointer expression is computed from, looking through casts and pointer
+// arithmetic, e.g. `buffer` for `(uint8_t *)buffer + offset`
+fn pointer_name(expression: &Expression) -> Option<&str> {
+    match expression {
+        Expression::Identifier(identifier) => Some(&identifier.node.name),
+        Expression::Cast(cast_expression) => pointer_name(&cast_expression.node.expression.node),
+        Expression::BinaryOperator(binary_operator_expression)
+            if matches!(
+                binary_operator_expression.node.operator.node,
+                BinaryOperator::Plus | BinaryOperator::Minus
+            ) =>
+        {
+            pointer_name(&binary_operator_expression.node.lhs.node)
+                .or_else(|| pointer_name(&binary_operator_expression.node.rhs.node))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use nasa_static_analyzer::config::resolve_ruleset;
+use nasa_static_analyzer::{analyze_file_to, analyze_source_to};
+use nasa_static_analyzer::{AnalyzerError, Diagnostic, DiagnosticSink, RuleSet};
+
+use crate::cli::Options;
+
+// JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+// The analysis itself failed, e.g. the file could not be read or preprocessed
+const ANALYSIS_FAILED: i64 = -32000;
+
+// Configs are resolved for this path when a request only has source, so the configs
+// of the working directory apply
+const SOURCE_CONFIG_PATH: &str = "<source>";
+
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Option<Value>, // Absent for notifications, which get no response
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+// Parameters of `analyze`: a file to analyze, or source held by the client. With
+// both, the source is analyzed with the configs that apply to the path.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AnalyzeParams {
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    source: Option<String>,
+}
+
+// Long-running analyzer answering newline-delimited JSON-RPC requests:
+//
+//   {"jsonrpc": "2.0", "id": 1, "method": "analyze", "params": {"path": "src/gnc.c"}}
+//
+// Each finding is streamed as a `diagnostic` notification carrying the request ID,
+// followed by the response with the number of findings. `reload` drops the cached
+// rulesets after config changes and `shutdown` stops the server.
+struct Server<'a> {
+    options: &'a Options,
+    rule_sets: HashMap<PathBuf, RuleSet>, // Resolved rulesets by source path
+}
+
+impl Server<'_> {
+    // Answer requests until the client disconnects, returns whether it asked to shut down
+    fn serve(&mut self, reader: impl BufRead, mut writer: impl Write) -> io::Result<bool> {
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let request: Request = match serde_json::from_str(&line) {
+                Ok(request) => request,
+                Err(error) => {
+                    let response = error_response(&Value::Null, PARSE_ERROR, &error.to_string());
+                    write_message(&mut writer, &response)?;
+                    continue;
+                }
+            };
+            let id = request.id.clone().unwrap_or(Value::Null);
+
+            let response = match request.method.as_str() {
+                "analyze" => self.analyze(&id, request.params, &mut writer)?,
+                "reload" => {
+                    self.rule_sets.clear();
+                    result_response(&id, Value::Null)
+                }
+                "shutdown" => {
+                    write_message(&mut writer, &result_response(&id, Value::Null))?;
+                    return Ok(true);
+                }
+                method => error_response(
+                    &id,
+                    METHOD_NOT_FOUND,
+                    &format!("Unknown method '{}'", method),
+                ),
+            };
+            if request.id.is_some() {
+                write_message(&mut writer, &response)?;
+            }
+        }
+        Ok(false)
+    }
+
+    fn analyze(&mut self, id: &Value, params: Value, writer: &mut impl Write) -> io::Result<Value> {
+        let params: AnalyzeParams = match serde_json::from_value(params) {
+            Ok(params) => params,
+            Err(error) => return Ok(error_response(id, INVALID_PARAMS, &error.to_string())),
+        };
+        let config_path = params.path.as_deref().unwrap_or(SOURCE_CONFIG_PATH);
+        let rule_set = match self.rule_set(Path::new(config_path)) {
+            Ok(rule_set) => rule_set,
+            Err(error) => return Ok(error_response(id, ANALYSIS_FAILED, &error.to_string())),
+        };
+
+        let mut sink = NotificationSink {
+            writer,
+            id,
+            findings: 0,
+            error: None,
+        };
+        let result = match (&params.source, &params.path) {
+            (Some(source), _) => {
+                analyze_source_to(source, &rule_set, &mut sink);
+                Ok(())
+            }
+            (None, Some(path)) => analyze_file_to(Path::new(path), &rule_set, &mut sink),
+            (None, None) => {
+                let message = "analyze requires a path or a source";
+                return Ok(error_response(id, INVALID_PARAMS, message));
+            }
+        };
+        if let Some(error) = sink.error {
+            return Err(error);
+        }
+
+        Ok(match result {
+            Ok(()) => result_response(id, json!({ "findings": sink.findings })),
+            Err(error) => error_response(id, ANALYSIS_FAILED, &error.to_string()),
+        })
+    }
+
+    // Ruleset for a source path, resolved once and reused until `reload`
+    fn rule_set(&mut self, path: &Path) -> Result<RuleSet, AnalyzerError> {
+        if let Some(rule_set) = self.rule_sets.get(path) {
+            return Ok(rule_set.clone());
+        }
+        let mut rule_set = resolve_ruleset(path, self.options.preset.as_deref(), None)?;
+        rule_set.apply_overrides(&self.options.enable, &self.options.disable)?;
+        self.rule_sets.insert(path.to_path_buf(), rule_set.clone());
+        Ok(rule_set)
+    }
+}
+
+// Streams each finding to the client as a `diagnostic` notification
+struct NotificationSink<'a, W: Write> {
+    writer: &'a mut W,
+    id: &'a Value,
+    findings: usize,
+    error: Option<io::Error>, // First failure to write, reported once the analysis is done
+}
+
+impl<W: Write> DiagnosticSink for NotificationSink<'_, W> {
+    fn report(&mut self, diagnostic: Diagnostic) {
+        self.findings += 1;
+        if self.error.is_some() {
+            return;
+        }
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "diagnostic",
+            "params": { "id": self.id, "diagnostic": diagnostic },
+        });
+        if let Err(error) = write_message(self.writer, &notification) {
+            self.error = Some(error);
+        }
+    }
+}
+
+fn result_response(id: &Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn error_response(id: &Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+// One message per line, flushed right away so clients can act on it
+fn write_message(writer: &mut impl Write, message: &Value) -> io::Result<()> {
+    writeln!(writer, "{}", message)?;
+    writer.flush()
+}
+
+// Serve requests on stdin and stdout until stdin is closed or `shutdown` is received
+pub fn serve_stdio(options: &Options) -> Result<(), AnalyzerError> {
+    let mut server = Server {
+        options,
+        rule_sets: HashMap::new(),
+    };
+    server
+        .serve(io::stdin().lock(), io::stdout().lock())
+        .map_err(|error| AnalyzerError::Daemon(error.to_string()))?;
+    Ok(())
+}
+
+// Serve clients connecting to a Unix domain socket one at a time, sharing the cached
+// rulesets between them, until a client sends `shutdown`
+#[cfg(unix)]
+pub fn serve_socket(options: &Options, path: &str) -> Result<(), AnalyzerError> {
+    use std::os::unix::net::UnixListener;
+
+    let daemon_error = |error: io::Error| AnalyzerError::Daemon(format!("{}: {}", path, error));
+    let listener = UnixListener::bind(path).map_err(daemon_error)?;
+    let mut server = Server {
+        options,
+        rule_sets: HashMap::new(),
+    };
+
+    for stream in listener.incoming() {
+        let stream = stream.map_err(daemon_error)?;
+        let reader = BufReader::new(stream.try_clone().map_err(daemon_error)?);
+        // A client dropping its connection only ends that session
+        match server.serve(reader, &stream) {
+            Ok(true) => break,
+            Ok(false) => {}
+            Err(error) => eprintln!("warning: {}", error),
+        }
+    }
+
+    let _ = std::fs::remove_file(path);
+    Ok(())
+}
@@ -0,0 +1,472 @@
+use std::collections::{HashMap, HashSet};
+
+use lang_c::ast::{
+    BinaryOperator, BinaryOperatorExpression, Declaration, DeclarationSpecifier, Declarator,
+    DeclaratorKind, DerivedDeclarator, Expression, FunctionDefinition, Identifier, MemberOperator,
+    Statement, StorageClassSpecifier, UnaryOperator, UnaryOperatorExpression,
+};
+use lang_c::span::{Node, Span};
+use lang_c::visit::{
+    visit_binary_operator_expression, visit_expression, visit_statement,
+    visit_unary_operator_expression, Visit,
+};
+
+use crate::cfg::{ControlFlowGraph, Element};
+
+// A local variable: its name and the offset of the identifier declaring it, which
+// tells apart variables of the same name declared in different scopes
+type Variable<'ast> = (&'ast str, usize);
+
+// What an element of a basic block does to a local variable, in evaluation order
+#[derive(Debug, Clone, Copy)]
+enum Effect<'ast> {
+    Read(Variable<'ast>, Span),
+    Assign(Variable<'ast>),
+    Declare(Variable<'ast>), // Declared without an initializer, so holding no value yet
+}
+
+// Reads of local variables along a path on which they have not been assigned, in
+// source order and once per variable. A variable counts as assigned on a path once
+// it is initialized, assigned, or has its address taken, e.g. to be filled in by
+// `read_sensor(&value)`. Arrays are left out, they are usually filled through a
+// pointer, as are static locals, which start out zeroed.
+pub(crate) fn uninitialized_reads(function_definition: &FunctionDefinition) -> Vec<(&str, Span)> {
+    let graph = ControlFlowGraph::build(function_definition);
+    let reachable = graph.reachable();
+    let declarations = resolve_declarations(function_definition);
+    let mut tracked = HashSet::new();
+    let mut arrays = HashSet::new();
+    let effects: Vec<Vec<Effect>> = graph
+        .blocks
+        .iter()
+        .map(|block| {
+            let mut collector = EffectCollector {
+                declarations: &declarations,
+                tracked: &mut tracked,
+                arrays: &mut arrays,
+                effects: Vec::new(),
+            };
+            for element in &block.elements {
+                collector.element(element);
+            }
+            collector.effects
+        })
+        .collect();
+
+    let mut predecessors = vec![Vec::new(); graph.blocks.len()];
+    for (block, basic_block) in graph.blocks.iter().enumerate() {
+        if reachable[block] {
+            for &successor in &basic_block.successors {
+                predecessors[successor].push(block);
+            }
+        }
+    }
+
+    // Variables assigned on every path to the end of each block, None until the block
+    // is first evaluated, which the intersection over predecessors skips
+    let mut assigned_out: Vec<Option<HashSet<Variable>>> = vec![None; graph.blocks.len()];
+    let assigned_in = |assigned_out: &[Option<HashSet<Variable>>], block: usize| {
+        if block == ControlFlowGraph::ENTRY {
+            return Some(HashSet::new());
+        }
+        predecessors[block]
+            .iter()
+            .filter_map(|&predecessor| assigned_out[predecessor].as_ref())
+            .fold(
+                None,
+                |assigned: Option<HashSet<Variable>>, out| match assigned {
+                    None => Some(out.clone()),
+                    Some(assigned) => Some(assigned.intersection(out).copied().collect()),
+                },
+            )
+    };
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for block in (0..graph.blocks.len()).filter(|&block| reachable[block]) {
+            let Some(mut assigned) = assigned_in(&assigned_out, block) else {
+                continue;
+            };
+            apply_effects(&effects[block], &tracked, &mut assigned, &mut Vec::new());
+            if assigned_out[block].as_ref() != Some(&assigned) {
+                assigned_out[block] = Some(assigned);
+                changed = true;
+            }
+        }
+    }
+
+    let mut reads = Vec::new();
+    for block in (0..graph.blocks.len()).filter(|&block| reachable[block]) {
+        if let Some(mut assigned) = assigned_in(&assigned_out, block) {
+            apply_effects(&effects[block], &tracked, &mut assigned, &mut reads);
+        }
+    }
+    reads.sort_by_key(|(_, span): &(Variable, Span)| span.start);
+    let mut reported = HashSet::new();
+    reads.retain(|(variable, _)| reported.insert(*variable));
+    reads
+        .into_iter()
+        .map(|((name, _), span)| (name, span))
+        .collect()
+}
+
+// Update the assigned variables with the effects of a block, recording reads of
+// tracked variables that are not assigned
+fn apply_effects<'ast>(
+    effects: &[Effect<'ast>],
+    tracked: &HashSet<Variable<'ast>>,
+    assigned: &mut HashSet<Variable<'ast>>,
+    reads: &mut Vec<(Variable<'ast>, Span)>,
+) {
+    for effect in effects {
+        match *effect {
+            Effect::Read(variable, span) => {
+                if tracked.contains(&variable) && !assigned.contains(&variable) {
+                    reads.push((variable, span));
+                }
+            }
+            Effect::Assign(variable) => {
+                assigned.insert(variable);
+            }
+            Effect::Declare(variable) => {
+                assigned.remove(&variable);
+            }
+        }
+    }
+}
+
+// Identifier declaring a declarator, e.g. `x` for `*x[4]`
+fn declarator_identifier(declarator: &Declarator) -> Option<&Node<Identifier>> {
+    match &declarator.kind.node {
+        DeclaratorKind::Identifier(identifier) => Some(identifier),
+        DeclaratorKind::Declarator(inner) => declarator_identifier(&inner.node),
+        DeclaratorKind::Abstract => None,
+    }
+}
+
+// Declaration each identifier in a function body refers to, as the offset of the
+// declaring identifier by the offset of the use. Uses of globals are left out.
+fn resolve_declarations(function_definition: &FunctionDefinition) -> HashMap<usize, usize> {
+    let mut resolver = ScopeResolver {
+        scopes: vec![HashMap::new()],
+        declarations: HashMap::new(),
+    };
+    for derived in &function_definition.declarator.node.derived {
+        let DerivedDeclarator::Function(function_declarator) = &derived.node else {
+            continue;
+        };
+        for parameter in &function_declarator.node.parameters {
+            if let Some(declarator) = &parameter.node.declarator {
+                resolver.declare(&declarator.node);
+            }
+        }
+    }
+    let statement = &function_definition.statement;
+    resolver.visit_statement(&statement.node, &statement.span);
+    resolver.declarations
+}
+
+// Follows the block scopes of a function body to find the declaration in scope at
+// each use of a name
+struct ScopeResolver<'ast> {
+    scopes: Vec<HashMap<&'ast str, usize>>,
+    declarations: HashMap<usize, usize>,
+}
+
+impl<'ast> ScopeResolver<'ast> {
+    fn declare(&mut self, declarator: &'ast Declarator) {
+        if let (Some(identifier), Some(scope)) =
+            (declarator_identifier(declarator), self.scopes.last_mut())
+        {
+            scope.insert(identifier.node.name.as_str(), identifier.span.start);
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for ScopeResolver<'ast> {
+    fn visit_statement(&mut self, statement: &'ast Statement, span: &'ast Span) {
+        if matches!(statement, Statement::Compound(_) | Statement::For(_)) {
+            self.scopes.push(HashMap::new());
+            visit_statement(self, statement, span);
+            self.scopes.pop();
+        } else {
+            visit_statement(self, statement, span);
+        }
+    }
+
+    // A name is in scope from its declarator on, including in its own initializer.
+    // Nested declarators, such as the parameters of a function prototype, are skipped.
+    fn visit_declaration(&mut self, declaration: &'ast Declaration, _span: &'ast Span) {
+        for init_declarator in &declaration.declarators {
+            self.declare(&init_declarator.node.declarator.node);
+            if let Some(initializer) = &init_declarator.node.initializer {
+                self.visit_initializer(&initializer.node, &initializer.span);
+            }
+        }
+    }
+
+    fn visit_expression(&mut self, expression: &'ast Expression, span: &'ast Span) {
+        let Expression::Identifier(identifier) = expression else {
+            visit_expression(self, expression, span);
+            return;
+        };
+        let name = identifier.node.name.as_str();
+        if let Some(&declaration) = self.scopes.iter().rev().find_map(|scope| scope.get(name)) {
+            self.declarations.insert(identifier.span.start, declaration);
+        }
+    }
+}
+
+// Collects the effects of the elements of a basic block, and the local variables
+// whose reads are checked
+struct EffectCollector<'a, 'ast> {
+    declarations: &'a HashMap<usize, usize>,
+    tracked: &'a mut HashSet<Variable<'ast>>,
+    arrays: &'a mut HashSet<Variable<'ast>>, // Local arrays, whose elements are part of the variable
+    effects: Vec<Effect<'ast>>,
+}
+
+impl<'ast> EffectCollector<'_, 'ast> {
+    fn element(&mut self, element: &Element<'ast>) {
+        match *element {
+            Element::Declaration(declaration) => {
+                self.visit_declaration(&declaration.node, &declaration.span)
+            }
+            Element::Statement(statement) => self.visit_statement(&statement.node, &statement.span),
+            Element::Condition(expression) | Element::Expression(expression) => {
+                self.visit_expression(&expression.node, &expression.span)
+            }
+        }
+    }
+
+    // Local variable a use of a name refers to, None for globals
+    fn variable(&self, identifier: &'ast Node<Identifier>) -> Option<Variable<'ast>> {
+        let declaration = self.declarations.get(&identifier.span.start)?;
+        Some((identifier.node.name.as_str(), *declaration))
+    }
+
+    // Variable an assignment target or address-of operand refers to, looking through
+    // `.` member accesses and indexing of local arrays, e.g. `state` for `state.mode`
+    // or `table[2]`. Writing through a pointer, as in `p[0]`, `*p` or `p->mode`, reads
+    // the pointer instead.
+    fn assigned_variable(&self, expression: &'ast Expression) -> Option<Variable<'ast>> {
+        match expression {
+            Expression::Identifier(identifier) => self.variable(identifier),
+            Expression::Member(member_expression)
+                if member_expression.node.operator.node == MemberOperator::Direct =>
+            {
+                self.assigned_variable(&member_expression.node.expression.node)
+            }
+            Expression::BinaryOperator(binary_operator_expression)
+                if binary_operator_expression.node.operator.node == BinaryOperator::Index =>
+            {
+                match &binary_operator_expression.node.lhs.node {
+                    Expression::Identifier(identifier) => self
+                        .variable(identifier)
+                        .filter(|variable| self.arrays.contains(variable)),
+                    // An element of a multidimensional array, e.g. `grid[i][j]`
+                    lhs @ Expression::BinaryOperator(_) => self.assigned_variable(lhs),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    // Record the reads of the indexes of an assigned variable, e.g. of `i` and `j` in
+    // `grid[i][j] = 0`, which assigned_variable looks through
+    fn visit_indexes(&mut self, expression: &'ast Expression) {
+        match expression {
+            Expression::Member(member_expression) => {
+                self.visit_indexes(&member_expression.node.expression.node)
+            }
+            Expression::BinaryOperator(binary_operator_expression) => {
+                let binary_operator_expression = &binary_operator_expression.node;
+                self.visit_indexes(&binary_operator_expression.lhs.node);
+                let rhs = &binary_operator_expression.rhs;
+                self.visit_expression(&rhs.node, &rhs.span);
+            }
+            _ => {}
+        }
+    }
+
+    // Record the assignment of the variable an expression refers to, after the reads
+    // of its indexes. False when it refers to no variable, e.g. when it dereferences.
+    fn assign(&mut self, expression: &'ast Expression) -> bool {
+        let Some(variable) = self.assigned_variable(expression) else {
+            return false;
+        };
+        self.visit_indexes(expression);
+        self.effects.push(Effect::Assign(variable));
+        true
+    }
+}
+
+impl<'ast> Visit<'ast> for EffectCollector<'_, 'ast> {
+    fn visit_declaration(&mut self, declaration: &'ast Declaration, _span: &'ast Span) {
+        let is_static_or_extern = declaration.specifiers.iter().any(|specifier| {
+            matches!(
+                &specifier.node,
+                DeclarationSpecifier::StorageClass(storage_class)
+                    if !matches!(storage_class.node, StorageClassSpecifier::Auto | StorageClassSpecifier::Register)
+            )
+        });
+
+        // Initializers are read before the variable they initialize is assigned
+        for init_declarator in &declaration.declarators {
+            let declarator = &init_declarator.node.declarator.node;
+            let Some(identifier) = declarator_identifier(declarator) else {
+                continue;
+            };
+            let variable = (identifier.node.name.as_str(), identifier.span.start);
+            if matches!(
+                declarator.derived.first(),
+                Some(derived) if matches!(derived.node, DerivedDeclarator::Array(_))
+            ) {
+                self.arrays.insert(variable);
+            }
+            if let Some(initializer) = &init_declarator.node.initializer {
+                self.visit_initializer(&initializer.node, &initializer.span);
+                self.effects.push(Effect::Assign(variable));
+                continue;
+            }
+
+            let is_tracked = !is_static_or_extern
+                && !declarator.derived.iter().any(|derived| {
+                    matches!(
+                        derived.node,
+                        DerivedDeclarator::Array(_)
+                            | DerivedDeclarator::Function(_)
+                            | DerivedDeclarator::KRFunction(_)
+                    )
+                });
+            if is_tracked {
+                self.tracked.insert(variable);
+                self.effects.push(Effect::Declare(variable));
+            }
+        }
+    }
+
+    fn visit_expression(&mut self, expression: &'ast Expression, span: &'ast Span) {
+        match expression {
+            Expression::Identifier(identifier) => {
+                if let Some(variable) = self.variable(identifier) {
+                    self.effects.push(Effect::Read(variable, *span));
+                }
+            }
+            // sizeof and _Alignof do not evaluate their operand
+            Expression::SizeOfVal(_) | Expression::SizeOfTy(_) | Expression::AlignOf(_) => {}
+            _ => visit_expression(self, expression, span),
+        }
+    }
+
+    fn visit_binary_operator_expression(
+        &mut self,
+        binary_operator_expression: &'ast BinaryOperatorExpression,
+        span: &'ast Span,
+    ) {
+        if binary_operator_expression.operator.node != BinaryOperator::Assign {
+            visit_binary_operator_expression(self, binary_operator_expression, span);
+            return;
+        }
+
+        let rhs = &binary_operator_expression.rhs;
+        self.visit_expression(&rhs.node, &rhs.span);
+        // Assigning a member or element counts as assigning the variable, the analysis
+        // does not track parts of a variable
+        let lhs = &binary_operator_expression.lhs;
+        if !self.assign(&lhs.node) {
+            self.visit_expression(&lhs.node, &lhs.span);
+        }
+    }
+
+    fn visit_unary_operator_expression(
+        &mut self,
+        unary_operator_expression: &'ast UnaryOperatorExpression,
+        span: &'ast Span,
+    ) {
+        let operand = &unary_operator_expression.operand.node;
+        if unary_operator_expression.operator.node == UnaryOperator::Address && self.assign(operand)
+        {
+            return;
+        }
+        visit_unary_operator_expression(self, unary_operator_expression, span);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lang_c::ast::ExternalDeclaration;
+    use lang_c::driver::{parse_preprocessed, Config};
+
+    use super::*;
+
+    // Names of the variables read uninitialized in the first function of a source
+    fn uninitialized(source: &str) -> Vec<String> {
+        let parse =
+            parse_preprocessed(&Config::default(), source.to_string()).expect("test source parses");
+        let function_definition = parse
+            .unit
+            .0
+            .iter()
+            .find_map(|declaration| match &declaration.node {
+                ExternalDeclaration::FunctionDefinition(function_definition) => {
+                    Some(&function_definition.node)
+                }
+                _ => None,
+            })
+            .expect("test source defines a function");
+        uninitialized_reads(function_definition)
+            .into_iter()
+            .map(|(name, _)| name.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn reads_before_any_assignment_are_reported() {
+        assert_eq!(uninitialized("int f(void) { int x; return x; }"), ["x"]);
+        assert_eq!(
+            uninitialized(
+                "int f(int n) { int sum; for (int i = 0; i < n; i++) sum += i; return sum; }"
+            ),
+            ["sum"]
+        );
+    }
+
+    #[test]
+    fn a_variable_must_be_assigned_on_every_path() {
+        assert!(
+            uninitialized("int f(int c) { int x; if (c) x = 1; else x = 2; return x; }").is_empty()
+        );
+        assert_eq!(
+            uninitialized("int f(int c) { int x; if (c) x = 1; return x; }"),
+            ["x"]
+        );
+    }
+
+    #[test]
+    fn taking_the_address_counts_as_assignment() {
+        assert!(uninitialized("int f(void) { int x; read_sensor(&x); return x; }").is_empty());
+    }
+
+    #[test]
+    fn indexes_of_an_assigned_array_element_are_read() {
+        assert_eq!(
+            uninitialized("void f(void) { int table[4]; int i; table[i] = 0; }"),
+            ["i"]
+        );
+        assert!(
+            uninitialized("void f(void) { int table[4]; int i = 0; table[i] = 0; }").is_empty()
+        );
+    }
+
+    #[test]
+    fn shadowing_variables_are_kept_apart() {
+        assert!(uninitialized("int f(void) { int x = 1; { int x; x = 2; } return x; }").is_empty());
+        assert_eq!(
+            uninitialized("int f(void) { int x; { int x = 1; use(x); } return x; }"),
+            ["x"]
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::rules::find_rule;
+
+// How serious a violation is. Advisory findings point out improvements rather
+// than breaches of the coding standard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[default]
+    Error,
+    Warning,
+    Advisory,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Severity::Error => "Error",
+            Severity::Warning => "Warning",
+            Severity::Advisory => "Advisory",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+// Severity of a rule unless overridden in the ruleset, plugin rules default to errors
+pub fn default_severity(rule: &str) -> Severity {
+    find_rule(rule).map_or(Severity::Error, |rule| rule.default_severity)
+}
+
+// A single rule violation found by the analyzer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub rule: String, // Name of the rule that was violated, matching its key in the ruleset
+    #[serde(default)]
+    pub severity: Severity,
+    pub file: String, // File the violation was found in
+    pub line: usize,  // Line number of the violation
+    #[serde(default)]
+    pub column: usize, // Column of the violation, 0 when only the line is known
+    pub message: String, // Human readable description of the violation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>, // Offending source code with squiggles, if available
+}
+
+// Order findings by file, line, column and rule ID, so the output does not depend on
+// the order the checks ran in. Findings at the same place keep their relative order.
+pub fn sort_diagnostics(diagnostics: &mut [Diagnostic]) {
+    let rule_id = |rule: &str| find_rule(rule).map_or(rule.to_string(), |rule| rule.id.to_string());
+    diagnostics.sort_by_cached_key(|diagnostic| {
+        (
+            diagnostic.file.clone(),
+            diagnostic.line,
+            diagnostic.column,
+            rule_id(&diagnostic.rule),
+        )
+    });
+}
+
+// file:line:column: severity: message, the way compilers print it so editors can jump
+// to the location. The column is left out when unknown.
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:", self.file, self.line)?;
+        if self.column > 0 {
+            write!(f, "{}:", self.column)?;
+        }
+        write!(f, " {}: {}", self.severity, self.message)?;
+        if let Some(snippet) = &self.snippet {
+            write!(f, "\n{}", snippet)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+// Everything that can stop an analysis run. Findings in the analyzed code are
+// diagnostics, not errors, including syntax errors.
+#[derive(Debug)]
+pub enum AnalyzerError {
+    // Invalid command line arguments
+    Usage(String),
+    // A config or baseline file could not be read
+    ConfigIo { path: PathBuf, error: io::Error },
+    // A config or baseline file, or the ruleset merged from them, is invalid
+    ConfigParse { location: String, message: String },
+    // A source file could not be read
+    SourceRead { path: PathBuf, error: io::Error },
+    // The C preprocessor could not be run on a source file
+    CParse { path: PathBuf, message: String },
+    // A report, baseline or history file could not be written
+    OutputWrite { path: PathBuf, error: io::Error },
+    // A plugin library could not be loaded
+    Plugin { path: PathBuf, message: String },
+    // The analyzed files could not be watched for changes
+    Watch(String),
+    // The daemon could not listen for or answer requests
+    Daemon(String),
+    // git could not be asked which files or lines changed
+    Git(String),
+}
+
+// Process exit code of a run that completed but left errors or warnings to fix
+pub const FINDINGS_EXIT_CODE: i32 = 1;
+
+impl AnalyzerError {
+    // Process exit code for the command line tool, FINDINGS_EXIT_CODE is left for findings
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AnalyzerError::Usage(_) => 2,
+            AnalyzerError::ConfigIo { .. }
+            | AnalyzerError::ConfigParse { .. }
+            | AnalyzerError::Plugin { .. } => 3,
+            AnalyzerError::SourceRead { .. } | AnalyzerError::CParse { .. } => 4,
+            AnalyzerError::OutputWrite { .. } => 5,
+            AnalyzerError::Watch(_) | AnalyzerError::Daemon(_) | AnalyzerError::Git(_) => 6,
+        }
+    }
+}
+
+impl fmt::Display for AnalyzerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnalyzerError::Usage(message) => write!(f, "{}", message),
+            AnalyzerError::ConfigIo { path, error } => {
+                write!(f, "Failed to read {}: {}", path.display(), error)
+            }
+            AnalyzerError::ConfigParse { location, message } => {
+                write!(f, "Invalid config in {}: {}", location, message)
+            }
+            AnalyzerError::SourceRead { path, error } => {
+                write!(
+                    f,
+                    "Failed to read source file {}: {}",
+                    path.display(),
+                    error
+                )
+            }
+            AnalyzerError::CParse { path, message } => {
+                write!(f, "Failed to preprocess {}: {}", path.display(), message)
+            }
+            AnalyzerError::OutputWrite { path, error } => {
+                write!(f, "Failed to write {}: {}", path.display(), error)
+            }
+            AnalyzerError::Plugin { path, message } => {
+                write!(f, "Failed to load plugin {}: {}", path.display(), message)
+            }
+            AnalyzerError::Watch(message) => write!(f, "Failed to watch files: {}", message),
+            AnalyzerError::Daemon(message) => write!(f, "Daemon failed: {}", message),
+            AnalyzerError::Git(message) => write!(f, "git failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for AnalyzerError {}
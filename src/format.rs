@@ -0,0 +1,119 @@
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+// Family of functions a format string belongs to, which decides what each
+// conversion expects: a value for printf, a pointer to store into for scanf
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum FormatFamily {
+    Printf,
+    Scanf,
+}
+
+// What a conversion expects its argument to be, or to point to for scanf
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ArgumentKind {
+    Integer,  // d i o u x X c, and a * width or precision
+    Floating, // f F e E g G a A
+    String,   // s, and c and [ for scanf
+    Pointer,  // p, and n for printf
+}
+
+// An argument a format string consumes, with the conversion specification it
+// belongs to as written, e.g. "%08lx"
+#[derive(Debug)]
+pub(crate) struct Conversion {
+    pub(crate) specification: String,
+    pub(crate) kind: ArgumentKind,
+}
+
+// Text of a string literal, joining adjacent pieces and dropping their quotes and
+// encoding prefixes. Escape sequences are kept as written.
+pub(crate) fn literal_text(pieces: &[String]) -> String {
+    pieces
+        .iter()
+        .filter_map(|piece| {
+            let start = piece.find('"')? + 1;
+            let end = piece.rfind('"')?;
+            piece.get(start..end)
+        })
+        .collect()
+}
+
+// Arguments a format string consumes in order, None when it holds a conversion this
+// parser does not know, e.g. a project specific extension
+pub(crate) fn conversions(format: &str, family: FormatFamily) -> Option<Vec<Conversion>> {
+    let mut conversions = Vec::new();
+    let mut characters = format.char_indices().peekable();
+
+    while let Some((start, character)) = characters.next() {
+        if character != '%' {
+            continue;
+        }
+        if characters.next_if(|&(_, c)| c == '%').is_some() {
+            continue;
+        }
+
+        let mut kinds = Vec::new();
+        // scanf reads and discards the input of a conversion starting with *
+        let suppressed =
+            family == FormatFamily::Scanf && characters.next_if(|&(_, c)| c == '*').is_some();
+        while characters.next_if(|&(_, c)| "-+ #0'".contains(c)).is_some() {}
+        skip_width(&mut characters, family, &mut kinds);
+        if characters.next_if(|&(_, c)| c == '.').is_some() {
+            skip_width(&mut characters, family, &mut kinds);
+        }
+        while characters
+            .next_if(|&(_, c)| "hlLjztq".contains(c))
+            .is_some()
+        {}
+
+        let (_, conversion) = characters.next()?;
+        let kind = match (conversion, family) {
+            ('d' | 'i' | 'o' | 'u' | 'x' | 'X', _) => ArgumentKind::Integer,
+            ('f' | 'F' | 'e' | 'E' | 'g' | 'G' | 'a' | 'A', _) => ArgumentKind::Floating,
+            ('s', _) => ArgumentKind::String,
+            ('p', _) => ArgumentKind::Pointer,
+            ('c', FormatFamily::Printf) => ArgumentKind::Integer,
+            ('c', FormatFamily::Scanf) => ArgumentKind::String,
+            ('n', FormatFamily::Printf) => ArgumentKind::Pointer,
+            ('n', FormatFamily::Scanf) => ArgumentKind::Integer,
+            ('[', FormatFamily::Scanf) => {
+                // The set runs to the next ], which is part of it when it comes first
+                characters.next_if(|&(_, c)| c == '^');
+                characters.next_if(|&(_, c)| c == ']');
+                characters.find(|&(_, c)| c == ']')?;
+                ArgumentKind::String
+            }
+            // glibc's %m prints strerror(errno) and takes no argument
+            ('m', FormatFamily::Printf) => continue,
+            _ => return None,
+        };
+        if suppressed {
+            continue;
+        }
+
+        let end = characters.peek().map_or(format.len(), |&(index, _)| index);
+        let specification = format[start..end].to_string();
+        for kind in kinds.into_iter().chain([kind]) {
+            conversions.push(Conversion {
+                specification: specification.clone(),
+                kind,
+            });
+        }
+    }
+
+    Some(conversions)
+}
+
+// Skip a width or precision. printf takes one written as * from an int argument
+// ahead of the value.
+fn skip_width(
+    characters: &mut Peekable<CharIndices>,
+    family: FormatFamily,
+    kinds: &mut Vec<ArgumentKind>,
+) {
+    if family == FormatFamily::Printf && characters.next_if(|&(_, c)| c == '*').is_some() {
+        kinds.push(ArgumentKind::Integer);
+    }
+    while characters.next_if(|&(_, c)| c.is_ascii_digit()).is_some() {}
+}
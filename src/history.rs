@@ -0,0 +1,111 @@
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::diagnostic::Diagnostic;
+use crate::error::AnalyzerError;
+use crate::rules::RULES;
+
+// Environment variables CI systems put the commit being built in, checked in order
+const COMMIT_VARIABLES: [&str; 3] = ["GITHUB_SHA", "CI_COMMIT_SHA", "GIT_COMMIT"];
+
+// Append one row of statistics for this run to a CSV history file, writing the
+// header first when the file is new. Rows have a column per known rule, so
+// projects build up a record of compliance over time. When rules were added or
+// removed since the file was started, it is rewritten with the new columns: earlier
+// rows are left empty in the columns of new rules, and the columns of retired rules
+// are kept at the end.
+pub fn append_history(
+    path: &str,
+    lines_of_code: usize,
+    diagnostics: &[Diagnostic],
+) -> Result<(), AnalyzerError> {
+    let write_error = |error| AnalyzerError::OutputWrite {
+        path: Path::new(path).to_path_buf(),
+        error,
+    };
+    let existing = match fs::read_to_string(path) {
+        Ok(content) => Some(content),
+        Err(error) if error.kind() == ErrorKind::NotFound => None,
+        Err(error) => return Err(write_error(error)),
+    };
+
+    let mut header: Vec<String> = ["timestamp", "commit", "lines_of_code", "findings"]
+        .iter()
+        .copied()
+        .chain(RULES.iter().map(|rule| rule.name))
+        .map(str::to_string)
+        .collect();
+    let mut rows = Vec::new();
+    match existing
+        .as_deref()
+        .and_then(|content| content.lines().next())
+    {
+        None => rows.push(header.join(",")),
+        Some(existing_header) => {
+            let existing_columns: Vec<&str> = existing_header.split(',').collect();
+            for column in &existing_columns {
+                if !header.iter().any(|name| name == column) {
+                    header.push(column.to_string());
+                }
+            }
+            if existing_columns != header {
+                rows.push(header.join(","));
+                for line in existing.iter().flat_map(|content| content.lines().skip(1)) {
+                    let values: Vec<&str> = line.split(',').collect();
+                    let row: Vec<&str> = header
+                        .iter()
+                        .map(|name| {
+                            existing_columns
+                                .iter()
+                                .position(|column| column == name)
+                                .and_then(|index| values.get(index).copied())
+                                .unwrap_or_default()
+                        })
+                        .collect();
+                    rows.push(row.join(","));
+                }
+            }
+        }
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let commit = COMMIT_VARIABLES
+        .iter()
+        .find_map(|variable| env::var(variable).ok())
+        .unwrap_or_default();
+    let mut row = vec![
+        timestamp.to_string(),
+        commit,
+        lines_of_code.to_string(),
+        diagnostics.len().to_string(),
+    ];
+    row.extend(RULES.iter().map(|rule| {
+        diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.rule == rule.name)
+            .count()
+            .to_string()
+    }));
+    // Retired rules have no findings to count
+    row.resize(header.len(), String::new());
+    rows.push(row.join(","));
+
+    // A rewritten file starts over from its header, otherwise the row is appended
+    let rewrite = rows.len() > 1 && existing.is_some();
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(!rewrite)
+        .truncate(rewrite)
+        .open(path)
+        .map_err(write_error)?;
+    for row in rows {
+        writeln!(file, "{}", row).map_err(write_error)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,160 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use lang_c::ast::{Declaration, Expression, FunctionDefinition, TranslationUnit};
+use lang_c::span::Span;
+use lang_c::visit::{visit_declaration, visit_expression, visit_function_definition, Visit};
+
+use crate::analyzer::{declarator_name, is_static};
+use crate::config::{glob_matches, RuleSet};
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::location::LineIndex;
+
+// A function definition with external linkage
+#[derive(Clone, Serialize, Deserialize)]
+struct ExternalDefinition {
+    name: String,
+    file: String, // File the definition is located in, which may be an included file
+    line: usize,
+    column: usize,
+    severity: Severity,
+}
+
+// What a single translation unit contributes to the project index. Collected on the
+// worker thread analyzing the file and cached along with its diagnostics.
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub struct FileIndex {
+    header_declarations: HashSet<String>, // Functions declared in a project header
+    references: HashSet<String>,          // Identifiers the file refers to
+    external_definitions: Vec<ExternalDefinition>, // Non-static definitions subject to the static check
+}
+
+impl FileIndex {
+    // Index one translation unit. Definitions are only recorded when the missing
+    // static rule is enabled for the file, and when they are not exported.
+    pub fn collect(source: &str, unit: &TranslationUnit, rule_set: &RuleSet) -> Self {
+        let static_severity = rule_set
+            .require_static_functions
+            .then(|| rule_set.severity("require_static_functions"));
+        let mut collector = IndexCollector {
+            line_index: LineIndex::new(source),
+            static_severity,
+            exported_functions: &rule_set.exported_functions,
+            index: FileIndex::default(),
+        };
+        collector.visit_translation_unit(unit);
+        collector.index
+    }
+}
+
+// Cross-file index of function definitions, header declarations and references,
+// filled in from each analyzed file and checked once all files are done
+#[derive(Default)]
+pub struct ProjectIndex {
+    header_declarations: HashSet<String>,
+    references: HashMap<String, HashSet<String>>, // Identifier -> analyzed files referring to it
+    external_definitions: Vec<(String, ExternalDefinition)>, // With the analyzed file defining them
+}
+
+impl ProjectIndex {
+    pub fn add(&mut self, translation_unit: &str, file_index: FileIndex) {
+        self.header_declarations
+            .extend(file_index.header_declarations);
+        for reference in file_index.references {
+            self.references
+                .entry(reference)
+                .or_default()
+                .insert(translation_unit.to_string());
+        }
+        self.external_definitions.extend(
+            file_index
+                .external_definitions
+                .into_iter()
+                .map(|definition| (translation_unit.to_string(), definition)),
+        );
+    }
+
+    // Functions with external linkage that no header declares and no other file uses
+    pub fn check_missing_static(&self) -> Vec<Diagnostic> {
+        self.external_definitions
+            .iter()
+            .filter(|(_, definition)| definition.name != "main")
+            .filter(|(_, definition)| !self.header_declarations.contains(&definition.name))
+            .filter(|(translation_unit, definition)| {
+                self.references.get(&definition.name).map_or(true, |files| {
+                    files.iter().all(|file| file == translation_unit)
+                })
+            })
+            .map(|(_, definition)| Diagnostic {
+                rule: "require_static_functions".to_string(),
+                severity: definition.severity,
+                file: definition.file.clone(),
+                line: definition.line,
+                column: definition.column,
+                message: format!(
+                    "Function '{}' is only used in this file but is not declared static",
+                    definition.name
+                ),
+                snippet: None,
+            })
+            .collect()
+    }
+}
+
+struct IndexCollector<'a> {
+    line_index: LineIndex,
+    static_severity: Option<Severity>, // Severity of the missing static rule, None when disabled
+    exported_functions: &'a [String],  // Patterns of functions other programs link against
+    index: FileIndex,
+}
+
+impl<'ast> Visit<'ast> for IndexCollector<'_> {
+    fn visit_function_definition(
+        &mut self,
+        function_definition: &'ast FunctionDefinition,
+        span: &'ast Span,
+    ) {
+        let location = self.line_index.location(span.start);
+        if let Some(severity) = self.static_severity {
+            if location.file.ends_with(".c") && !is_static(&function_definition.specifiers) {
+                let name = declarator_name(&function_definition.declarator.node).filter(|name| {
+                    !self
+                        .exported_functions
+                        .iter()
+                        .any(|pattern| glob_matches(pattern, name))
+                });
+                if let Some(name) = name {
+                    self.index.external_definitions.push(ExternalDefinition {
+                        name: name.to_string(),
+                        file: location.file.to_string(),
+                        line: location.line,
+                        column: location.column,
+                        severity,
+                    });
+                }
+            }
+        }
+        visit_function_definition(self, function_definition, span);
+    }
+
+    fn visit_declaration(&mut self, declaration: &'ast Declaration, span: &'ast Span) {
+        // System headers may declare a name the project reuses, e.g. `init`
+        let location = self.line_index.location(span.start);
+        if location.file.ends_with(".h") && !self.line_index.is_system_header(span.start) {
+            for init_declarator in &declaration.declarators {
+                if let Some(name) = declarator_name(&init_declarator.node.declarator.node) {
+                    self.index.header_declarations.insert(name.to_string());
+                }
+            }
+        }
+        visit_declaration(self, declaration, span);
+    }
+
+    fn visit_expression(&mut self, expression: &'ast Expression, span: &'ast Span) {
+        if let Expression::Identifier(identifier) = expression {
+            self.index.references.insert(identifier.node.name.clone());
+        }
+        visit_expression(self, expression, span);
+    }
+}
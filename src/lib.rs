@@ -0,0 +1,247 @@
+//! Static analyzer checking C code against NASA's Power of Ten rules.
+//!
+//! The command line tool is a thin wrapper around this library, so the same
+//! analysis can be embedded into other build tooling without shelling out.
+
+use std::fs;
+use std::path::Path;
+
+use lang_c::driver::{parse, parse_preprocessed, Config, Error, Parse, SyntaxError};
+use lang_c::loc::get_location_for_offset;
+use lang_c::visit::Visit;
+
+mod analyzer;
+pub mod baseline;
+pub mod blame;
+pub mod cache;
+pub mod cfg;
+pub mod changes;
+pub mod compare;
+pub mod conditional;
+pub mod config;
+mod constness;
+mod dataflow;
+pub mod diagnostic;
+pub mod error;
+mod format;
+pub mod history;
+pub mod index;
+pub mod location;
+pub mod macros;
+pub mod metrics;
+pub mod plugin;
+pub mod preprocessor;
+pub mod rules;
+pub mod sink;
+pub mod workspace;
+
+pub use config::RuleSet;
+pub use diagnostic::{sort_diagnostics, Diagnostic, Severity};
+pub use error::AnalyzerError;
+pub use sink::DiagnosticSink;
+
+use analyzer::StaticAnalyzer;
+
+// File name reported for diagnostics of in-memory sources
+const SOURCE_NAME: &str = "<source>";
+
+/// Rule reported for files that fail to parse
+pub const PARSE_ERROR_RULE: &str = "parse_error";
+
+/// Analyze C source held in memory. The source is parsed as is, without running
+/// the C preprocessor, so it must not depend on #include or macro expansion.
+pub fn analyze_source(source: &str, rule_set: &RuleSet) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    analyze_source_to(source, rule_set, &mut diagnostics);
+    diagnostics
+}
+
+/// Like `analyze_source`, reporting findings to the given sink.
+pub fn analyze_source_to(source: &str, rule_set: &RuleSet, sink: &mut dyn DiagnosticSink) {
+    let mut diagnostics = match parse_preprocessed(&Config::default(), source.to_string()) {
+        Ok(parse) => analyze_parsed(SOURCE_NAME, source, &parse, rule_set),
+        Err(error) => {
+            let mut diagnostics = vec![parse_error_diagnostic(&error, rule_set)];
+            diagnostics.extend(analyze_raw_source(SOURCE_NAME, source, rule_set));
+            diagnostics
+        }
+    };
+    sort_diagnostics(&mut diagnostics);
+    diagnostics
+        .into_iter()
+        .for_each(|diagnostic| sink.report(diagnostic));
+}
+
+/// Read a C source file, failing with the path in the error.
+pub fn read_source(path: &Path) -> Result<String, AnalyzerError> {
+    fs::read_to_string(path).map_err(|error| AnalyzerError::SourceRead {
+        path: path.to_path_buf(),
+        error,
+    })
+}
+
+/// Preprocess and parse a C source file. Syntax errors are returned as they are
+/// findings in the file, while a preprocessor that cannot be run or rejects the
+/// file means the build environment is misconfigured and fails with an error.
+pub fn parse_file(path: &Path) -> Result<Result<Parse, SyntaxError>, AnalyzerError> {
+    match parse(&Config::default(), path) {
+        Ok(parse) => Ok(Ok(parse)),
+        Err(Error::SyntaxError(error)) => Ok(Err(error)),
+        Err(Error::PreprocessorError(error)) => Err(AnalyzerError::CParse {
+            path: path.to_path_buf(),
+            message: error.to_string(),
+        }),
+    }
+}
+
+/// Preprocess, parse and analyze a C source file. A file that fails to parse is
+/// reported as a diagnostic and only checked by the rules that work on the raw source.
+pub fn analyze_file(path: &Path, rule_set: &RuleSet) -> Result<Vec<Diagnostic>, AnalyzerError> {
+    let mut diagnostics = Vec::new();
+    analyze_file_to(path, rule_set, &mut diagnostics)?;
+    Ok(diagnostics)
+}
+
+/// Like `analyze_file`, reporting findings to the given sink. Findings are only
+/// reported once the file could be analyzed, never before an error is returned.
+pub fn analyze_file_to(
+    path: &Path,
+    rule_set: &RuleSet,
+    sink: &mut dyn DiagnosticSink,
+) -> Result<(), AnalyzerError> {
+    let file = path.to_string_lossy();
+    let raw_source = read_source(path)?;
+    let mut diagnostics = match parse_file(path)? {
+        Ok(parse) => {
+            let mut diagnostics = analyze_parsed(&file, &raw_source, &parse, rule_set);
+            if rule_set.require_parsable_conditional_branches {
+                diagnostics.extend(conditional::check_conditional_branches(
+                    path,
+                    &raw_source,
+                    rule_set,
+                )?);
+            }
+            diagnostics
+        }
+        Err(error) => {
+            let mut diagnostics = vec![parse_error_diagnostic(&error, rule_set)];
+            diagnostics.extend(analyze_raw_source(&file, &raw_source, rule_set));
+            diagnostics
+        }
+    };
+    sort_diagnostics(&mut diagnostics);
+    diagnostics
+        .into_iter()
+        .for_each(|diagnostic| sink.report(diagnostic));
+    Ok(())
+}
+
+/// Diagnostic for a file that failed to parse, pointing at the offending line.
+pub fn parse_error_diagnostic(error: &SyntaxError, rule_set: &RuleSet) -> Diagnostic {
+    let (location, _) = get_location_for_offset(&error.source, error.offset);
+    let mut expected: Vec<&str> = error.expected.iter().copied().collect();
+    expected.sort_unstable();
+    let line_start = error.source[..error.offset]
+        .rfind('\n')
+        .map_or(0, |start| start + 1);
+    let line_end = error.source[error.offset..]
+        .find('\n')
+        .map_or(error.source.len(), |end| error.offset + end);
+
+    Diagnostic {
+        rule: PARSE_ERROR_RULE.to_string(),
+        severity: rule_set.severity(PARSE_ERROR_RULE),
+        file: location.file.to_string(),
+        line: location.line,
+        column: location.column,
+        message: format!("Syntax error, expected one of {}", expected.join(" ")),
+        snippet: Some(error.source[line_start..line_end].trim().to_string()),
+    }
+}
+
+/// Analyze a file that has already been parsed. `raw_source` is the file before
+/// preprocessing, which the preprocessor rules are checked against.
+pub fn analyze_parsed(
+    file: &str,
+    raw_source: &str,
+    parse: &Parse,
+    rule_set: &RuleSet,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = analyze_raw_source(file, raw_source, rule_set);
+
+    // Whether a constant macro is only used as one type takes the parsed uses
+    if rule_set.prefer_typed_constants {
+        let directives = preprocessor::parse_directives(raw_source);
+        diagnostics.extend(preprocessor::check_constant_macros(
+            file,
+            raw_source,
+            &directives,
+            parse,
+            rule_set,
+        ));
+    }
+
+    let mut analyzer = StaticAnalyzer::new(rule_set.clone(), parse.source.clone());
+    analyzer.visit_translation_unit(&parse.unit);
+    diagnostics.extend(analyzer.diagnostics);
+
+    diagnostics
+}
+
+/// Run the rules that work on the source before preprocessing: directives, macro
+/// bodies and constructs lang_c cannot parse. These also apply to files that fail to parse.
+pub fn analyze_raw_source(file: &str, raw_source: &str, rule_set: &RuleSet) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    let directives = preprocessor::parse_directives(raw_source);
+    if rule_set.restrict_banned_headers {
+        diagnostics.extend(preprocessor::check_banned_headers(
+            file,
+            &directives,
+            rule_set,
+        ));
+    }
+    if rule_set.restrict_complex_macros {
+        diagnostics.extend(preprocessor::check_complex_macros(
+            file,
+            &directives,
+            rule_set,
+        ));
+    }
+    if rule_set.restrict_undef_and_redefinition {
+        diagnostics.extend(preprocessor::check_undef_and_redefinition(
+            file,
+            &directives,
+            rule_set,
+        ));
+    }
+    if rule_set.restrict_include_placement {
+        diagnostics.extend(preprocessor::check_include_placement(
+            file,
+            raw_source,
+            &directives,
+            rule_set,
+        ));
+    }
+    if rule_set.restrict_include_paths {
+        diagnostics.extend(preprocessor::check_include_paths(
+            file,
+            &directives,
+            rule_set,
+        ));
+    }
+    if rule_set.restrict_computed_goto {
+        diagnostics.extend(preprocessor::check_computed_goto(
+            file, raw_source, rule_set,
+        ));
+    }
+    if rule_set.restrict_pragmas {
+        diagnostics.extend(preprocessor::check_pragmas(file, &directives, rule_set));
+    }
+
+    if rule_set.analyze_macro_bodies {
+        diagnostics.extend(macros::check_macro_bodies(file, &directives, rule_set));
+    }
+
+    diagnostics
+}
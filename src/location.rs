@@ -0,0 +1,111 @@
+use lang_c::loc::get_location_for_offset;
+
+// Position in an original source file, resolved from an offset in preprocessed source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location<'a> {
+    pub file: &'a str,
+    pub line: usize,
+    pub column: usize, // 1-based, in bytes
+}
+
+// Start offsets of the lines of a preprocessed source together with the original
+// file and line each of them came from, built once per file so looking up a
+// location is a binary search instead of a scan over the source.
+#[derive(Debug)]
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+    origins: Vec<(usize, usize)>, // Index into `files` and line each source line came from
+    files: Vec<String>,
+    system_headers: Vec<bool>, // Whether each of `files` is a system header
+}
+
+impl LineIndex {
+    pub fn new(source: &str) -> Self {
+        // Source before the first line marker is attributed like lang_c does
+        let (start, _) = get_location_for_offset(source, 0);
+        let mut files = vec![start.file.to_string()];
+        let mut system_headers = vec![false];
+        let mut file = 0;
+        let mut line = start.line;
+
+        let mut line_starts = Vec::new();
+        let mut origins = Vec::new();
+        let mut offset = 0;
+        for text in source.split_inclusive('\n') {
+            line_starts.push(offset);
+            origins.push((file, line));
+            offset += text.len();
+
+            // A line marker sets the origin of the line following it
+            match parse_line_marker(text) {
+                Some((marker_file, marker_line, is_system_header)) => {
+                    if files[file] != marker_file {
+                        file = match files.iter().position(|known| known == marker_file) {
+                            Some(known) => known,
+                            None => {
+                                files.push(marker_file.to_string());
+                                system_headers.push(false);
+                                files.len() - 1
+                            }
+                        };
+                    }
+                    system_headers[file] |= is_system_header;
+                    line = marker_line;
+                }
+                None => line += 1,
+            }
+        }
+        // An empty source or the end of the last line still resolves
+        if source.is_empty() || source.ends_with('\n') {
+            line_starts.push(offset);
+            origins.push((file, line));
+        }
+
+        LineIndex {
+            line_starts,
+            origins,
+            files,
+            system_headers,
+        }
+    }
+
+    pub fn location(&self, offset: usize) -> Location<'_> {
+        let index = self.line_containing(offset);
+        let (file, line) = self.origins[index];
+        Location {
+            file: &self.files[file],
+            line,
+            column: offset - self.line_starts[index] + 1,
+        }
+    }
+
+    pub fn line(&self, offset: usize) -> usize {
+        self.location(offset).line
+    }
+
+    // Whether an offset comes from a header the preprocessor marked as a system header
+    pub fn is_system_header(&self, offset: usize) -> bool {
+        let (file, _) = self.origins[self.line_containing(offset)];
+        self.system_headers[file]
+    }
+
+    // Index of the source line an offset falls on
+    fn line_containing(&self, offset: usize) -> usize {
+        self.line_starts
+            .partition_point(|&start| start <= offset)
+            .saturating_sub(1)
+    }
+}
+
+// File and line of a `# 12 "file.c" 1` or `#line 12 "file.c"` marker, and whether
+// it carries flag 3, which GCC and Clang set for system headers
+fn parse_line_marker(text: &str) -> Option<(&str, usize, bool)> {
+    let rest = text.trim_start().strip_prefix('#')?.trim_start();
+    let rest = rest.strip_prefix("line").unwrap_or(rest).trim_start();
+    let digits_end = rest.find(|c: char| !c.is_ascii_digit())?;
+    let line = rest[..digits_end].parse().ok()?;
+    let rest = rest[digits_end..].trim_start().strip_prefix('"')?;
+    let end = rest.find('"')?;
+    let is_system_header = rest[end + 1..].split_whitespace().any(|flag| flag == "3");
+    Some((&rest[..end], line, is_system_header))
+}
@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+
+use lang_c::ast::{
+    BinaryOperator, BinaryOperatorExpression, Declaration, Expression, Initializer, UnaryOperator,
+};
+use lang_c::driver::{parse_preprocessed, Config, Parse};
+use lang_c::loc::get_location_for_offset;
+use lang_c::span::{Node, Span};
+use lang_c::visit::{visit_binary_operator_expression, visit_declaration, Visit};
+
+use crate::analyzer::{
+    declarator_name, scalar_type, type_specifier_text, type_specifiers, StaticAnalyzer,
+};
+use crate::config::RuleSet;
+use crate::diagnostic::Diagnostic;
+use crate::preprocessor::{code_only, identifiers, Directive};
+use crate::rules::RULES;
+
+// Rules that apply to individual expressions and so make sense for a macro body
+// checked on its own, away from the function it is expanded into
+const EXPRESSION_RULES: [&str; 14] = [
+    "restrict_setjmp",
+    "restrict_longjmp",
+    "restrict_heap_allocation",
+    "restrict_alloca",
+    "restrict_banned_functions",
+    "restrict_unsafe_string_functions",
+    "restrict_termination_calls",
+    "restrict_variadic",
+    "restrict_locale_apis",
+    "restrict_dereference_depth",
+    "restrict_comma_operator",
+    "restrict_nested_conditionals",
+    "restrict_octal_constants",
+    "restrict_magic_numbers",
+];
+
+// Name of the function a macro body is wrapped in for parsing
+const WRAPPER_FUNCTION: &str = "__nasa_analyzer_macro_body";
+
+// Split a function-like #define into its name and body, None for object-like macros
+fn function_like_macro(directive: &Directive) -> Option<(&str, &str)> {
+    let (name, rest) = directive.defined_macro()?;
+    let parameters = rest.strip_prefix('(')?;
+    let body = &parameters[parameters.find(')')? + 1..];
+    Some((name, body.trim()))
+}
+
+// Copy of the ruleset with everything but the expression rules disabled
+fn expression_rule_set(rule_set: &RuleSet) -> RuleSet {
+    let mut subset = rule_set.clone();
+    for rule in RULES {
+        if EXPRESSION_RULES.contains(&rule.name) {
+            continue;
+        }
+        if let Some(enabled) = subset.rule_mut(rule.name) {
+            *enabled = false;
+        }
+    }
+    subset
+}
+
+// Function-like macros are only seen by the parser where they are expanded, so
+// violations in unused macros or in headers go unnoticed. Bodies shaped like an
+// expression or statement are parsed on their own, wrapped in a function, and
+// checked with the expression rules. Findings are reported at the #define.
+pub fn check_macro_bodies(
+    file: &str,
+    directives: &[Directive],
+    rule_set: &RuleSet,
+) -> Vec<Diagnostic> {
+    let subset = expression_rule_set(rule_set);
+    let mut diagnostics = Vec::new();
+
+    for directive in directives {
+        let Some((name, body)) = function_like_macro(directive) else {
+            continue;
+        };
+        // Stringizing and token pasting only make sense after expansion
+        if body.is_empty() || body.contains('#') {
+            continue;
+        }
+
+        let wrapper = format!("void {}(void) {{\n{};\n}}\n", WRAPPER_FUNCTION, body);
+        // Bodies that are not a complete expression or statement are skipped
+        let Ok(parse) = parse_preprocessed(&Config::default(), wrapper) else {
+            continue;
+        };
+
+        let mut analyzer = StaticAnalyzer::new(subset.clone(), parse.source.clone());
+        analyzer.visit_translation_unit(&parse.unit);
+        for diagnostic in analyzer.diagnostics {
+            diagnostics.push(Diagnostic {
+                file: file.to_string(),
+                line: directive.line,
+                column: 0,
+                message: format!("{} in macro '{}'", diagnostic.message, name),
+                ..diagnostic
+            });
+        }
+    }
+
+    diagnostics
+}
+
+// Type each use of a constant macro in a file is converted to, by macro name. Macros
+// are expanded before parsing, so a use is matched up with the constant the parser
+// sees on the same line with the same text as the replacement. A use has a type when
+// it is the whole initializer of an arithmetic variable, or one side of a comparison
+// with such a variable. Any other use, e.g. as an array size, is None.
+pub fn constant_macro_uses<'a>(
+    file: &str,
+    raw_source: &str,
+    parse: &Parse,
+    macros: &[(&'a str, &str)],
+) -> HashMap<&'a str, Vec<Option<String>>> {
+    // Uses of each macro by line, outside directives
+    let mut unmatched: HashMap<(usize, &'a str), usize> = HashMap::new();
+    let mut in_directive = false;
+    for (index, line) in code_only(raw_source).lines().enumerate() {
+        let is_directive = in_directive || line.trim_start().starts_with('#');
+        in_directive = is_directive && line.trim_end().ends_with('\\');
+        if is_directive {
+            continue;
+        }
+        for identifier in identifiers(line) {
+            if let Some(&(name, _)) = macros.iter().find(|(name, _)| *name == identifier) {
+                *unmatched.entry((index + 1, name)).or_default() += 1;
+            }
+        }
+    }
+
+    let mut collector = TypedConstants {
+        source: &parse.source,
+        file,
+        variables: HashMap::new(),
+        constants: Vec::new(),
+    };
+    collector.visit_translation_unit(&parse.unit);
+
+    let mut uses: HashMap<&'a str, Vec<Option<String>>> = HashMap::new();
+    for (line, text, type_name) in collector.constants {
+        let Some(&(name, _)) = macros.iter().find(|(name, replacement)| {
+            constant_text(replacement) == text
+                && unmatched
+                    .get(&(line, *name))
+                    .is_some_and(|&count| count > 0)
+        }) else {
+            continue;
+        };
+        if let Some(count) = unmatched.get_mut(&(line, name)) {
+            *count -= 1;
+        }
+        uses.entry(name).or_default().push(Some(type_name));
+    }
+    for ((_, name), count) in unmatched {
+        uses.entry(name)
+            .or_default()
+            .extend(std::iter::repeat(None).take(count));
+    }
+    uses
+}
+
+// Text of a constant with whitespace and enclosing parentheses dropped, so a macro
+// replacement compares equal to its expansion
+fn constant_text(text: &str) -> String {
+    let text: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut constant = text.as_str();
+    while let Some(inner) = constant
+        .strip_prefix('(')
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        constant = inner;
+    }
+    constant.to_string()
+}
+
+// Whether an expression is a constant, possibly negated, e.g. `-5`
+fn is_constant(expression: &Expression) -> bool {
+    match expression {
+        Expression::Constant(_) => true,
+        Expression::UnaryOperator(unary_operator_expression) => {
+            matches!(
+                unary_operator_expression.node.operator.node,
+                UnaryOperator::Minus | UnaryOperator::Plus
+            ) && is_constant(&unary_operator_expression.node.operand.node)
+        }
+        _ => false,
+    }
+}
+
+// Collects the constants of a file that initialize or are compared with a variable
+// of a known arithmetic type
+struct TypedConstants<'a, 'ast> {
+    source: &'ast str,
+    file: &'a str,
+    variables: HashMap<&'ast str, String>, // Type of each arithmetic variable by name
+    constants: Vec<(usize, String, String)>, // Line, text and type of each constant
+}
+
+impl<'ast> TypedConstants<'_, 'ast> {
+    fn record(&mut self, constant: &Node<Expression>, type_name: String) {
+        let (location, _) = get_location_for_offset(self.source, constant.span.start);
+        if location.file != self.file {
+            return;
+        }
+        let text = constant_text(&self.source[constant.span.start..constant.span.end]);
+        self.constants.push((location.line, text, type_name));
+    }
+
+    // Type of an expression that names an arithmetic variable
+    fn variable_type(&self, expression: &Expression) -> Option<String> {
+        let Expression::Identifier(identifier) = expression else {
+            return None;
+        };
+        self.variables.get(identifier.node.name.as_str()).cloned()
+    }
+}
+
+impl<'ast> Visit<'ast> for TypedConstants<'_, 'ast> {
+    fn visit_declaration(&mut self, declaration: &'ast Declaration, span: &'ast Span) {
+        let specifiers = type_specifiers(&declaration.specifiers);
+        if scalar_type(&specifiers).is_some() {
+            let type_name: Vec<String> = specifiers
+                .iter()
+                .map(|type_specifier| type_specifier_text(type_specifier))
+                .collect();
+            let type_name = type_name.join(" ");
+            for init_declarator in &declaration.declarators {
+                let declarator = &init_declarator.node.declarator.node;
+                let Some(name) = declarator_name(declarator) else {
+                    continue;
+                };
+                if !declarator.derived.is_empty() {
+                    self.variables.remove(name);
+                    continue;
+                }
+                self.variables.insert(name, type_name.clone());
+                if let Some(Initializer::Expression(expression)) = init_declarator
+                    .node
+                    .initializer
+                    .as_ref()
+                    .map(|initializer| &initializer.node)
+                {
+                    if is_constant(&expression.node) {
+                        self.record(expression, type_name.clone());
+                    }
+                }
+            }
+        }
+        visit_declaration(self, declaration, span);
+    }
+
+    fn visit_binary_operator_expression(
+        &mut self,
+        binary_operator_expression: &'ast BinaryOperatorExpression,
+        span: &'ast Span,
+    ) {
+        if matches!(
+            binary_operator_expression.operator.node,
+            BinaryOperator::Equals
+                | BinaryOperator::NotEquals
+                | BinaryOperator::Less
+                | BinaryOperator::Greater
+                | BinaryOperator::LessOrEqual
+                | BinaryOperator::GreaterOrEqual
+        ) {
+            let (lhs, rhs) = (
+                &binary_operator_expression.lhs,
+                &binary_operator_expression.rhs,
+            );
+            for (variable, constant) in [(lhs, rhs), (rhs, lhs)] {
+                if !is_constant(&constant.node) {
+                    continue;
+                }
+                if let Some(type_name) = self.variable_type(&variable.node) {
+                    self.record(constant, type_name);
+                }
+            }
+        }
+        visit_binary_operator_expression(self, binary_operator_expression, span);
+    }
+}
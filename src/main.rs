@@ -1,379 +1,742 @@
 extern crate lang_c;
 
-use std::collections::HashMap;
-
-use lang_c::driver::{parse, Config};
-use lang_c::loc::get_location_for_offset;
-use lang_c::print::Printer;
-use lang_c::span::Span;
-use lang_c::visit::Visit;
-use lang_c::visit::{
-    visit_call_expression, visit_cast_expression, visit_declaration, visit_function_definition,
-    visit_initializer, visit_statement, visit_while_statement,
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Duration, Instant};
+
+use lang_c::driver::Parse;
+use rayon::prelude::*;
+
+use nasa_static_analyzer::baseline::{load_baseline, write_baseline};
+use nasa_static_analyzer::blame::BlameIndex;
+use nasa_static_analyzer::cache::{AnalysisCache, CacheEntry, DEFAULT_CACHE_DIRECTORY};
+use nasa_static_analyzer::changes::{changed_files, ChangedLines};
+use nasa_static_analyzer::compare::{compare, Fingerprinted};
+use nasa_static_analyzer::conditional::check_conditional_branches;
+use nasa_static_analyzer::config::RulesetCache;
+use nasa_static_analyzer::error::FINDINGS_EXIT_CODE;
+use nasa_static_analyzer::history::append_history;
+use nasa_static_analyzer::index::{FileIndex, ProjectIndex};
+use nasa_static_analyzer::metrics::{
+    CodeMetrics, LiteralIndex, LoopBoundInventory, PragmaInventory, DEFAULT_DUPLICATE_THRESHOLD,
 };
-
-mod config;
-use config::load_ruleset;
-use config::RuleSet;
-
-#[derive(Debug)]
-enum SymbolType {
-    Function {
-        return_type: lang_c::ast::TypeSpecifier,
-    },
+use nasa_static_analyzer::plugin::Plugin;
+use nasa_static_analyzer::preprocessor::parse_directives;
+use nasa_static_analyzer::rules::{find_rule, POWER_OF_TEN_TITLES, RULES};
+use nasa_static_analyzer::workspace::{load_workspace, Workspace};
+use nasa_static_analyzer::{analyze_parsed, analyze_raw_source, parse_error_diagnostic};
+use nasa_static_analyzer::{parse_file, read_source, sort_diagnostics, PARSE_ERROR_RULE};
+use nasa_static_analyzer::{AnalyzerError, Diagnostic, DiagnosticSink, RuleSet, Severity};
+
+mod cli;
+mod daemon;
+mod watch;
+
+fn list_rules() {
+    println!(
+        "{:<34} {:<36} {:<9} {:<13} Power of Ten",
+        "ID", "Name", "Severity", "Category"
+    );
+    for rule in RULES {
+        let power_of_ten = rule
+            .power_of_ten
+            .map_or("-".to_string(), |number| format!("Rule {}", number));
+        println!(
+            "{:<34} {:<36} {:<9} {:<13} {}",
+            rule.id,
+            rule.name,
+            rule.default_severity.to_string(),
+            rule.category,
+            power_of_ten
+        );
+    }
 }
 
-#[derive(Debug)]
-struct Symbol {
-    _name: String,
-    symbol_type: SymbolType,
+// Print which findings were fixed, introduced or kept between two reports
+fn compare_reports(old: &str, new: &str) -> Result<(), AnalyzerError> {
+    let old = load_baseline(old)?;
+    let new = load_baseline(new)?;
+    let comparison = compare(&old, &new);
+
+    let print_section = |title: &str, findings: &[Fingerprinted]| {
+        println!("{} ({}):", title, findings.len());
+        for Fingerprinted {
+            fingerprint,
+            finding,
+        } in findings
+        {
+            println!(
+                "  {}  {}  {}:{}  {}",
+                fingerprint, finding.rule, finding.file, finding.line, finding.message
+            );
+        }
+        println!();
+    };
+    print_section("Fixed", &comparison.fixed);
+    print_section("New", &comparison.new);
+    print_section("Unchanged", &comparison.unchanged);
+
+    println!(
+        "{} fixed, {} new, {} unchanged",
+        comparison.fixed.len(),
+        comparison.new.len(),
+        comparison.unchanged.len()
+    );
+    Ok(())
 }
 
-#[derive(Debug)]
-struct StaticAnalyzer {
-    rule_set: RuleSet,                     // Configuration for the static analyzer
-    symbol_table: HashMap<String, Symbol>, // Symbol table to store the types of variables
-    current_function_type_cast: Option<lang_c::ast::TypeSpecifier>, // Type of the current function being analyzed, is None if not cast
-    source: String,                   // Source code of the program being analyzed
-    current_function: Option<String>, // Name of the current function being analyzed for recursion
-}
+fn explain_rule(name_or_id: &str) -> Result<(), AnalyzerError> {
+    let Some(rule) = find_rule(name_or_id) else {
+        return Err(AnalyzerError::Usage(format!(
+            "Unknown rule: {}",
+            name_or_id
+        )));
+    };
 
-impl StaticAnalyzer {
-    fn new(rule_set: RuleSet, source: String) -> Self {
-        StaticAnalyzer {
-            rule_set,
-            symbol_table: HashMap::new(),
-            current_function_type_cast: None,
-            source,
-            current_function: None,
-        }
+    println!("{} ({})", rule.id, rule.name);
+    println!("{}", rule.summary);
+    println!();
+    println!("Category: {}", rule.category);
+    println!("Default severity: {}", rule.default_severity);
+    if let Some(number) = rule.power_of_ten {
+        println!("Power of Ten: Rule {}", number);
     }
-
-    // Helper function to get the line number for a given offset in the source code
-    fn get_line_number(&self, span_point: usize) -> usize {
-        get_location_for_offset(&self.source, span_point).0.line
+    println!();
+    println!("Rationale:\n{}", rule.rationale);
+    println!();
+    println!("Non-compliant:\n{}", rule.non_compliant);
+    println!();
+    println!("Compliant:\n{}", rule.compliant);
+    println!();
+    println!("Configuration:\n{} = true", rule.name);
+    for option in rule.options {
+        println!("{}", option);
     }
+    Ok(())
+}
 
-    fn get_source_code_from_span(&self, span: &Span) -> String {
-        let source_line = &self.source[span.start..span.end];
-        let squiggles = "^".repeat(span.end - span.start); // Create squiggles for the span length
-        format!("{}\n{}", source_line, squiggles)
+// Counts per severity followed by a verdict for each Power of Ten rule. A rule fails
+// on any error or warning, advisories alone do not make it fail.
+fn print_summary(diagnostics: &[Diagnostic], evaluated: &HashSet<u8>) {
+    let count = |severity: Severity| {
+        diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == severity)
+            .count()
+    };
+    println!();
+    println!(
+        "{} findings: {} errors, {} warnings, {} advisories",
+        diagnostics.len(),
+        count(Severity::Error),
+        count(Severity::Warning),
+        count(Severity::Advisory)
+    );
+
+    println!();
+    println!("Power of Ten compliance:");
+    for (index, title) in POWER_OF_TEN_TITLES.iter().enumerate() {
+        let number = index as u8 + 1;
+        let failures = diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity != Severity::Advisory)
+            .filter(|diagnostic| {
+                find_rule(&diagnostic.rule).and_then(|rule| rule.power_of_ten) == Some(number)
+            })
+            .count();
+        let verdict = if !evaluated.contains(&number) {
+            "not evaluated".to_string()
+        } else if failures == 0 {
+            "✅ pass".to_string()
+        } else {
+            format!("❌ fail ({} findings)", failures)
+        };
+        println!("  Rule {:<2} {:<52} {}", number, title, verdict);
     }
+}
 
-    fn check_goto(&self, statement: &lang_c::ast::Statement, span: &Span) {
-        if let lang_c::ast::Statement::Goto(_) = statement {
-            let line_number = self.get_line_number(span.start);
-            println!("Error: 'goto' statement found at line {}", line_number);
+// One-screen overview of the whole run: the findings of every rule and severity, how
+// many files were analyzed and how long it took. The totals per severity are left to
+// print_summary, which runs just before.
+fn print_run_summary(
+    diagnostics: &[Diagnostic],
+    files_analyzed: usize,
+    files_skipped: usize,
+    elapsed: Duration,
+) {
+    let mut rule_counts: Vec<(&str, Severity, usize)> = Vec::new();
+    for diagnostic in diagnostics {
+        match rule_counts.iter_mut().find(|(rule, severity, _)| {
+            *rule == diagnostic.rule && *severity == diagnostic.severity
+        }) {
+            Some((_, _, count)) => *count += 1,
+            None => rule_counts.push((&diagnostic.rule, diagnostic.severity, 1)),
         }
     }
-
-    fn check_setjmp(&self, call_expression: &lang_c::ast::CallExpression, span: &Span) {
-        if let lang_c::ast::Expression::Identifier(identifier) = &call_expression.callee.node {
-            if identifier.node.name == "setjmp" {
-                let line_number = self.get_line_number(span.start);
-                println!("Error: 'setjmp' call found at line {}", line_number);
-            }
+    rule_counts.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(b.0)));
+
+    println!();
+    println!("== Run summary ==");
+    if !rule_counts.is_empty() {
+        println!("{:<48} {:<10} {:>8}", "Rule", "Severity", "Findings");
+        for (rule, severity, count) in &rule_counts {
+            let rule = find_rule(rule).map_or(rule.to_string(), |rule| rule.id.to_string());
+            println!("{:<48} {:<10} {:>8}", rule, severity.to_string(), count);
         }
+        println!();
     }
 
-    fn check_longjmp(&self, call_expression: &lang_c::ast::CallExpression, span: &Span) {
-        if let lang_c::ast::Expression::Identifier(identifier) = &call_expression.callee.node {
-            if identifier.node.name == "longjmp" {
-                let line_number = self.get_line_number(span.start);
-                println!("Error: 'longjmp' call found at line {}", line_number);
-            }
-        }
+    println!(
+        "Files: {} analyzed, {} skipped due to errors",
+        files_analyzed, files_skipped
+    );
+    println!("Analysis time: {:.2}s", elapsed.as_secs_f64());
+
+    // Mutable globals stand in the way of unit testing, so show where they are
+    let mut global_counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for diagnostic in diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.rule == "restrict_global_mutable_data")
+    {
+        *global_counts.entry(&diagnostic.file).or_default() += 1;
     }
-
-    fn set_current_function(&mut self, function_definition: &lang_c::ast::FunctionDefinition) {
-        let declarator = &function_definition.declarator.node.kind.node;
-        if let lang_c::ast::DeclaratorKind::Identifier(identifier) = declarator {
-            self.current_function = Some(identifier.node.name.clone());
+    if !global_counts.is_empty() {
+        println!();
+        println!("{:<60} {:>8}", "Mutable globals by file", "Globals");
+        for (file, count) in &global_counts {
+            println!("{:<60} {:>8}", file, count);
         }
     }
+}
 
-    fn check_recursion(&self, call_expression: &lang_c::ast::CallExpression, span: &Span) {
-        if let lang_c::ast::Expression::Identifier(identifier) = &call_expression.callee.node {
-            if let Some(current_function) = &self.current_function {
-                if identifier.node.name == *current_function {
-                    let line_number = self.get_line_number(span.start);
-                    println!("Error: Recursion found at line {}", line_number);
-                }
+// The command line's sink: prints each finding, followed by the commit and author
+// of its line when blame is asked for
+struct TerminalSink<'a> {
+    blame: &'a mut BlameIndex,
+    show_blame: bool,
+}
+
+impl DiagnosticSink for TerminalSink<'_> {
+    fn report(&mut self, diagnostic: Diagnostic) {
+        println!("{}", diagnostic);
+        if self.show_blame {
+            if let Some(line) = self.blame.diagnostic(&diagnostic) {
+                let commit = line.commit.get(..8).unwrap_or(&line.commit);
+                println!("    blame: {} {}", commit, line.author);
             }
         }
     }
+}
 
-    fn check_while_loop_bounds(&self, while_statement: &lang_c::ast::WhileStatement, span: &Span) {
-        if let lang_c::ast::Expression::BinaryOperator(binary_operator_expression) =
-            &while_statement.expression.node
-        {
-            match binary_operator_expression.node.operator.node {
-                lang_c::ast::BinaryOperator::Less
-                | lang_c::ast::BinaryOperator::LessOrEqual
-                | lang_c::ast::BinaryOperator::Greater
-                | lang_c::ast::BinaryOperator::GreaterOrEqual
-                | lang_c::ast::BinaryOperator::Equals => {
-                    // Check if one side of the condition is a constant
-                    if matches!(
-                        binary_operator_expression.node.lhs.node,
-                        lang_c::ast::Expression::Constant(_)
-                    ) || matches!(
-                        binary_operator_expression.node.rhs.node,
-                        lang_c::ast::Expression::Constant(_)
-                    ) {
-                        return;
-                    }
-                }
-                _ => {}
-            }
-        }
+fn print_diagnostics<'a>(
+    diagnostics: impl IntoIterator<Item = &'a Diagnostic>,
+    blame: &mut BlameIndex,
+    show_blame: bool,
+) {
+    let mut sink = TerminalSink { blame, show_blame };
+    for diagnostic in diagnostics {
+        sink.report(diagnostic.clone());
+    }
+}
+
+// A report per module with its own findings and Power of Ten verdicts, followed by a
+// roll-up of the finding counts of every module and the verdicts of the whole workspace
+fn print_workspace_report(
+    workspace: &Workspace,
+    diagnostics: &[Diagnostic],
+    module_of_file: &HashMap<String, usize>,
+    module_evaluated: &[HashSet<u8>],
+    blame: &mut BlameIndex,
+    show_blame: bool,
+) {
+    let module_diagnostics: Vec<Vec<Diagnostic>> = (0..workspace.modules.len())
+        .map(|index| {
+            diagnostics
+                .iter()
+                .filter(|diagnostic| module_of_file.get(&diagnostic.file) == Some(&index))
+                .cloned()
+                .collect()
+        })
+        .collect();
+
+    for (index, module) in workspace.modules.iter().enumerate() {
+        println!("== Module {} ==", module.name);
+        print_diagnostics(&module_diagnostics[index], blame, show_blame);
+        print_summary(&module_diagnostics[index], &module_evaluated[index]);
+        println!();
+    }
 
-        let line_number = self.get_line_number(span.start);
+    println!("== Workspace ==");
+    println!(
+        "{:<24} {:>8} {:>8} {:>8} {:>10}",
+        "Module", "Findings", "Errors", "Warnings", "Advisories"
+    );
+    let count = |diagnostics: &[Diagnostic], severity: Severity| {
+        diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == severity)
+            .count()
+    };
+    let rows = workspace
+        .modules
+        .iter()
+        .map(|module| module.name.as_str())
+        .zip(module_diagnostics.iter().map(Vec::as_slice))
+        .chain([("Total", diagnostics)]);
+    for (name, diagnostics) in rows {
         println!(
-            "Error: Loop at line {} does not have fixed bounds",
-            line_number
+            "{:<24} {:>8} {:>8} {:>8} {:>10}",
+            name,
+            diagnostics.len(),
+            count(diagnostics, Severity::Error),
+            count(diagnostics, Severity::Warning),
+            count(diagnostics, Severity::Advisory)
         );
     }
+    let evaluated: HashSet<u8> = module_evaluated.iter().flatten().copied().collect();
+    print_summary(diagnostics, &evaluated);
+}
 
-    fn check_heap_usage(&self, call_expression: &lang_c::ast::CallExpression, span: &Span) {
-        if let lang_c::ast::Expression::Identifier(identifier) = &call_expression.callee.node {
-            let heap_functions = ["malloc", "calloc", "realloc", "free"];
-            if heap_functions.contains(&identifier.node.name.as_str()) {
-                let line_number = self.get_line_number(span.start);
-                println!("Error: Heap usage found at line {}", line_number);
-                println!("{}", self.get_source_code_from_span(span));
-            }
+// Everything the analysis of one file produces, merged in file order afterwards
+struct FileAnalysis {
+    rule_set: RuleSet,
+    raw_source: String,
+    ast: Option<Parse>, // None when the file failed to parse or was served from the cache
+    diagnostics: Vec<Diagnostic>,
+    file_index: FileIndex,
+}
+
+// Analyze a single file. Runs on a worker thread with its own StaticAnalyzer, so
+// only the checks that need no state shared between files happen here.
+fn analyze_one(
+    file: &str,
+    module_ruleset: Option<&Path>,
+    plugin_rules: &[String],
+    options: &cli::Options,
+    rulesets: &RulesetCache,
+    cache: Option<&AnalysisCache>,
+) -> Result<FileAnalysis, AnalyzerError> {
+    let mut rule_set =
+        rulesets.resolve(Path::new(file), options.preset.as_deref(), module_ruleset)?;
+    rule_set.register_plugin_rules(plugin_rules.iter().map(String::as_str));
+    rule_set.apply_overrides(&options.enable, &options.disable)?;
+    let raw_source = read_source(Path::new(file))?;
+
+    let cache_key = cache.map(|cache| cache.key(Path::new(file), &raw_source, &rule_set));
+    if let (Some(cache), Some(key)) = (cache, &cache_key) {
+        if let Some(entry) = cache.load(key) {
+            return Ok(FileAnalysis {
+                rule_set,
+                raw_source,
+                ast: None,
+                diagnostics: entry.diagnostics,
+                file_index: entry.file_index,
+            });
         }
     }
 
-    fn add_function_to_symbol_table(&mut self, declaration: &lang_c::ast::Declaration) {
-        for init_declarator in &declaration.declarators {
-            if let Some(lang_c::span::Node {
-                node: lang_c::ast::DerivedDeclarator::KRFunction(_),
-                ..
-            }) = init_declarator.node.declarator.node.derived.first()
-            {
-                if let lang_c::ast::DeclaratorKind::Identifier(identifier) =
-                    &init_declarator.node.declarator.node.kind.node
-                {
-                    // Extract the return type of the function
-                    let return_type = self.extract_return_type(declaration);
-
-                    // Insert the function into the symbol table with its return type
-                    self.symbol_table.insert(
-                        identifier.node.name.clone(),
-                        Symbol {
-                            _name: identifier.node.name.clone(),
-                            symbol_type: SymbolType::Function { return_type },
-                        },
-                    );
-                }
+    // Report files that fail to parse and carry on with the remaining files
+    let (ast, diagnostics, file_index) = match parse_file(Path::new(file))? {
+        Ok(ast) => {
+            let mut diagnostics = analyze_parsed(file, &raw_source, &ast, &rule_set);
+            if rule_set.require_parsable_conditional_branches {
+                diagnostics.extend(check_conditional_branches(
+                    Path::new(file),
+                    &raw_source,
+                    &rule_set,
+                )?);
             }
+            let file_index = FileIndex::collect(&ast.source, &ast.unit, &rule_set);
+            (Some(ast), diagnostics, file_index)
+        }
+        Err(error) => {
+            let mut diagnostics = vec![parse_error_diagnostic(&error, &rule_set)];
+            diagnostics.extend(analyze_raw_source(file, &raw_source, &rule_set));
+            (None, diagnostics, FileIndex::default())
         }
+    };
+
+    if let (Some(cache), Some(key)) = (cache, &cache_key) {
+        let entry = CacheEntry {
+            diagnostics: diagnostics.clone(),
+            file_index: file_index.clone(),
+        };
+        cache.store(key, &entry)?;
     }
 
-    // Helper function to extract the return type
-    fn extract_return_type(
-        &self,
-        declaration: &lang_c::ast::Declaration,
-    ) -> lang_c::ast::TypeSpecifier {
-        match &declaration.specifiers[..] {
-            [lang_c::span::Node {
-                node: lang_c::ast::DeclarationSpecifier::TypeSpecifier(type_specifier),
-                ..
-            }] => type_specifier.node.clone(), // Clone the TypeSpecifier for storage
-            _ => lang_c::ast::TypeSpecifier::Void, // Default to void if unknown
-        }
+    Ok(FileAnalysis {
+        rule_set,
+        raw_source,
+        ast,
+        diagnostics,
+        file_index,
+    })
+}
+
+// Keep the findings within the change under review: on lines changed since
+// --diff-base, and last changed by --author and after --since. Legacy findings
+// outside the change do not block it. Findings without blame information only
+// pass when no author or date filter is given.
+fn retain_in_review_scope(
+    diagnostics: &mut Vec<Diagnostic>,
+    options: &cli::Options,
+    blame: &mut BlameIndex,
+) -> Result<(), AnalyzerError> {
+    if let Some(base) = &options.diff_base {
+        let changed = ChangedLines::since(base)?;
+        diagnostics.retain(|diagnostic| changed.contains(&diagnostic.file, diagnostic.line));
     }
 
-    // This will only handle cases where the function call is the immediate child of an initializer
-    // It should handle cases where the function call is nested within other expressions
-    fn mark_function_type_checked(&mut self, initializer: &lang_c::ast::Initializer) {
-        if let lang_c::ast::Initializer::Expression(expression) = &initializer {
-            let node = &expression.node;
-            if let lang_c::ast::Expression::Call(call) = node {
-                let callee = &call.node.callee.node;
-                if let lang_c::ast::Expression::Identifier(identifier) = callee {
-                    let func_name = &identifier.node.name;
-                    // If function is in symbol table, set self.current_function_type_cast to return type of function
-                    if let Some(symbol) = self.symbol_table.get(func_name) {
-                        let SymbolType::Function { return_type } = &symbol.symbol_type;
-                        self.current_function_type_cast = Some(return_type.clone());
-                    }
-                }
-            }
-        }
+    if options.author.is_some() || options.since.is_some() {
+        diagnostics.retain(|diagnostic| {
+            blame.diagnostic(diagnostic).is_some_and(|line| {
+                options
+                    .author
+                    .as_ref()
+                    .map_or(true, |author| &line.author == author)
+                    && options.since.map_or(true, |since| line.time >= since)
+            })
+        });
     }
+    Ok(())
+}
 
-    fn check_return_value(&self, call_expression: &lang_c::ast::CallExpression, span: &Span) {
-        if let lang_c::ast::Expression::Identifier(identifier) = &call_expression.callee.node {
-            if let Some(symbol) = self.symbol_table.get(&identifier.node.name) {
-                let SymbolType::Function { return_type } = &symbol.symbol_type;
-                // Check if the return type is not void
-                if *return_type != lang_c::ast::TypeSpecifier::Void {
-                    // Ensure that current_function_type_cast is set
-                    if self.current_function_type_cast.is_none() {
-                        let line_number = self.get_line_number(span.start);
-                        println!(
-                                "Error: Call to non-void function at line {} does not handle return value",
-                                line_number
-                            );
-                        println!("{}", self.get_source_code_from_span(span));
-                    }
-                }
-            }
-        }
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("error: {}", error);
+        process::exit(error.exit_code());
     }
 }
 
-impl<'ast> Visit<'ast> for StaticAnalyzer {
-    fn visit_initializer(&mut self, initializer: &'ast lang_c::ast::Initializer, span: &'ast Span) {
-        self.mark_function_type_checked(initializer);
-        visit_initializer(self, initializer, span);
-        self.current_function_type_cast = None;
+fn run() -> Result<(), AnalyzerError> {
+    let options = cli::parse_args()?;
+
+    if let Some(rule) = &options.explain {
+        return explain_rule(rule);
     }
 
-    fn visit_declaration(&mut self, declaration: &'ast lang_c::ast::Declaration, span: &'ast Span) {
-        if self.rule_set.check_return_value {
-            self.add_function_to_symbol_table(declaration);
-        }
-        visit_declaration(self, declaration, span);
-        self.current_function_type_cast = None;
+    if let Some((old, new)) = &options.compare {
+        return compare_reports(old, new);
     }
-    fn visit_statement(&mut self, statement: &'ast lang_c::ast::Statement, span: &'ast Span) {
-        if self.rule_set.restrict_goto {
-            self.check_goto(statement, span);
-        }
 
-        visit_statement(self, statement, span);
+    if options.list_rules {
+        list_rules();
+        return Ok(());
     }
 
-    fn visit_function_definition(
-        &mut self,
-        function_definition: &'ast lang_c::ast::FunctionDefinition,
-        span: &'ast Span,
-    ) {
-        if let lang_c::ast::DeclaratorKind::Identifier(identifier) =
-            &function_definition.declarator.node.kind.node
-        {
-            // Extract the return type of the function
-            let return_type = match &function_definition.specifiers[..] {
-                [lang_c::span::Node {
-                    node: lang_c::ast::DeclarationSpecifier::TypeSpecifier(type_specifier),
-                    ..
-                }] => {
-                    type_specifier.node.clone() // Clone the TypeSpecifier for storage
-                }
-                _ => lang_c::ast::TypeSpecifier::Void, // Default to void if unknown
-            };
-
-            // Insert the function into the symbol table with its return type
-            self.symbol_table.insert(
-                identifier.node.name.clone(),
-                Symbol {
-                    _name: identifier.node.name.clone(),
-                    symbol_type: SymbolType::Function { return_type },
-                },
-            );
-        }
+    if options.daemon {
+        return match &options.socket {
+            #[cfg(unix)]
+            Some(path) => daemon::serve_socket(&options, path),
+            #[cfg(not(unix))]
+            Some(_) => Err(AnalyzerError::Usage(
+                "--socket is only supported on Unix".to_string(),
+            )),
+            None => daemon::serve_stdio(&options),
+        };
+    }
 
-        if self.rule_set.restrict_recursion {
-            self.set_current_function(function_definition);
-        }
+    let started = Instant::now();
+    let mut diagnostics = Vec::new();
+    let mut files_skipped = 0;
+    let mut first_error = None;
+    let mut literal_index = LiteralIndex::default();
+    let mut pragma_inventory = PragmaInventory::default();
+    let mut loop_bound_inventory = LoopBoundInventory::default();
+    let mut code_metrics = CodeMetrics::default();
+    let mut project_index = ProjectIndex::default();
+    let mut evaluated_rules = HashSet::new();
+    let mut lines_of_code = 0;
+    let mut plugins: Vec<Plugin> = options
+        .plugins
+        .iter()
+        .map(|path| Plugin::load(Path::new(path)))
+        .collect::<Result<_, _>>()?;
+    let plugin_rules: Vec<String> = plugins
+        .iter()
+        .flat_map(Plugin::rule_names)
+        .map(str::to_string)
+        .collect();
+
+    if let Some(jobs) = options.jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build_global()
+            .map_err(|error| AnalyzerError::Usage(format!("--jobs: {}", error)))?;
+    }
 
-        if self.rule_set.restrict_function_size {
-            let start_line = self.get_line_number(span.start);
-            let end_line = self.get_line_number(span.end);
-            let size = end_line - start_line + 1;
-
-            // TODO: This should be configurable
-            if size > 60 {
-                println!(
-                    "Error: Function size exceeds 60 lines at line {}",
-                    start_line
-                );
+    // In workspace mode the files come from the modules, each tagged with its module
+    let workspace = options
+        .workspace
+        .as_deref()
+        .map(|path| load_workspace(Path::new(path)))
+        .transpose()?;
+    let mut units: Vec<(String, Option<usize>)> = match &workspace {
+        Some(workspace) => {
+            let mut units = Vec::new();
+            for (index, module) in workspace.modules.iter().enumerate() {
+                let files = module.source_files()?;
+                units.extend(files.into_iter().map(|file| (file, Some(index))));
             }
+            units
         }
+        None => options
+            .files
+            .iter()
+            .map(|file| (file.clone(), None))
+            .collect(),
+    };
 
-        visit_function_definition(self, function_definition, span);
-
-        self.current_function = None;
+    // Narrow the run down to what git reports as changed. Headers are not analyzed on
+    // their own, and cross-file rules, which would only see the changed files, are skipped.
+    if options.changed_only {
+        let changed = changed_files(options.changed_since.as_deref())?;
+        if workspace.is_none() && options.files.is_empty() {
+            units = changed
+                .into_iter()
+                .filter(|file| Path::new(file).extension().is_some_and(|ext| ext == "c"))
+                .map(|file| (file, None))
+                .collect();
+        } else {
+            let changed: HashSet<PathBuf> = changed
+                .iter()
+                .filter_map(|file| fs::canonicalize(file).ok())
+                .collect();
+            units.retain(|(file, _)| {
+                fs::canonicalize(file).is_ok_and(|path| changed.contains(&path))
+            });
+        }
+        if units.is_empty() {
+            println!("No changed files to analyze");
+            return Ok(());
+        }
     }
 
-    fn visit_cast_expression(
-        &mut self,
-        cast_expression: &'ast lang_c::ast::CastExpression,
-        span: &'ast Span,
-    ) {
-        let Some(specifier) = cast_expression.type_name.node.specifiers.first() else {
-            return;
+    let mut module_of_file = HashMap::new();
+    let module_count = workspace
+        .as_ref()
+        .map_or(0, |workspace| workspace.modules.len());
+    let mut module_evaluated = vec![HashSet::new(); module_count];
+
+    // Plugins and the metrics need every file's AST, which the cache does not keep
+    let collect_metrics = options.metrics || options.metrics_json.is_some();
+    let cache = (options.cache && options.plugins.is_empty() && !collect_metrics)
+        .then(|| AnalysisCache::new(Path::new(DEFAULT_CACHE_DIRECTORY)));
+    let rulesets = RulesetCache::default();
+
+    // Files are analyzed in parallel, then merged in the order they were given so
+    // the output does not depend on thread scheduling. Files that cannot be analyzed
+    // are reported and skipped, and the first of their errors sets the exit code.
+    let analyses: Vec<Result<FileAnalysis, AnalyzerError>> = units
+        .par_iter()
+        .map(|(file, module)| {
+            let module_ruleset = module
+                .and_then(|index| workspace.as_ref()?.modules[index].ruleset.as_deref())
+                .map(Path::new);
+            analyze_one(
+                file,
+                module_ruleset,
+                &plugin_rules,
+                &options,
+                &rulesets,
+                cache.as_ref(),
+            )
+        })
+        .collect();
+
+    for ((file, module), analysis) in units.iter().zip(analyses) {
+        let FileAnalysis {
+            rule_set,
+            raw_source,
+            ast,
+            diagnostics: file_diagnostics,
+            file_index,
+        } = match analysis {
+            Ok(analysis) => analysis,
+            Err(error) => {
+                eprintln!("error: {}", error);
+                files_skipped += 1;
+                first_error.get_or_insert(error);
+                continue;
+            }
         };
-
-        let type_specifier: &lang_c::ast::SpecifierQualifier = &specifier.node;
-        if let lang_c::ast::SpecifierQualifier::TypeSpecifier(type_specifier) = type_specifier {
-            self.current_function_type_cast = Some(type_specifier.node.clone());
+        let evaluated = RULES
+            .iter()
+            .filter(|rule| rule_set.is_enabled(rule.name))
+            .filter_map(|rule| rule.power_of_ten);
+        evaluated_rules.extend(evaluated.clone());
+        lines_of_code += raw_source.lines().count();
+        if file_diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.rule == PARSE_ERROR_RULE)
+        {
+            files_skipped += 1;
         }
-        visit_cast_expression(self, cast_expression, span);
-        self.current_function_type_cast = None;
-    }
 
-    fn visit_call_expression(
-        &mut self,
-        call_expression: &'ast lang_c::ast::CallExpression,
-        span: &'ast Span,
-    ) {
-        if self.rule_set.restrict_recursion {
-            self.check_recursion(call_expression, span);
+        // Findings in headers belong to the module of the first file including them
+        if let Some(module) = *module {
+            module_evaluated[module].extend(evaluated);
+            module_of_file.entry(file.clone()).or_insert(module);
+            for diagnostic in &file_diagnostics {
+                module_of_file
+                    .entry(diagnostic.file.clone())
+                    .or_insert(module);
+            }
         }
+        diagnostics.extend(file_diagnostics);
+        project_index.add(file, file_index);
 
-        if self.rule_set.restrict_setjmp {
-            self.check_setjmp(call_expression, span);
-        }
+        let Some(ast) = ast else {
+            continue;
+        };
 
-        if self.rule_set.restrict_longjmp {
-            self.check_longjmp(call_expression, span);
+        // Plugins and the metrics indexes are not thread safe and run here
+        for plugin in &mut plugins {
+            diagnostics.extend(plugin.check(&ast, &rule_set));
         }
 
-        if self.rule_set.restrict_heap_allocation {
-            self.check_heap_usage(call_expression, span);
+        if collect_metrics {
+            literal_index.collect(&ast.source, &ast.unit);
+            pragma_inventory.collect(file, &parse_directives(&raw_source));
+            loop_bound_inventory.collect(file, &raw_source, &rule_set.loop_bound_marker);
+            code_metrics.collect(file, &raw_source, &ast);
         }
+    }
 
-        if self.rule_set.check_return_value {
-            self.check_return_value(call_expression, span);
-        }
+    // A function used only by files left out of the run would look file-local
+    if !options.changed_only {
+        diagnostics.extend(project_index.check_missing_static());
+    }
+    sort_diagnostics(&mut diagnostics);
 
-        visit_call_expression(self, call_expression, span);
+    // History records every finding, including those accepted in the baseline
+    if let Some(path) = &options.append_history {
+        append_history(path, lines_of_code, &diagnostics)?;
     }
 
-    fn visit_while_statement(
-        &mut self,
-        while_statement: &'ast lang_c::ast::WhileStatement,
-        span: &'ast Span,
-    ) {
-        if self.rule_set.fixed_loop_bounds {
-            self.check_while_loop_bounds(while_statement, span);
+    if let Some(path) = &options.write_baseline {
+        write_baseline(path, &diagnostics)?;
+    }
+
+    let baseline = options.baseline.as_deref().map(load_baseline).transpose()?;
+    if let Some(baseline) = &baseline {
+        diagnostics = baseline.filter_new(diagnostics);
+    }
+
+    let mut blame = BlameIndex::default();
+    retain_in_review_scope(&mut diagnostics, &options, &mut blame)?;
+
+    match &workspace {
+        Some(workspace) => print_workspace_report(
+            workspace,
+            &diagnostics,
+            &module_of_file,
+            &module_evaluated,
+            &mut blame,
+            options.blame,
+        ),
+        None => {
+            print_diagnostics(&diagnostics, &mut blame, options.blame);
+            print_summary(&diagnostics, &evaluated_rules);
         }
-        visit_while_statement(self, while_statement, span);
     }
-}
 
-fn main() {
-    let config = Config::default();
-    let Ok(ast) = parse(&config, "example.c") else {
-        panic!("Failed to parse the input file");
-    };
+    if options.metrics {
+        literal_index.print_duplicates(
+            options
+                .duplicate_threshold
+                .unwrap_or(DEFAULT_DUPLICATE_THRESHOLD),
+        );
+        pragma_inventory.print();
+        loop_bound_inventory.print();
+        code_metrics.print();
+    }
+    if let Some(path) = &options.metrics_json {
+        code_metrics.write_json(path)?;
+    }
 
-    let mut buf = String::new();
-    let mut printer = Printer::new(&mut buf);
-    printer.visit_translation_unit(&ast.unit);
+    print_run_summary(
+        &diagnostics,
+        units.len() - files_skipped,
+        files_skipped,
+        started.elapsed(),
+    );
 
-    println!("{}", buf);
+    // Failures were reported as they happened, watching carries on regardless
+    if let Some(error) = first_error.filter(|_| !options.watch) {
+        process::exit(error.exit_code());
+    }
 
-    let rule_set = load_ruleset("ruleset.toml");
-    let source = ast.source;
+    // Errors and warnings left after the baseline and filters fail the run, so it
+    // can gate CI. Advisories alone do not.
+    if !options.watch
+        && diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity != Severity::Advisory)
+    {
+        process::exit(FINDINGS_EXIT_CODE);
+    }
+
+    if options.watch {
+        // Changed files are re-analyzed on their own, so cross-file rules are not updated
+        let files: Vec<String> = units.iter().map(|(file, _)| file.clone()).collect();
+        watch::watch_files(&files, |changed| {
+            // The changed files no longer match the blame collected before the change
+            blame = BlameIndex::default();
+            // Config files may have been edited alongside the sources
+            rulesets.clear();
+            for file in changed {
+                let module = units
+                    .iter()
+                    .find(|(unit, _)| unit == file)
+                    .and_then(|(_, module)| *module);
+                let module_ruleset = module
+                    .and_then(|index| workspace.as_ref()?.modules[index].ruleset.as_deref())
+                    .map(Path::new);
+                let analysis = match analyze_one(
+                    file,
+                    module_ruleset,
+                    &plugin_rules,
+                    &options,
+                    &rulesets,
+                    cache.as_ref(),
+                ) {
+                    Ok(analysis) => analysis,
+                    Err(error) => {
+                        eprintln!("error: {}", error);
+                        continue;
+                    }
+                };
 
-    let mut analyzer = StaticAnalyzer::new(rule_set, source);
-    analyzer.visit_translation_unit(&ast.unit);
+                let mut diagnostics = analysis.diagnostics;
+                if let Some(ast) = &analysis.ast {
+                    for plugin in &mut plugins {
+                        diagnostics.extend(plugin.check(ast, &analysis.rule_set));
+                    }
+                }
+                sort_diagnostics(&mut diagnostics);
+                if let Some(baseline) = &baseline {
+                    diagnostics = baseline.filter_new(diagnostics);
+                }
+                if let Err(error) = retain_in_review_scope(&mut diagnostics, &options, &mut blame) {
+                    eprintln!("error: {}", error);
+                    continue;
+                }
+                let evaluated = RULES
+                    .iter()
+                    .filter(|rule| analysis.rule_set.is_enabled(rule.name))
+                    .filter_map(|rule| rule.power_of_ten)
+                    .collect();
+
+                println!();
+                println!("== {} ==", file);
+                print_diagnostics(&diagnostics, &mut blame, options.blame);
+                print_summary(&diagnostics, &evaluated);
+            }
+        })?;
+    }
 
-    //println!("{:?}", analyzer.symbol_table);
+    Ok(())
 }
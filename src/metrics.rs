@@ -0,0 +1,420 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use lang_c::ast::{
+    BinaryOperator, DeclarationSpecifier, DerivedDeclarator, Expression, ExternalDeclaration,
+    FunctionDefinition, IfStatement, Label, ParameterDeclaration, Statement, TranslationUnit,
+    TypeSpecifier,
+};
+use lang_c::driver::Parse;
+use lang_c::span::Span;
+use lang_c::visit::{visit_expression, visit_statement, Visit};
+use serde::Serialize;
+
+use crate::analyzer::{count_source_lines, declarator_name, parse_loop_bound};
+use crate::error::AnalyzerError;
+use crate::location::LineIndex;
+use crate::preprocessor::Directive;
+
+// Number of call sites at which a literal is reported as duplicated, unless overridden
+pub const DEFAULT_DUPLICATE_THRESHOLD: usize = 3;
+
+// Numeric constants too common to be worth centralizing
+const TRIVIAL_CONSTANTS: [&str; 2] = ["0", "1"];
+
+// Every string literal and numeric constant in the analyzed files with the places it is used
+#[derive(Default)]
+pub struct LiteralIndex {
+    occurrences: HashMap<String, Vec<(String, usize)>>,
+    original_sources: HashMap<String, Option<String>>, // Original files read back to skip macro expansions
+}
+
+impl LiteralIndex {
+    pub fn collect(&mut self, source: &str, unit: &TranslationUnit) {
+        let mut collector = LiteralCollector {
+            source,
+            line_index: LineIndex::new(source),
+            index: self,
+        };
+        collector.visit_translation_unit(unit);
+    }
+
+    fn record(&mut self, source: &str, line_index: &LineIndex, span: &Span) {
+        let literal = source[span.start..span.end].to_string();
+        if TRIVIAL_CONSTANTS.contains(&literal.as_str()) {
+            return;
+        }
+
+        let location = line_index.location(span.start);
+        let file = location.file.to_string();
+        let line = location.line;
+
+        // Literals that come from a macro expansion are already centralized in the macro
+        let original_source = self
+            .original_sources
+            .entry(file.clone())
+            .or_insert_with(|| fs::read_to_string(&file).ok());
+        if let Some(original_source) = original_source {
+            let original_line = original_source.lines().nth(line - 1).unwrap_or_default();
+            if !original_line.contains(&literal) {
+                return;
+            }
+        }
+
+        self.occurrences
+            .entry(literal)
+            .or_default()
+            .push((file, line));
+    }
+
+    // Print every literal used at `threshold` or more places, most duplicated first
+    pub fn print_duplicates(&self, threshold: usize) {
+        let mut duplicates: Vec<(&String, &Vec<(String, usize)>)> = self
+            .occurrences
+            .iter()
+            .filter(|(_, sites)| sites.len() >= threshold)
+            .collect();
+        duplicates.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then(a.0.cmp(b.0)));
+
+        println!("Duplicated literals (used at {} or more sites):", threshold);
+        for (literal, sites) in duplicates {
+            let sites: Vec<String> = sites
+                .iter()
+                .map(|(file, line)| format!("{}:{}", file, line))
+                .collect();
+            println!("  {} x{}: {}", literal, sites.len(), sites.join(", "));
+        }
+    }
+}
+
+// Every #pragma in the analyzed files by kind and file, for reviewers to see
+// everything that changes how the code is compiled
+#[derive(Default)]
+pub struct PragmaInventory {
+    pragmas: BTreeMap<String, BTreeMap<String, Vec<usize>>>,
+}
+
+impl PragmaInventory {
+    pub fn collect(&mut self, file: &str, directives: &[Directive]) {
+        for directive in directives {
+            if let Some(kind) = directive.pragma_kind() {
+                self.pragmas
+                    .entry(kind)
+                    .or_default()
+                    .entry(file.to_string())
+                    .or_default()
+                    .push(directive.line);
+            }
+        }
+    }
+
+    pub fn print(&self) {
+        println!("Pragmas:");
+        for (kind, files) in &self.pragmas {
+            let count: usize = files.values().map(Vec::len).sum();
+            println!("  {} x{}", kind, count);
+            for (file, lines) in files {
+                let lines: Vec<String> = lines.iter().map(usize::to_string).collect();
+                println!("    {}: {}", file, lines.join(", "));
+            }
+        }
+    }
+}
+
+// Loop bounds declared in comments, for reviewers to check every bound the fixed
+// loop bound rule takes on trust
+#[derive(Default)]
+pub struct LoopBoundInventory {
+    bounds: Vec<(String, usize, u64, String)>, // File, line, declared bound and the loop below it
+}
+
+impl LoopBoundInventory {
+    pub fn collect(&mut self, file: &str, raw_source: &str, marker: &str) {
+        let lines: Vec<&str> = raw_source.lines().collect();
+        for (index, line) in lines.iter().enumerate() {
+            let Some(bound) = parse_loop_bound(line, marker) else {
+                continue;
+            };
+            // The loop starts on the first line after the comment block
+            let loop_line = lines[index + 1..]
+                .iter()
+                .map(|line| line.trim())
+                .find(|line| {
+                    !line.is_empty()
+                        && !line.starts_with("//")
+                        && !line.starts_with("/*")
+                        && !line.starts_with('*')
+                })
+                .unwrap_or_default();
+            self.bounds
+                .push((file.to_string(), index + 1, bound, loop_line.to_string()));
+        }
+    }
+
+    pub fn print(&self) {
+        println!("Declared loop bounds:");
+        for (file, line, bound, loop_line) in &self.bounds {
+            println!("  {}:{}: {} iterations: {}", file, line, bound, loop_line);
+        }
+    }
+}
+
+// Size and complexity of a single function, independent of any rule's limits
+#[derive(Debug, Serialize)]
+pub struct FunctionMetrics {
+    pub name: String,
+    pub line: usize,
+    pub lines_of_code: usize, // Lines with code, leaving out blank and comment-only lines
+    pub cyclomatic_complexity: usize, // Decision points plus one
+    pub nesting_depth: usize, // Deepest nesting of control statements, 0 for straight-line code
+    pub parameters: usize,
+    pub comment_density: f64, // Share of the non-blank lines that only hold comments
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileMetrics {
+    pub file: String,
+    pub lines_of_code: usize,
+    pub comment_density: f64,
+    pub functions: Vec<FunctionMetrics>,
+}
+
+// Metrics of every analyzed file and the functions defined in it, for software
+// assurance reports
+#[derive(Debug, Default, Serialize)]
+pub struct CodeMetrics {
+    pub files: Vec<FileMetrics>,
+}
+
+impl CodeMetrics {
+    // Measure a file and the functions defined in it. Functions defined in included
+    // headers are left out, headers are measured when they are analyzed themselves.
+    pub fn collect(&mut self, file: &str, raw_source: &str, parse: &Parse) {
+        let lines: Vec<&str> = raw_source.lines().collect();
+        let line_index = LineIndex::new(&parse.source);
+
+        let mut functions = Vec::new();
+        for declaration in &parse.unit.0 {
+            let ExternalDeclaration::FunctionDefinition(definition) = &declaration.node else {
+                continue;
+            };
+            let start = line_index.location(definition.span.start);
+            if start.file != file {
+                continue;
+            }
+            let end = line_index.line(definition.span.end).max(start.line);
+            let body = lines
+                .get(start.line - 1..end.min(lines.len()))
+                .unwrap_or(&[]);
+
+            let mut complexity = ComplexityCollector::default();
+            complexity.visit_statement(
+                &definition.node.statement.node,
+                &definition.node.statement.span,
+            );
+            functions.push(FunctionMetrics {
+                name: declarator_name(&definition.node.declarator.node)
+                    .unwrap_or_default()
+                    .to_string(),
+                line: start.line,
+                lines_of_code: count_source_lines(body, false, false),
+                cyclomatic_complexity: complexity.decisions + 1,
+                nesting_depth: complexity.max_depth,
+                parameters: parameter_count(&definition.node),
+                comment_density: comment_density(body),
+            });
+        }
+
+        self.files.push(FileMetrics {
+            file: file.to_string(),
+            lines_of_code: count_source_lines(&lines, false, false),
+            comment_density: comment_density(&lines),
+            functions,
+        });
+    }
+
+    pub fn print(&self) {
+        println!("Code metrics:");
+        for file in &self.files {
+            println!(
+                "  {}: {} lines of code, {:.0}% comments",
+                file.file,
+                file.lines_of_code,
+                file.comment_density * 100.0
+            );
+            if file.functions.is_empty() {
+                continue;
+            }
+            println!(
+                "    {:<32} {:>6} {:>6} {:>10} {:>7} {:>6} {:>8}",
+                "Function", "Line", "LOC", "Complexity", "Nesting", "Params", "Comments"
+            );
+            for function in &file.functions {
+                println!(
+                    "    {:<32} {:>6} {:>6} {:>10} {:>7} {:>6} {:>7.0}%",
+                    function.name,
+                    function.line,
+                    function.lines_of_code,
+                    function.cyclomatic_complexity,
+                    function.nesting_depth,
+                    function.parameters,
+                    function.comment_density * 100.0
+                );
+            }
+        }
+    }
+
+    pub fn write_json(&self, path: &str) -> Result<(), AnalyzerError> {
+        let write_error = |error| AnalyzerError::OutputWrite {
+            path: Path::new(path).to_path_buf(),
+            error,
+        };
+        let content =
+            serde_json::to_string_pretty(self).map_err(|error| write_error(error.into()))?;
+        fs::write(path, content).map_err(write_error)
+    }
+}
+
+// Share of the non-blank lines that only hold comments
+fn comment_density(lines: &[&str]) -> f64 {
+    let non_blank = count_source_lines(lines, false, true);
+    if non_blank == 0 {
+        return 0.0;
+    }
+    let code = count_source_lines(lines, false, false);
+    (non_blank - code) as f64 / non_blank as f64
+}
+
+// Declared parameters of a function, where a lone `void` means none
+fn parameter_count(definition: &FunctionDefinition) -> usize {
+    definition
+        .declarator
+        .node
+        .derived
+        .iter()
+        .find_map(|derived| match &derived.node {
+            DerivedDeclarator::Function(function) => match function.node.parameters.as_slice() {
+                [parameter] if is_void_parameter(&parameter.node) => Some(0),
+                parameters => Some(parameters.len()),
+            },
+            DerivedDeclarator::KRFunction(names) => Some(names.len()),
+            _ => None,
+        })
+        .unwrap_or(0)
+}
+
+fn is_void_parameter(parameter: &ParameterDeclaration) -> bool {
+    parameter.declarator.is_none()
+        && matches!(
+            parameter.specifiers.as_slice(),
+            [specifier] if matches!(
+                &specifier.node,
+                DeclarationSpecifier::TypeSpecifier(type_specifier)
+                    if matches!(type_specifier.node, TypeSpecifier::Void)
+            )
+        )
+}
+
+// Counts the decision points of a function and how deeply its control statements nest
+#[derive(Default)]
+struct ComplexityCollector {
+    decisions: usize,
+    depth: usize,
+    max_depth: usize,
+}
+
+impl ComplexityCollector {
+    fn enter(&mut self) {
+        self.depth += 1;
+        self.max_depth = self.max_depth.max(self.depth);
+    }
+
+    fn visit_nested<'ast>(&mut self, statement: &'ast Statement, span: &'ast Span) {
+        self.enter();
+        self.visit_statement(statement, span);
+        self.depth -= 1;
+    }
+}
+
+impl<'ast> Visit<'ast> for ComplexityCollector {
+    fn visit_statement(&mut self, statement: &'ast Statement, span: &'ast Span) {
+        match statement {
+            Statement::If(_) | Statement::While(_) | Statement::DoWhile(_) | Statement::For(_) => {
+                self.decisions += 1
+            }
+            Statement::Labeled(labeled)
+                if matches!(
+                    labeled.node.label.node,
+                    Label::Case(_) | Label::CaseRange(_)
+                ) =>
+            {
+                self.decisions += 1
+            }
+            _ => {}
+        }
+
+        // The branches of an if nest on their own, see visit_if_statement
+        let nests = matches!(
+            statement,
+            Statement::While(_) | Statement::DoWhile(_) | Statement::For(_) | Statement::Switch(_)
+        );
+        if nests {
+            self.enter();
+        }
+        visit_statement(self, statement, span);
+        if nests {
+            self.depth -= 1;
+        }
+    }
+
+    fn visit_if_statement(&mut self, if_statement: &'ast IfStatement, _: &'ast Span) {
+        let condition = &if_statement.condition;
+        self.visit_expression(&condition.node, &condition.span);
+        let then_statement = &if_statement.then_statement;
+        self.visit_nested(&then_statement.node, &then_statement.span);
+
+        let Some(else_statement) = &if_statement.else_statement else {
+            return;
+        };
+        match &else_statement.node {
+            // An else-if chain stays at the depth of its first if
+            Statement::If(chained) => {
+                self.decisions += 1;
+                self.visit_if_statement(&chained.node, &else_statement.span);
+            }
+            statement => self.visit_nested(statement, &else_statement.span),
+        }
+    }
+
+    fn visit_expression(&mut self, expression: &'ast Expression, span: &'ast Span) {
+        match expression {
+            Expression::Conditional(_) => self.decisions += 1,
+            Expression::BinaryOperator(binary)
+                if matches!(
+                    binary.node.operator.node,
+                    BinaryOperator::LogicalAnd | BinaryOperator::LogicalOr
+                ) =>
+            {
+                self.decisions += 1
+            }
+            _ => {}
+        }
+        visit_expression(self, expression, span);
+    }
+}
+
+struct LiteralCollector<'a> {
+    source: &'a str,
+    line_index: LineIndex,
+    index: &'a mut LiteralIndex,
+}
+
+impl<'ast> Visit<'ast> for LiteralCollector<'_> {
+    fn visit_expression(&mut self, expression: &'ast Expression, span: &'ast Span) {
+        if let Expression::Constant(_) | Expression::StringLiteral(_) = expression {
+            self.index.record(self.source, &self.line_index, span);
+        }
+        visit_expression(self, expression, span);
+    }
+}
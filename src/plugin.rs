@@ -0,0 +1,124 @@
+use std::path::Path;
+
+use lang_c::ast::TranslationUnit;
+use lang_c::driver::Parse;
+use lang_c::span::Span;
+use libloading::Library;
+
+use crate::config::RuleSet;
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::error::AnalyzerError;
+use crate::location::LineIndex;
+
+/// Symbol a plugin library exports to hand out its rules:
+///
+/// ```ignore
+/// #[no_mangle]
+/// pub fn nasa_analyzer_rules() -> Vec<Box<dyn Rule>> {
+///     vec![Box::new(MyRule)]
+/// }
+/// ```
+///
+/// Plugins are Rust dynamic libraries (`crate-type = ["cdylib"]`) built against
+/// the same version of this crate and the same compiler as the analyzer.
+pub const PLUGIN_ENTRY_POINT: &[u8] = b"nasa_analyzer_rules";
+
+type RulesConstructor = fn() -> Vec<Box<dyn Rule>>;
+
+/// A project specific rule shipped outside of this crate. Implementations
+/// usually run their own `lang_c::visit::Visit` over the translation unit and
+/// report findings through the context, so they end up in the same pipeline
+/// (baselines, severities, output) as the built-in rules.
+pub trait Rule {
+    /// Name used for the rule in diagnostics, baselines and severity overrides
+    fn name(&self) -> &str;
+
+    fn check(&mut self, unit: &TranslationUnit, context: &mut RuleContext);
+}
+
+/// Collects the findings of a single rule on a single file
+pub struct RuleContext<'a> {
+    rule: &'a str,
+    source: &'a str,
+    line_index: &'a LineIndex,
+    severity: Severity,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl RuleContext<'_> {
+    /// Preprocessed source the translation unit was parsed from
+    pub fn source(&self) -> &str {
+        self.source
+    }
+
+    pub fn report(&mut self, span: &Span, message: &str) {
+        let location = self.line_index.location(span.start);
+        self.diagnostics.push(Diagnostic {
+            rule: self.rule.to_string(),
+            severity: self.severity,
+            file: location.file.to_string(),
+            line: location.line,
+            column: location.column,
+            message: message.to_string(),
+            snippet: None,
+        });
+    }
+}
+
+/// A loaded plugin library and the rules it provides
+pub struct Plugin {
+    rules: Vec<Box<dyn Rule>>,
+    // Declared last so the rules are dropped before their code is unloaded
+    _library: Library,
+}
+
+impl Plugin {
+    pub fn load(path: &Path) -> Result<Plugin, AnalyzerError> {
+        let plugin_error = |error: libloading::Error| AnalyzerError::Plugin {
+            path: path.to_path_buf(),
+            message: error.to_string(),
+        };
+
+        // Loading a library runs its initializers and calling the entry point trusts
+        // its signature, which is why plugins must be built against this crate.
+        unsafe {
+            let library = Library::new(path).map_err(plugin_error)?;
+            let rules = {
+                let constructor: libloading::Symbol<RulesConstructor> =
+                    library.get(PLUGIN_ENTRY_POINT).map_err(plugin_error)?;
+                constructor()
+            };
+            Ok(Plugin {
+                rules,
+                _library: library,
+            })
+        }
+    }
+
+    // Names of the rules the plugin provides
+    pub fn rule_names(&self) -> impl Iterator<Item = &str> {
+        self.rules.iter().map(|rule| rule.name())
+    }
+
+    // Run the rules of the plugin that are enabled in the ruleset
+    pub fn check(&mut self, parse: &Parse, rule_set: &RuleSet) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let line_index = LineIndex::new(&parse.source);
+        for rule in &mut self.rules {
+            let name = rule.name().to_string();
+            if !rule_set.is_enabled(&name) {
+                continue;
+            }
+            let mut context = RuleContext {
+                rule: &name,
+                source: &parse.source,
+                line_index: &line_index,
+                severity: rule_set.severity(&name),
+                diagnostics: Vec::new(),
+            };
+            rule.check(&parse.unit, &mut context);
+            diagnostics.extend(context.diagnostics);
+        }
+        diagnostics
+    }
+}
@@ -0,0 +1,726 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use lang_c::driver::Parse;
+
+use crate::config::RuleSet;
+use crate::diagnostic::Diagnostic;
+use crate::macros::constant_macro_uses;
+
+// Directives whose arguments are evaluated by the preprocessor itself
+const CONDITIONAL_DIRECTIVES: [&str; 4] = ["if", "elif", "ifdef", "ifndef"];
+
+// Pragma prefixes that only namespace the pragma, e.g. "GCC" in "#pragma GCC poison"
+const PRAGMA_NAMESPACES: [&str; 4] = ["GCC", "clang", "STDC", "omp"];
+
+// A preprocessor directive read from the raw source, before lang_c runs the preprocessor
+#[derive(Debug)]
+pub struct Directive {
+    pub line: usize,       // Line the directive starts on
+    pub name: String,      // Directive name, e.g. "include" or "define"
+    pub arguments: String, // Everything after the name, with continuation lines joined
+}
+
+impl Directive {
+    // Name of a #define and the text right after it, its parameter list or its
+    // replacement, e.g. "MAX" and " 3" for `#define MAX 3`
+    pub fn defined_macro(&self) -> Option<(&str, &str)> {
+        if self.name != "define" {
+            return None;
+        }
+        let arguments = self.arguments.as_str();
+        let name_end = arguments
+            .find(|c: char| !c.is_alphanumeric() && c != '_')
+            .unwrap_or(arguments.len());
+        let (name, rest) = arguments.split_at(name_end);
+        if name.is_empty() {
+            return None;
+        }
+        Some((name, rest))
+    }
+
+    // Header named by an #include directive, without the surrounding <> or ""
+    pub fn include_target(&self) -> Option<&str> {
+        if self.name != "include" {
+            return None;
+        }
+        let arguments = self.arguments.as_str();
+        let (open, close) = match arguments.chars().next()? {
+            '<' => ('<', '>'),
+            '"' => ('"', '"'),
+            _ => return None,
+        };
+        let target = arguments.strip_prefix(open)?;
+        let end = target.find(close)?;
+        Some(&target[..end])
+    }
+
+    // Kind of a #pragma directive, its first word or its first two for namespaced pragmas
+    pub fn pragma_kind(&self) -> Option<String> {
+        if self.name != "pragma" {
+            return None;
+        }
+        let mut words = self.arguments.split_whitespace();
+        let first = words.next()?;
+        match words.next() {
+            Some(second) if PRAGMA_NAMESPACES.contains(&first) => {
+                Some(format!("{} {}", first, second))
+            }
+            _ => Some(first.to_string()),
+        }
+    }
+}
+
+// Split an object-like #define into its name and replacement, None for function-like macros
+fn object_like_macro(directive: &Directive) -> Option<(&str, &str)> {
+    let (name, replacement) = directive.defined_macro()?;
+    if replacement.starts_with('(') {
+        return None;
+    }
+    Some((name, replacement.trim()))
+}
+
+// Whether a macro replacement is a single numeric literal, optionally negated or
+// parenthesized, and if so whether it is a floating-point one
+fn numeric_literal_kind(replacement: &str) -> Option<bool> {
+    let mut literal = replacement.trim();
+    while let Some(inner) = literal
+        .strip_prefix('(')
+        .and_then(|inner| inner.strip_suffix(')'))
+    {
+        literal = inner.trim();
+    }
+    let literal = literal.strip_prefix('-').unwrap_or(literal).trim_start();
+
+    let starts_numeric = literal
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_digit() || c == '.');
+    let all_numeric = literal
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '+' || c == '-');
+    if !starts_numeric || !all_numeric {
+        return None;
+    }
+
+    let is_hex = literal.starts_with("0x") || literal.starts_with("0X");
+    let is_float = literal.contains('.')
+        || (!is_hex && (literal.contains(['e', 'E']) || literal.ends_with(['f', 'F'])));
+    Some(is_float)
+}
+
+// Object-like macros defining a plain numeric constant that the file only uses as
+// a value of one type, to initialize or compare with variables of that type, are
+// better expressed as an enum or static const, which carry the type. Macros used in
+// any other way, or tested by conditional directives, which need them to stay
+// visible to the preprocessor, are left alone.
+pub fn check_constant_macros(
+    file: &str,
+    raw_source: &str,
+    directives: &[Directive],
+    parse: &Parse,
+    rule_set: &RuleSet,
+) -> Vec<Diagnostic> {
+    let conditional_identifiers: HashSet<&str> = directives
+        .iter()
+        .filter(|directive| CONDITIONAL_DIRECTIVES.contains(&directive.name.as_str()))
+        .flat_map(|directive| {
+            directive
+                .arguments
+                .split(|c: char| !c.is_alphanumeric() && c != '_')
+        })
+        .collect();
+
+    let constants: Vec<(&Directive, &str, &str)> = directives
+        .iter()
+        .filter_map(|directive| {
+            let (name, replacement) = object_like_macro(directive)?;
+            Some((directive, name, replacement))
+        })
+        .filter(|(_, name, replacement)| {
+            !conditional_identifiers.contains(name) && numeric_literal_kind(replacement).is_some()
+        })
+        .collect();
+    let macros: Vec<(&str, &str)> = constants
+        .iter()
+        .map(|&(_, name, replacement)| (name, replacement))
+        .collect();
+    let uses = constant_macro_uses(file, raw_source, parse, &macros);
+
+    let mut diagnostics = Vec::new();
+    for (directive, name, replacement) in constants {
+        let Some(is_float) = numeric_literal_kind(replacement) else {
+            continue;
+        };
+        // Used at least once, and every time as a value of the same type
+        let Some([Some(type_name), rest @ ..]) = uses.get(name).map(Vec::as_slice) else {
+            continue;
+        };
+        if rest
+            .iter()
+            .any(|other| other.as_deref() != Some(type_name.as_str()))
+        {
+            continue;
+        }
+
+        let suggestion = if is_float {
+            "a static const"
+        } else {
+            "an enum constant"
+        };
+        diagnostics.push(Diagnostic {
+            rule: "prefer_typed_constants".to_string(),
+            severity: rule_set.severity("prefer_typed_constants"),
+            file: file.to_string(),
+            line: directive.line,
+            column: 0,
+            message: format!(
+                "Macro '{}' is only used as a '{}' constant, use {} instead",
+                name, type_name, suggestion
+            ),
+            snippet: None,
+        });
+    }
+
+    diagnostics
+}
+
+// Name and whitespace-normalized definition (parameters and body) of a #define
+fn macro_definition(directive: &Directive) -> Option<(&str, String)> {
+    let (name, definition) = directive.defined_macro()?;
+    let definition: Vec<&str> = definition.split_whitespace().collect();
+    Some((name, definition.join(" ")))
+}
+
+// Keywords that make a macro body a statement rather than an expression
+const STATEMENT_KEYWORDS: [&str; 10] = [
+    "do", "if", "else", "for", "while", "switch", "return", "goto", "break", "continue",
+];
+
+// Name, parameter list and body of a #define, with comments and literals blanked out
+// of the body. The parameter list is None for object-like macros.
+fn macro_parts(directive: &Directive) -> Option<(&str, Option<&str>, String)> {
+    let (name, rest) = directive.defined_macro()?;
+    // Only a parenthesis right after the name starts a parameter list
+    let (parameters, body) = match rest.strip_prefix('(') {
+        Some(rest) => {
+            let end = rest.find(')')?;
+            (Some(&rest[..end]), &rest[end + 1..])
+        }
+        None => (None, rest),
+    };
+    Some((name, parameters, code_only(body)))
+}
+
+pub(crate) fn identifiers(code: &str) -> impl Iterator<Item = &str> {
+    code.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| word.starts_with(|c: char| c.is_alphabetic() || c == '_'))
+}
+
+// Power of Ten rule 8 limits the preprocessor to includes and simple macros. Flag
+// function-like macros holding statements, token pasting, variadic macros and
+// macros that expand to themselves, directly or through other macros of the file.
+pub fn check_complex_macros(
+    file: &str,
+    directives: &[Directive],
+    rule_set: &RuleSet,
+) -> Vec<Diagnostic> {
+    let rule = "restrict_complex_macros";
+    let macros: Vec<(&Directive, &str, Option<&str>, String)> = directives
+        .iter()
+        .filter_map(|directive| {
+            let (name, parameters, body) = macro_parts(directive)?;
+            Some((directive, name, parameters, body))
+        })
+        .collect();
+    // Parameters shadow macros of the same name
+    let references: HashMap<&str, HashSet<&str>> = macros
+        .iter()
+        .map(|(_, name, parameters, body)| {
+            let parameters: HashSet<&str> =
+                parameters.map(identifiers).into_iter().flatten().collect();
+            let referenced = identifiers(body)
+                .filter(|word| !parameters.contains(word))
+                .collect();
+            (*name, referenced)
+        })
+        .collect();
+
+    let mut diagnostics = Vec::new();
+    for (directive, name, parameters, body) in &macros {
+        let mut messages = Vec::new();
+        if parameters.is_some()
+            && (body.contains(';')
+                || body.trim_start().starts_with('{')
+                || identifiers(body).any(|word| STATEMENT_KEYWORDS.contains(&word)))
+        {
+            messages.push(format!(
+                "Function-like macro '{}' contains statements",
+                name
+            ));
+        }
+        if body.contains("##") {
+            messages.push(format!("Macro '{}' uses token pasting", name));
+        }
+        if parameters.is_some_and(|parameters| parameters.contains("...")) {
+            messages.push(format!("Macro '{}' is variadic", name));
+        }
+        if let Some(path) = expansion_cycle(name, &references) {
+            messages.push(format!(
+                "Macro '{}' expands recursively: {}",
+                name,
+                path.join(" -> ")
+            ));
+        }
+
+        for message in messages {
+            diagnostics.push(Diagnostic {
+                rule: rule.to_string(),
+                severity: rule_set.severity(rule),
+                file: file.to_string(),
+                line: directive.line,
+                column: 0,
+                message,
+                snippet: Some(format!("#define {}", directive.arguments)),
+            });
+        }
+    }
+
+    diagnostics
+}
+
+// Shortest chain of macro references leading from a macro back to itself, if any
+fn expansion_cycle<'a>(
+    name: &'a str,
+    references: &HashMap<&'a str, HashSet<&'a str>>,
+) -> Option<Vec<&'a str>> {
+    let mut previous: HashMap<&str, &str> = HashMap::new();
+    let mut queue = VecDeque::from([name]);
+    while let Some(current) = queue.pop_front() {
+        let mut next: Vec<&str> = references.get(current)?.iter().copied().collect();
+        next.sort_unstable();
+        for reference in next {
+            if reference == name {
+                let mut path = vec![name];
+                let mut step = current;
+                while step != name {
+                    path.push(step);
+                    step = previous[step];
+                }
+                path.push(name);
+                path[1..path.len() - 1].reverse();
+                return Some(path);
+            }
+            if references.contains_key(reference) && !previous.contains_key(reference) {
+                previous.insert(reference, current);
+                queue.push_back(reference);
+            }
+        }
+    }
+    None
+}
+
+// Flag every #undef and every #define of an already defined macro with a different definition
+pub fn check_undef_and_redefinition(
+    file: &str,
+    directives: &[Directive],
+    rule_set: &RuleSet,
+) -> Vec<Diagnostic> {
+    let rule = "restrict_undef_and_redefinition";
+    let mut defined: HashMap<&str, (String, usize)> = HashMap::new();
+    let mut diagnostics = Vec::new();
+
+    for directive in directives {
+        let message = if directive.name == "undef" {
+            format!("'#undef {}' found", directive.arguments)
+        } else if let Some((name, definition)) = macro_definition(directive) {
+            match defined.insert(name, (definition.clone(), directive.line)) {
+                Some((previous, line)) if previous != definition => format!(
+                    "Macro '{}' redefined with a different expansion, previously defined at line {}",
+                    name, line
+                ),
+                _ => continue,
+            }
+        } else {
+            continue;
+        };
+
+        diagnostics.push(Diagnostic {
+            rule: rule.to_string(),
+            severity: rule_set.severity(rule),
+            file: file.to_string(),
+            line: directive.line,
+            column: 0,
+            message,
+            snippet: None,
+        });
+    }
+
+    diagnostics
+}
+
+// Line of the first declaration or definition, i.e. the first line holding code
+// that is neither a comment nor part of a preprocessor directive
+fn first_code_line(source: &str) -> Option<usize> {
+    let mut in_block_comment = false;
+    let mut in_directive = false;
+
+    for (index, line) in source.lines().enumerate() {
+        let mut rest = line;
+        let continues = line.trim_end().ends_with('\\');
+
+        if in_directive {
+            in_directive = continues;
+            continue;
+        }
+
+        loop {
+            if in_block_comment {
+                let Some(end) = rest.find("*/") else {
+                    break;
+                };
+                in_block_comment = false;
+                rest = &rest[end + 2..];
+                continue;
+            }
+
+            rest = rest.trim_start();
+            if rest.is_empty() || rest.starts_with("//") {
+                break;
+            }
+            if let Some(comment) = rest.strip_prefix("/*") {
+                in_block_comment = true;
+                rest = comment;
+                continue;
+            }
+            if rest.starts_with('#') {
+                in_directive = continues;
+                break;
+            }
+            return Some(index + 1);
+        }
+    }
+
+    None
+}
+
+// Flag #include directives after the first declaration and, when enabled, the first
+// include out of the order own header, project headers, system headers
+pub fn check_include_placement(
+    file: &str,
+    raw_source: &str,
+    directives: &[Directive],
+    rule_set: &RuleSet,
+) -> Vec<Diagnostic> {
+    let rule = "restrict_include_placement";
+    let first_code_line = first_code_line(raw_source).unwrap_or(usize::MAX);
+    let own_header = std::path::Path::new(file)
+        .with_extension("h")
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string());
+
+    let mut diagnostics = Vec::new();
+    let mut report = |line: usize, message: String| {
+        diagnostics.push(Diagnostic {
+            rule: rule.to_string(),
+            severity: rule_set.severity(rule),
+            file: file.to_string(),
+            line,
+            column: 0,
+            message,
+            snippet: None,
+        });
+    };
+
+    let mut highest_group = 0;
+    let mut order_reported = false;
+    for directive in directives {
+        let Some(header) = directive.include_target() else {
+            continue;
+        };
+
+        if directive.line > first_code_line {
+            report(
+                directive.line,
+                format!("'#include' of '{}' after the first declaration", header),
+            );
+        }
+
+        if !rule_set.enforce_include_order || order_reported {
+            continue;
+        }
+        let group = if directive.arguments.starts_with('<') {
+            2
+        } else if own_header.as_deref() == header.rsplit('/').next() {
+            0
+        } else {
+            1
+        };
+        if group < highest_group {
+            report(
+                directive.line,
+                format!(
+                    "'#include' of '{}' out of order, expected own header, then project headers, then system headers",
+                    header
+                ),
+            );
+            order_reported = true;
+        }
+        highest_group = highest_group.max(group);
+    }
+
+    diagnostics
+}
+
+// Flag include paths that depend on the layout or platform of the build machine:
+// absolute paths, ".." segments and backslash separators
+pub fn check_include_paths(
+    file: &str,
+    directives: &[Directive],
+    rule_set: &RuleSet,
+) -> Vec<Diagnostic> {
+    let rule = "restrict_include_paths";
+    let mut diagnostics = Vec::new();
+
+    for directive in directives {
+        let Some(header) = directive.include_target() else {
+            continue;
+        };
+        let problem = if header.contains('\\') {
+            "uses backslash separators"
+        } else if header.starts_with('/') {
+            "is an absolute path"
+        } else if header.split('/').any(|segment| segment == "..") {
+            "contains a '..' segment"
+        } else {
+            continue;
+        };
+
+        diagnostics.push(Diagnostic {
+            rule: rule.to_string(),
+            severity: rule_set.severity(rule),
+            file: file.to_string(),
+            line: directive.line,
+            column: 0,
+            message: format!("Include path '{}' {}", header, problem),
+            snippet: None,
+        });
+    }
+
+    diagnostics
+}
+
+// Pragmas silently change how code is compiled, so only allow-listed kinds are accepted
+pub fn check_pragmas(file: &str, directives: &[Directive], rule_set: &RuleSet) -> Vec<Diagnostic> {
+    let rule = "restrict_pragmas";
+    let mut diagnostics = Vec::new();
+
+    for directive in directives {
+        let Some(kind) = directive.pragma_kind() else {
+            continue;
+        };
+        if rule_set.allowed_pragmas.contains(&kind) {
+            continue;
+        }
+        diagnostics.push(Diagnostic {
+            rule: rule.to_string(),
+            severity: rule_set.severity(rule),
+            file: file.to_string(),
+            line: directive.line,
+            column: 0,
+            message: format!("'#pragma {}' is not on the pragma allow-list", kind),
+            snippet: Some(format!("#pragma {}", directive.arguments)),
+        });
+    }
+
+    diagnostics
+}
+
+// Source with comments and string and character literals blanked out with spaces,
+// line breaks kept, so every byte stays at its offset in the source
+pub(crate) fn code_only(source: &str) -> String {
+    let mut code = String::with_capacity(source.len());
+    let blank = |code: &mut String, c: char| match c {
+        '\n' => code.push('\n'),
+        _ => code.extend(std::iter::repeat(' ').take(c.len_utf8())),
+    };
+    let mut chars = source.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'/') => {
+                blank(&mut code, c);
+                while let Some(next) = chars.next_if(|&next| next != '\n') {
+                    blank(&mut code, next);
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                blank(&mut code, c);
+                if let Some(star) = chars.next() {
+                    blank(&mut code, star);
+                }
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    blank(&mut code, next);
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            '"' | '\'' => {
+                blank(&mut code, c);
+                while let Some(next) = chars.next() {
+                    blank(&mut code, next);
+                    if next == '\\' {
+                        if let Some(escaped) = chars.next() {
+                            blank(&mut code, escaped);
+                        }
+                    } else if next == c || next == '\n' {
+                        break;
+                    }
+                }
+            }
+            _ => code.push(c),
+        }
+    }
+
+    code
+}
+
+// GNU computed gotos (`goto *target`) and label addresses (`&&label`) make the
+// control flow graph unknowable. lang_c cannot parse either, so they are found in
+// the source text, where `&&` only takes a label address when used as a unary operator.
+pub fn check_computed_goto(file: &str, raw_source: &str, rule_set: &RuleSet) -> Vec<Diagnostic> {
+    let rule = "restrict_computed_goto";
+    let code = code_only(raw_source);
+    let bytes = code.as_bytes();
+    let is_identifier = |byte: u8| byte.is_ascii_alphanumeric() || byte == b'_';
+    let previous_token = |end: usize| {
+        let text = code[..end].trim_end();
+        match text.char_indices().last() {
+            Some((_, c)) if c.is_alphanumeric() || c == '_' => {
+                let start = text.rfind(|c: char| !c.is_alphanumeric() && c != '_');
+                &text[start.map_or(0, |start| start + 1)..]
+            }
+            Some((index, _)) => &text[index..],
+            None => "",
+        }
+    };
+
+    // Offsets in the blanked code are offsets in the source
+    let mut diagnostics = Vec::new();
+    let mut report = |offset: usize, message: &str| {
+        let line = raw_source[..offset].matches('\n').count() + 1;
+        let line_start = raw_source[..offset]
+            .rfind('\n')
+            .map_or(0, |start| start + 1);
+        diagnostics.push(Diagnostic {
+            rule: rule.to_string(),
+            severity: rule_set.severity(rule),
+            file: file.to_string(),
+            line,
+            column: offset - line_start + 1,
+            message: message.to_string(),
+            snippet: raw_source
+                .lines()
+                .nth(line - 1)
+                .map(|source_line| source_line.trim().to_string()),
+        });
+    };
+
+    for (offset, _) in code.match_indices("goto") {
+        let starts_word = offset == 0 || !is_identifier(bytes[offset - 1]);
+        if starts_word && code[offset + 4..].trim_start().starts_with('*') {
+            report(offset, "Computed 'goto *' found");
+        }
+    }
+
+    for (offset, _) in code.match_indices("&&") {
+        let unary = matches!(
+            previous_token(offset),
+            "" | "=" | "(" | "," | "{" | "[" | "?" | ":" | "return"
+        );
+        let takes_label = code[offset + 2..]
+            .trim_start()
+            .bytes()
+            .next()
+            .is_some_and(|byte| byte.is_ascii_alphabetic() || byte == b'_');
+        if unary && takes_label {
+            report(offset, "Label address '&&' found");
+        }
+    }
+
+    diagnostics
+}
+
+// Directives of a source file. Lines are told apart as directives on the source with
+// comments blanked out, so commented-out directives, e.g. inside a /* ... */ block,
+// are left out, while the arguments are taken from the source as written.
+pub fn parse_directives(source: &str) -> Vec<Directive> {
+    let mut directives = Vec::new();
+    let code = code_only(source);
+    let mut lines = source.lines().zip(code.lines()).enumerate();
+
+    while let Some((index, (line, code_line))) = lines.next() {
+        // code_only keeps every byte at its offset, so the '#' is at the same place
+        let Some(hash) = code_line
+            .find(|c: char| !c.is_whitespace())
+            .filter(|&start| code_line[start..].starts_with('#'))
+        else {
+            continue;
+        };
+        let rest = &line[hash + 1..];
+
+        // Join backslash continued lines into a single directive
+        let mut text = rest.to_string();
+        while text.ends_with('\\') {
+            text.pop();
+            match lines.next() {
+                Some((_, (next, _))) => text.push_str(next),
+                None => break,
+            }
+        }
+
+        let text = text.trim_start();
+        let name_end = text
+            .find(|c: char| !c.is_alphanumeric() && c != '_')
+            .unwrap_or(text.len());
+        directives.push(Directive {
+            line: index + 1,
+            name: text[..name_end].to_string(),
+            arguments: text[name_end..].trim().to_string(),
+        });
+    }
+
+    directives
+}
+
+pub fn check_banned_headers(
+    file: &str,
+    directives: &[Directive],
+    rule_set: &RuleSet,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for directive in directives {
+        let Some(header) = directive.include_target() else {
+            continue;
+        };
+        let banned = rule_set.banned_headers.iter().any(|banned_header| {
+            header == banned_header || header.ends_with(&format!("/{}", banned_header))
+        });
+        if banned {
+            diagnostics.push(Diagnostic {
+                rule: "restrict_banned_headers".to_string(),
+                severity: rule_set.severity("restrict_banned_headers"),
+                file: file.to_string(),
+                line: directive.line,
+                column: 0,
+                message: format!("Banned header '{}' included", header),
+                snippet: None,
+            });
+        }
+    }
+
+    diagnostics
+}
@@ -0,0 +1,773 @@
+use crate::diagnostic::Severity;
+
+// Static description of a rule, used for listing and explaining rules and for
+// default severities. `name` is the rule's key in the ruleset.
+#[derive(Debug)]
+pub struct RuleMetadata {
+    pub name: &'static str,
+    pub id: &'static str,
+    pub category: &'static str,
+    pub power_of_ten: Option<u8>, // Power of Ten rule number the check enforces, if any
+    pub default_severity: Severity,
+    pub summary: &'static str,
+    pub rationale: &'static str,
+    pub non_compliant: &'static str, // Example code the rule reports
+    pub compliant: &'static str,     // The same example rewritten to pass
+    pub options: &'static [&'static str], // Ruleset keys that tune the rule
+}
+
+// Titles of the ten Power of Ten rules, indexed by rule number minus one
+pub const POWER_OF_TEN_TITLES: [&str; 10] = [
+    "Restrict to simple control flow",
+    "Give all loops a fixed upper bound",
+    "No dynamic memory allocation after initialization",
+    "Limit functions to a single printed page",
+    "Use at least two assertions per function",
+    "Declare data at the smallest possible scope",
+    "Check return values and validate parameters",
+    "Limit the use of the preprocessor",
+    "Restrict the use of pointers",
+    "Compile with all warnings and use static analyzers",
+];
+
+pub const RULES: &[RuleMetadata] = &[
+    RuleMetadata {
+        name: "restrict_goto",
+        id: "P10-R1-goto",
+        category: "control-flow",
+        power_of_ten: Some(1),
+        default_severity: Severity::Error,
+        summary: "Do not use goto",
+        rationale: "Simple control flow makes code easier to verify and analyze; goto creates arbitrary edges in the control flow graph.",
+        non_compliant: "if (err) goto cleanup;",
+        compliant: "if (err) {\n    cleanup();\n    return;\n}",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "restrict_setjmp",
+        id: "P10-R1-setjmp",
+        category: "control-flow",
+        power_of_ten: Some(1),
+        default_severity: Severity::Error,
+        summary: "Do not call setjmp",
+        rationale: "setjmp/longjmp transfer control across function boundaries, bypassing the normal call structure that analysis relies on.",
+        non_compliant: "if (setjmp(env) != 0) { recover(); }",
+        compliant: "status = run_step();\nif (status != OK) { recover(); }",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "restrict_longjmp",
+        id: "P10-R1-longjmp",
+        category: "control-flow",
+        power_of_ten: Some(1),
+        default_severity: Severity::Error,
+        summary: "Do not call longjmp",
+        rationale: "longjmp unwinds the stack without returning through the intermediate functions, skipping their cleanup and checks.",
+        non_compliant: "longjmp(env, 1);",
+        compliant: "return ERROR_ABORTED;",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "restrict_recursion",
+        id: "P10-R1-recursion",
+        category: "control-flow",
+        power_of_ten: Some(1),
+        default_severity: Severity::Error,
+        summary: "Do not use recursion",
+        rationale: "Without recursion the call graph is acyclic, so stack usage can be bounded. Cycles through several functions of a file are reported with the calls forming them. Allow-listed functions must document their depth with an '@recursion max_depth=N measure=param' comment and recurse on a strictly reduced parameter.",
+        non_compliant: "int fact(int n) { return n <= 1 ? 1 : n * fact(n - 1); }",
+        compliant: "int fact(int n) {\n    int result = 1;\n    for (int i = 2; i <= MAX_N && i <= n; i++) { result *= i; }\n    return result;\n}",
+        options: &["allowed_recursive_functions"],
+    },
+    RuleMetadata {
+        name: "restrict_unstructured_goto",
+        id: "P10-R1-unstructured-goto",
+        category: "control-flow",
+        power_of_ten: Some(1),
+        default_severity: Severity::Error,
+        summary: "Where goto is permitted, forbid backward jumps, jumps into blocks and jumps past initializations",
+        rationale: "These jumps defeat structured reasoning about loops, scopes and variable initialization. Only checked when restrict_goto is disabled.",
+        non_compliant: "retry:\n    status = send();\n    if (status != OK) goto retry;",
+        compliant: "for (int i = 0; i < MAX_RETRIES && status != OK; i++) {\n    status = send();\n}",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "restrict_computed_goto",
+        id: "P10-R1-computed-goto",
+        category: "control-flow",
+        power_of_ten: Some(1),
+        default_severity: Severity::Error,
+        summary: "Do not use GNU computed gotos or label addresses",
+        rationale: "A jump through a computed address can reach any label whose address was taken, which defeats every analysis based on the control flow graph.",
+        non_compliant: "static void *table[] = { &&idle, &&run };\ngoto *table[state];",
+        compliant: "switch (state) {\ncase IDLE: idle(); break;\ncase RUN: run(); break;\ndefault: fault_report(); break;\n}",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "fixed_loop_bounds",
+        id: "P10-R2-loop-bounds",
+        category: "loops",
+        power_of_ten: Some(2),
+        default_severity: Severity::Error,
+        summary: "Loops must have a fixed upper bound",
+        rationale: "A statically provable iteration bound prevents runaway code and lets tools check that the loop terminates. A for loop has to compare its counter against a constant and step the counter towards it. In a condition such as `i < MAX && !done` one bounded conjunct is enough, and the body must not reset the counter or move it away from the bound. Loops whose bound cannot be proven pass with a `/* loop-bound: N */` comment above them declaring it.",
+        non_compliant: "while (node != NULL) { node = node->next; }",
+        compliant: "while (count < MAX_NODES) { count++; }",
+        options: &["loop_bound_marker"],
+    },
+    RuleMetadata {
+        name: "restrict_float_loop_counters",
+        id: "P10-R2-float-loop-counter",
+        category: "loops",
+        power_of_ten: Some(2),
+        default_severity: Severity::Error,
+        summary: "Loops must not be controlled by floating-point counters",
+        rationale: "Rounding makes the number of iterations of a floating-point controlled loop platform dependent, so it never counts as a fixed bound.",
+        non_compliant: "for (float t = 0.0f; t < 1.0f; t += 0.1f) { step(t); }",
+        compliant: "for (int i = 0; i < 10; i++) { step(i * 0.1f); }",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "restrict_loop_counter_modification",
+        id: "P10-R2-loop-counter-modification",
+        category: "loops",
+        power_of_ten: Some(2),
+        default_severity: Severity::Error,
+        summary: "A for-loop counter must not be modified in the loop body",
+        rationale: "Modifying the counter inside the body invalidates the bound stated in the loop header (MISRA C 14.2).",
+        non_compliant: "for (int i = 0; i < 10; i++) { if (skip) { i++; } }",
+        compliant: "for (int i = 0; i < 10; i++) { if (skip) { continue; } }",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "restrict_heap_allocation",
+        id: "P10-R3-heap-allocation",
+        category: "memory",
+        power_of_ten: Some(3),
+        default_severity: Severity::Error,
+        summary: "Do not use dynamic memory allocation after initialization",
+        rationale: "Allocators have unpredictable timing and failure modes, and heap use invites leaks, use after free and fragmentation.",
+        non_compliant: "char *buffer = malloc(size);",
+        compliant: "static char buffer[BUFFER_SIZE];",
+        options: &["additional_heap_functions", "excluded_heap_functions"],
+    },
+    RuleMetadata {
+        name: "restrict_variable_length_arrays",
+        id: "P10-R3-variable-length-arrays",
+        category: "memory",
+        power_of_ten: Some(3),
+        default_severity: Severity::Error,
+        summary: "Do not declare variable-length arrays",
+        rationale: "A variable-length array is dynamic allocation on the stack: its size is only known at run time, so the stack use of the function cannot be bounded and an unexpected size overflows the stack without any error to check.",
+        non_compliant: "void filter(size_t count) {\n    int32_t samples[count];\n}",
+        compliant: "enum { MAX_SAMPLES = 64 };\n\nvoid filter(size_t count) {\n    int32_t samples[MAX_SAMPLES];\n}",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "restrict_alloca",
+        id: "P10-R3-alloca",
+        category: "memory",
+        power_of_ten: Some(3),
+        default_severity: Severity::Error,
+        summary: "Do not allocate memory on the stack at run time",
+        rationale: "alloca is dynamic allocation by another name: it defeats stack-bound analysis, cannot report failure, and a large size overflows the stack silently. It is treated like heap allocation.",
+        non_compliant: "char *line = alloca(length + 1);",
+        compliant: "static char line[MAX_LINE_LENGTH + 1];",
+        options: &["additional_stack_allocation_functions"],
+    },
+    RuleMetadata {
+        name: "restrict_function_size",
+        id: "P10-R4-function-size",
+        category: "functions",
+        power_of_ten: Some(4),
+        default_severity: Severity::Error,
+        summary: "Functions must fit on a single printed page",
+        rationale: "Each function should be a logical unit that can be understood and verified as a whole.",
+        non_compliant: "void process(void) {\n    /* more than max_function_lines lines */\n}",
+        compliant: "void process(void) {\n    read_inputs();\n    update_state();\n    write_outputs();\n}",
+        options: &["max_function_lines", "count_blank_lines", "count_comment_lines"],
+    },
+    RuleMetadata {
+        name: "restrict_parameter_count",
+        id: "CS-parameter-count",
+        category: "functions",
+        power_of_ten: None,
+        default_severity: Severity::Warning,
+        summary: "Limit the number of parameters of a function",
+        rationale: "Long parameter lists are easy to call with arguments in the wrong order, and usually mean the function does too much or its inputs belong together in a struct.",
+        non_compliant: "void set_attitude(double roll, double pitch, double yaw, double rate_x, double rate_y, double rate_z, int frame);",
+        compliant: "void set_attitude(const struct attitude *target, int frame);",
+        options: &["max_parameters"],
+    },
+    RuleMetadata {
+        name: "require_prototypes",
+        id: "CS-prototypes",
+        category: "functions",
+        power_of_ten: None,
+        default_severity: Severity::Error,
+        summary: "Declare and define every function with a full prototype",
+        rationale: "Without a prototype the compiler does not check the number or types of the arguments of a call, and applies the default argument promotions instead of converting them, so a mismatched call silently passes garbage. Empty parentheses declare no prototype either, a function without parameters takes (void).",
+        non_compliant: "int checksum(buffer, length)\n    const uint8_t *buffer;\n    size_t length;\n{\n    ...\n}",
+        compliant: "int checksum(const uint8_t *buffer, size_t length)\n{\n    ...\n}",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "require_assertions",
+        id: "P10-R5-assertion-density",
+        category: "functions",
+        power_of_ten: Some(5),
+        default_severity: Severity::Error,
+        summary: "Functions must contain at least two assertions",
+        rationale: "Assertions check for anomalous conditions that should never happen in real executions; the higher their density, the more likely a defect is caught close to its cause. Calls are counted in the source before preprocessing, so assertion macros are recognized by name.",
+        non_compliant: "void set_rate(int hz) {\n    rate = hz;\n    reconfigure();\n}",
+        compliant: "void set_rate(int hz) {\n    assert(hz > 0);\n    assert(hz <= MAX_RATE);\n    rate = hz;\n    reconfigure();\n}",
+        options: &["min_assertions", "assertion_macros", "min_assertion_function_lines"],
+    },
+    RuleMetadata {
+        name: "restrict_assertion_side_effects",
+        id: "P10-R5-assertion-side-effects",
+        category: "functions",
+        power_of_ten: Some(5),
+        default_severity: Severity::Error,
+        summary: "Assertions must be free of side effects",
+        rationale: "Assertions may be compiled out of flight builds, so a condition that changes state makes the flight software behave differently from the software that was tested. Calls count as side effects unless the function is listed as pure.",
+        non_compliant: "assert(queue_pop(&queue) == OK);",
+        compliant: "int status = queue_pop(&queue);\nassert(status == OK);",
+        options: &["pure_functions", "assertion_macros"],
+    },
+    RuleMetadata {
+        name: "require_static_functions",
+        id: "P10-R6-static-functions",
+        category: "scope",
+        power_of_ten: Some(6),
+        default_severity: Severity::Error,
+        summary: "Functions only used in their own file must be static",
+        rationale: "Declaring data and functions at the smallest possible scope keeps the global namespace clean and limits what can depend on them, and lets link-time analysis see every caller of a function. Functions declared in a project header, called from another analyzed file or listed as exported keep external linkage.",
+        non_compliant: "int helper(int x) { return x * 2; }",
+        compliant: "static int helper(int x) { return x * 2; }",
+        options: &["exported_functions"],
+    },
+    RuleMetadata {
+        name: "require_smallest_scope",
+        id: "P10-R6-smallest-scope",
+        category: "scope",
+        power_of_ten: Some(6),
+        default_severity: Severity::Error,
+        summary: "Declare data at the smallest possible scope",
+        rationale: "Data that is not in scope cannot be referenced or corrupted, and the fewer statements can touch a value, the fewer places there are to look when it is wrong. A local only one block uses belongs in that block. Locals initialized with more than a constant are not reported, since moving them would change when the initializer runs.",
+        non_compliant: "void poll(void) {\n    int status;\n    if (ready()) {\n        status = read_status();\n        log_status(status);\n    }\n}",
+        compliant: "void poll(void) {\n    if (ready()) {\n        int status = read_status();\n        log_status(status);\n    }\n}",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "restrict_global_mutable_data",
+        id: "P10-R6-global-mutable-data",
+        category: "scope",
+        power_of_ten: Some(6),
+        default_severity: Severity::Error,
+        summary: "Do not define non-const variables at file scope",
+        rationale: "A mutable global is visible to every function, so any of them may be what changed it, and a function reading it behaves differently depending on what ran before. Such functions cannot be unit tested without resetting hidden state between tests. Static variables can be allowed, since they are at least private to one file, and the run summary counts the findings of each file.",
+        non_compliant: "int retries;\n\nvoid poll(void) {\n    retries++;\n}",
+        compliant: "static const int max_retries = 3;\n\nvoid poll(struct poller *poller) {\n    poller->retries++;\n}",
+        options: &["allow_static_globals", "global_allowed_patterns"],
+    },
+    RuleMetadata {
+        name: "restrict_unused_variables",
+        id: "CS-unused-variables",
+        category: "scope",
+        power_of_ten: None,
+        default_severity: Severity::Warning,
+        summary: "Remove parameters and local variables that are never read",
+        rationale: "A variable that is written but never read is either dead code or a sign that the value was meant to be used and is not, such as a status that is never checked. Parameters a required signature forces on a function are marked with `(void)x;` or an allowed name.",
+        non_compliant: "void on_timer(int id) {\n    int status;\n    status = poll_sensor();\n}",
+        compliant: "void on_timer(int id) {\n    (void)id;\n    if (poll_sensor() != OK) {\n        fault_report(FAULT_SENSOR);\n    }\n}",
+        options: &["unused_allowed_patterns"],
+    },
+    RuleMetadata {
+        name: "restrict_shadowing",
+        id: "CS-shadowing",
+        category: "scope",
+        power_of_ten: None,
+        default_severity: Severity::Error,
+        summary: "Do not reuse the name of a variable from an enclosing scope",
+        rationale: "A declaration that hides another variable of the same name makes every later use ambiguous to the reader, and in a long function an assignment meant for the outer variable silently goes to the inner one.",
+        non_compliant: "int status = check_bus();\nif (status == OK) {\n    int status = check_power();\n}\nreport(status);",
+        compliant: "int bus_status = check_bus();\nif (bus_status == OK) {\n    int power_status = check_power();\n}\nreport(bus_status);",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "check_return_value",
+        id: "P10-R7-return-value",
+        category: "functions",
+        power_of_ten: Some(7),
+        default_severity: Severity::Error,
+        summary: "The return value of non-void functions must be used",
+        rationale: "Ignoring a return value silently drops error reports; explicitly casting to void documents that it is intentionally ignored.",
+        non_compliant: "write_telemetry(packet);",
+        compliant: "int status = write_telemetry(packet);",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "require_parameter_validation",
+        id: "P10-R7-parameter-validation",
+        category: "functions",
+        power_of_ten: Some(7),
+        default_severity: Severity::Warning,
+        summary: "Pointer and array parameters must be checked before they are used",
+        rationale: "Each function must validate the parameters it is passed, so a null or invalid pointer is caught at the boundary instead of corrupting state. A parameter counts as checked once it appears in an if condition or an assertion, statement by statement through the function body.",
+        non_compliant: "void store(struct packet *packet) {\n    buffer[count++] = *packet;\n}",
+        compliant: "void store(struct packet *packet) {\n    assert(packet != NULL);\n    buffer[count++] = *packet;\n}",
+        options: &["assertion_macros"],
+    },
+    RuleMetadata {
+        name: "require_state_fault_handling",
+        id: "CS-state-fault-handling",
+        category: "control-flow",
+        power_of_ten: None,
+        default_severity: Severity::Error,
+        summary: "State machine switches must report unexpected states to a fault handler",
+        rationale: "A corrupted or unexpected state has to reach fault detection, isolation and recovery rather than being silently ignored.",
+        non_compliant: "switch (mode) {\ncase MODE_SAFE: safe(); break;\ndefault: break;\n}",
+        compliant: "switch (mode) {\ncase MODE_SAFE: safe(); break;\ndefault: fault_report(FAULT_BAD_MODE); break;\n}",
+        options: &["state_types", "fault_handlers"],
+    },
+    RuleMetadata {
+        name: "require_switch_default",
+        id: "CS-switch-default",
+        category: "control-flow",
+        power_of_ten: None,
+        default_severity: Severity::Error,
+        summary: "Every switch must have a default label",
+        rationale: "Without a default, a value no case expected, such as a corrupted command code, passes through the switch unnoticed. A default shows the author considered every other value; the strict variant makes it handle them.",
+        non_compliant: "switch (command) {\ncase CMD_ARM: arm(); break;\ncase CMD_SAFE: safe(); break;\n}",
+        compliant: "switch (command) {\ncase CMD_ARM: arm(); break;\ncase CMD_SAFE: safe(); break;\ndefault: fault_report(FAULT_BAD_COMMAND); break;\n}",
+        options: &["strict_switch_default", "fault_handlers", "assertion_macros"],
+    },
+    RuleMetadata {
+        name: "restrict_implicit_fallthrough",
+        id: "CS-implicit-fallthrough",
+        category: "control-flow",
+        power_of_ten: None,
+        default_severity: Severity::Error,
+        summary: "End every case with a jump or mark the fallthrough",
+        rationale: "A missing break makes one command run the handler of the next, which in a command dispatch table executes an action nobody sent. Falling through on purpose has to be marked so readers and reviewers can tell it from a mistake. Found on the control-flow graph, so a case ending in an if whose branches both return does not fall through.",
+        non_compliant: "case CMD_ARM:\n    arm();\ncase CMD_FIRE:\n    fire();\n    break;",
+        compliant: "case CMD_ARM:\n    arm();\n    /* fallthrough */\ncase CMD_FIRE:\n    fire();\n    break;",
+        options: &["fallthrough_marker"],
+    },
+    RuleMetadata {
+        name: "require_exhaustive_enum_switch",
+        id: "CS-enum-switch",
+        category: "control-flow",
+        power_of_ten: None,
+        default_severity: Severity::Error,
+        summary: "Switches over an enum must handle its enumerators and only those",
+        rationale: "When an enumerator is added, every switch over the enum has to decide what to do with it; without a default the compiler gives no sign of the switches that were missed. A case label from another enum or a bare number compares against a value the variable is not meant to hold.",
+        non_compliant: "enum mode { MODE_SAFE, MODE_NOMINAL, MODE_BURN };\n\nswitch (mode) {\ncase MODE_SAFE: safe(); break;\ncase MODE_NOMINAL: nominal(); break;\n}",
+        compliant: "enum mode { MODE_SAFE, MODE_NOMINAL, MODE_BURN };\n\nswitch (mode) {\ncase MODE_SAFE: safe(); break;\ncase MODE_NOMINAL: nominal(); break;\ncase MODE_BURN: burn(); break;\n}",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "restrict_unreachable_code",
+        id: "CS-unreachable-code",
+        category: "control-flow",
+        power_of_ten: None,
+        default_severity: Severity::Error,
+        summary: "Do not leave code that can never execute",
+        rationale: "Dead code is never tested, misleads readers about what the function does and often hides a logic error, such as a misplaced return. Each unreachable region is reported at its first statement.",
+        non_compliant: "return status;\nlog_status(status);",
+        compliant: "log_status(status);\nreturn status;",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "restrict_uninitialized_reads",
+        id: "CS-uninitialized-reads",
+        category: "data-flow",
+        power_of_ten: None,
+        default_severity: Severity::Error,
+        summary: "Assign local variables on every path before reading them",
+        rationale: "An uninitialized local holds whatever was left on the stack, so the code behaves differently from run to run and the defect often hides until optimization or a change in call order exposes it. The check follows the function's control-flow graph and counts taking a variable's address as assigning it; arrays and static locals are not checked.",
+        non_compliant: "int mode;\nif (armed) {\n    mode = MODE_FIRE;\n}\nset_mode(mode);",
+        compliant: "int mode = MODE_SAFE;\nif (armed) {\n    mode = MODE_FIRE;\n}\nset_mode(mode);",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "require_braces",
+        id: "CS-braces",
+        category: "control-flow",
+        power_of_ten: None,
+        default_severity: Severity::Error,
+        summary: "Enclose the bodies of if, else and loops in braces",
+        rationale: "A statement added next to an unbraced body looks like part of it but runs unconditionally, and a macro expanding to several statements only has its first one guarded.",
+        non_compliant: "if (armed)\n    fire();",
+        compliant: "if (armed) {\n    fire();\n}",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "restrict_comma_operator",
+        id: "CS-comma-operator",
+        category: "control-flow",
+        power_of_ten: None,
+        default_severity: Severity::Error,
+        summary: "Do not use the comma operator outside for statement clauses",
+        rationale: "A comma expression evaluates its operands in turn and discards all but the last value, which packs several side effects into one expression and is easily mistaken for an argument list.",
+        non_compliant: "if (count++, count > LIMIT) {\n    reset();\n}",
+        compliant: "count++;\nif (count > LIMIT) {\n    reset();\n}",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "restrict_nested_conditionals",
+        id: "CS-nested-conditionals",
+        category: "control-flow",
+        power_of_ten: None,
+        default_severity: Severity::Warning,
+        summary: "Do not nest conditional expressions deeply",
+        rationale: "A chain of ?: operators hides a decision tree in one expression, where the branch each value comes from has to be worked out from operator precedence. An if statement or a lookup table states it plainly.",
+        non_compliant: "size = (mode == FAST) ? 64 : (mode == SLOW) ? 16 : (mode == IDLE) ? 0 : 32;",
+        compliant: "if (mode == FAST) {\n    size = 64;\n} else if (mode == SLOW) {\n    size = 16;\n} else if (mode == IDLE) {\n    size = 0;\n} else {\n    size = 32;\n}",
+        options: &["max_conditional_nesting"],
+    },
+    RuleMetadata {
+        name: "prefer_typed_constants",
+        id: "P10-R8-typed-constants",
+        category: "preprocessor",
+        power_of_ten: Some(8),
+        default_severity: Severity::Advisory,
+        summary: "Prefer an enum or static const over a #define constant used as one type",
+        rationale: "Use of the preprocessor should be limited; typed constants are checked by the compiler and visible to debuggers.",
+        non_compliant: "#define MAX_RETRIES 3\nint retries = MAX_RETRIES;",
+        compliant: "enum { MAX_RETRIES = 3 };\nint retries = MAX_RETRIES;",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "restrict_complex_macros",
+        id: "P10-R8-complex-macros",
+        category: "preprocessor",
+        power_of_ten: Some(8),
+        default_severity: Severity::Error,
+        summary: "Macros must be simple: no statements, token pasting, variadic parameters or recursion",
+        rationale: "The preprocessor obscures what the compiler and analysis tools see. Statement macros, token pasting, variadic and recursive macros are hard to read and impossible to check without expanding them.",
+        non_compliant: "#define SWAP(a, b) { int t = a; a = b; b = t; }\n#define FIELD(n) field_##n",
+        compliant: "static inline void swap(int *a, int *b) {\n    int t = *a;\n    *a = *b;\n    *b = t;\n}",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "restrict_undef_and_redefinition",
+        id: "P10-R8-undef-redefinition",
+        category: "preprocessor",
+        power_of_ten: Some(8),
+        default_severity: Severity::Error,
+        summary: "Do not #undef macros or redefine them differently",
+        rationale: "A macro whose meaning changes part way through a file makes the code depend on the order of definitions (MISRA C 20.5).",
+        non_compliant: "#define LIMIT 10\n#undef LIMIT\n#define LIMIT 20",
+        compliant: "#define LIMIT 10",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "restrict_include_placement",
+        id: "P10-R8-include-placement",
+        category: "preprocessor",
+        power_of_ten: Some(8),
+        default_severity: Severity::Error,
+        summary: "Includes must precede all declarations, optionally in a fixed order",
+        rationale: "Includes in the middle of a file make its dependencies hard to see and may change the meaning of earlier code.",
+        non_compliant: "int counter;\n#include \"sensors.h\"",
+        compliant: "#include \"sensors.h\"\nint counter;",
+        options: &["enforce_include_order"],
+    },
+    RuleMetadata {
+        name: "restrict_include_paths",
+        id: "P10-R8-include-paths",
+        category: "preprocessor",
+        power_of_ten: Some(8),
+        default_severity: Severity::Error,
+        summary: "Include paths must be relative, without '..' segments or backslashes",
+        rationale: "Absolute and parent-relative paths tie the code to one checkout layout and backslashes only work on Windows, breaking reproducible cross-platform builds.",
+        non_compliant: "#include \"../common/types.h\"",
+        compliant: "#include \"common/types.h\"",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "restrict_pragmas",
+        id: "P10-R8-pragmas",
+        category: "preprocessor",
+        power_of_ten: Some(8),
+        default_severity: Severity::Advisory,
+        summary: "Only use allow-listed pragmas",
+        rationale: "Pragmas silently change the semantics of the code they apply to, in compiler-specific ways reviewers need to see.",
+        non_compliant: "#pragma pack(1)",
+        compliant: "#pragma once",
+        options: &["allowed_pragmas"],
+    },
+    RuleMetadata {
+        name: "require_parsable_conditional_branches",
+        id: "P10-R8-conditional-branches",
+        category: "preprocessor",
+        power_of_ten: Some(8),
+        default_severity: Severity::Error,
+        summary: "Every branch of conditionally compiled code must parse",
+        rationale: "Branches that are not compiled in the current configuration are never checked by the compiler or reviewed, and rot until someone enables them.",
+        non_compliant: "#ifdef SIMULATION\n    sim_step(state\n#endif",
+        compliant: "#ifdef SIMULATION\n    sim_step(state);\n#endif",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "restrict_dereference_depth",
+        id: "P10-R9-dereference-depth",
+        category: "pointers",
+        power_of_ten: Some(9),
+        default_severity: Severity::Error,
+        summary: "Do not dereference more than one level of pointers in an expression",
+        rationale: "Each level of indirection hides which object is actually accessed, from reviewers and from static analysis tools alike. Both * and -> count as a dereference.",
+        non_compliant: "speed = vehicle->state->speed;",
+        compliant: "const struct state *state = vehicle->state;\nspeed = state->speed;",
+        options: &["max_dereference_depth"],
+    },
+    RuleMetadata {
+        name: "restrict_function_pointers",
+        id: "P10-R9-function-pointers",
+        category: "pointers",
+        power_of_ten: Some(9),
+        default_severity: Severity::Error,
+        summary: "Do not use function pointers",
+        rationale: "Calls through function pointers hide the call graph, so tools can no longer prove the absence of recursion or bound stack usage.",
+        non_compliant: "void (*handler)(int) = on_fault;\nhandler(code);",
+        compliant: "switch (kind) {\ncase FAULT_POWER: on_power_fault(code); break;\ndefault: on_fault(code); break;\n}",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "require_const_correctness",
+        id: "CS-const-correctness",
+        category: "pointers",
+        power_of_ten: None,
+        default_severity: Severity::Warning,
+        summary: "Declare pointer parameters that are only read as pointers to const, and do not cast const away",
+        rationale: "A pointer to const documents that the function leaves the object alone and lets the compiler hold it to that, while casting const away defeats the same guarantee and makes writing to read-only memory possible. A parameter counts as written once the function assigns through it or hands it on, e.g. to another function, so callbacks whose signature is fixed by their caller may need a suppression.",
+        non_compliant: "bool frame_too_long(struct frame *frame) {\n    return frame->length > MAX_FRAME_LENGTH;\n}",
+        compliant: "bool frame_too_long(const struct frame *frame) {\n    return frame->length > MAX_FRAME_LENGTH;\n}",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "restrict_banned_functions",
+        id: "CS-banned-functions",
+        category: "api",
+        power_of_ten: None,
+        default_severity: Severity::Error,
+        summary: "Do not call functions on the project's banned list",
+        rationale: "Projects forbid APIs that are unsafe or unsuitable for flight software, often in favour of a safer replacement.",
+        non_compliant: "gets(line);",
+        compliant: "fgets(line, sizeof(line), stdin);",
+        options: &["banned_functions", "banned_function_replacements"],
+    },
+    RuleMetadata {
+        name: "restrict_unsafe_string_functions",
+        id: "CS-unsafe-string-functions",
+        category: "api",
+        power_of_ten: None,
+        default_severity: Severity::Error,
+        summary: "Use the bounded variants of string and memory functions",
+        rationale: "gets, strcpy, strcat, sprintf and their relatives write as much as the input holds, whatever the size of the destination, so a long input overruns the buffer. Their bounded replacements take the destination size.",
+        non_compliant: "sprintf(label, \"%s-%d\", name, id);",
+        compliant: "snprintf(label, sizeof(label), \"%s-%d\", name, id);",
+        options: &["unsafe_string_functions"],
+    },
+    RuleMetadata {
+        name: "restrict_termination_calls",
+        id: "CS-termination-calls",
+        category: "api",
+        power_of_ten: None,
+        default_severity: Severity::Error,
+        summary: "Do not terminate the program, report faults to the fault handler",
+        rationale: "A flight task that calls exit or abort takes the whole software down with it, usually over a fault the system could have recovered from or at least safed. Faults belong with the project's fault-handling entry point, which decides whether to retry, safe the vehicle or restart.",
+        non_compliant: "if (status != OK) {\n    abort();\n}",
+        compliant: "if (status != OK) {\n    fault_report(FAULT_SENSOR, status);\n    return status;\n}",
+        options: &["termination_functions", "fault_handler"],
+    },
+    RuleMetadata {
+        name: "restrict_banned_headers",
+        id: "CS-banned-headers",
+        category: "api",
+        power_of_ten: None,
+        default_severity: Severity::Error,
+        summary: "Do not include banned headers",
+        rationale: "Banning a header is a stronger guarantee than banning its individual functions.",
+        non_compliant: "#include <setjmp.h>",
+        compliant: "#include \"fault.h\"",
+        options: &["banned_headers"],
+    },
+    RuleMetadata {
+        name: "restrict_variadic",
+        id: "CS-variadic",
+        category: "types",
+        power_of_ten: None,
+        default_severity: Severity::Error,
+        summary: "Do not define or call variadic functions",
+        rationale: "Arguments passed through an ellipsis are not type checked.",
+        non_compliant: "void log_event(const char *format, ...);",
+        compliant: "void log_event(event_id_t id, int32_t value);",
+        options: &["variadic_library_functions", "allowed_variadic_functions"],
+    },
+    RuleMetadata {
+        name: "restrict_format_mismatches",
+        id: "CS-format-strings",
+        category: "types",
+        power_of_ten: None,
+        default_severity: Severity::Error,
+        summary: "Pass printf and scanf the arguments their format string asks for",
+        rationale: "The compiler cannot check arguments passed through an ellipsis, so a missing argument or a conversion given the wrong type reads garbage from the stack or, for scanf, writes through it. Argument types are recognized by declared type, constants and casts.",
+        non_compliant: "printf(\"%d samples at %s\\n\", count);",
+        compliant: "printf(\"%d samples at %s\\n\", count, timestamp);",
+        options: &["printf_functions", "scanf_functions"],
+    },
+    RuleMetadata {
+        name: "restrict_octal_constants",
+        id: "CS-octal-constants",
+        category: "types",
+        power_of_ten: None,
+        default_severity: Severity::Error,
+        summary: "Do not write integer constants in octal",
+        rationale: "A leading zero makes a constant octal, so 010 is eight. Zero padding that lines up a table of numbers silently changes their values.",
+        non_compliant: "static const uint16_t limits[] = { 100, 050, 010 };",
+        compliant: "static const uint16_t limits[] = { 100, 50, 10 };",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "restrict_signed_bitwise",
+        id: "CS-signed-bitwise",
+        category: "types",
+        power_of_ten: None,
+        default_severity: Severity::Error,
+        summary: "Use unsigned types for shifts and bitwise operations",
+        rationale: "Shifting a negative value right is implementation-defined and shifting a one into the sign bit is undefined, so bit manipulation on signed types behaves differently between compilers. Operands are recognized by declared type and casts.",
+        non_compliant: "int32_t flags = read_flags();\nflags = flags >> 4;",
+        compliant: "uint32_t flags = read_flags();\nflags = flags >> 4U;",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "restrict_implicit_narrowing",
+        id: "CS-implicit-narrowing",
+        category: "types",
+        power_of_ten: None,
+        default_severity: Severity::Error,
+        summary: "Cast explicitly when storing an integer into a narrower type",
+        rationale: "An implicit conversion to a narrower integer type silently drops the high bits, so a value that grows past the range of the target wraps around. A cast documents that the truncation is intended. Types are taken from the declarations of variables, parameters and functions.",
+        non_compliant: "uint32_t total = sum_samples();\nuint8_t level = total;",
+        compliant: "uint32_t total = sum_samples();\nuint8_t level = (uint8_t)(total >> 24U);",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "restrict_unions",
+        id: "CS-unions",
+        category: "types",
+        power_of_ten: None,
+        default_severity: Severity::Error,
+        summary: "Do not use unions",
+        rationale: "Reading a union through another member than the one last written reinterprets its bytes, which depends on the compiler and target and hides the conversion from analysis. Approved register overlays can be allowed by name.",
+        non_compliant: "union sample {\n    float value;\n    uint32_t bits;\n};",
+        compliant: "uint32_t bits;\nmemcpy(&bits, &value, sizeof(bits));",
+        options: &["allowed_unions"],
+    },
+    RuleMetadata {
+        name: "restrict_float_equality",
+        id: "CS-float-equality",
+        category: "types",
+        power_of_ten: None,
+        default_severity: Severity::Error,
+        summary: "Do not compare floating-point values for equality",
+        rationale: "Rounding makes two computations of the same quantity differ in their last bits, so an exact comparison holds on one run or target and fails on the next. Operands are recognized by declared type and float constants.",
+        non_compliant: "if (altitude == target_altitude) {\n    hold();\n}",
+        compliant: "if (fabs(altitude - target_altitude) < ALTITUDE_TOLERANCE) {\n    hold();\n}",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "restrict_magic_numbers",
+        id: "CS-magic-numbers",
+        category: "types",
+        power_of_ten: None,
+        default_severity: Severity::Warning,
+        summary: "Name numeric constants instead of writing them inline",
+        rationale: "A bare number does not say what it means or which other occurrences have to change with it; a named constant documents the intent and keeps every use in step.",
+        non_compliant: "if (temperature > 85) {\n    throttle();\n}",
+        compliant: "enum { MAX_BOARD_TEMPERATURE = 85 };\n\nif (temperature > MAX_BOARD_TEMPERATURE) {\n    throttle();\n}",
+        options: &["allowed_numbers", "allow_hex_numbers"],
+    },
+    RuleMetadata {
+        name: "require_sizeof_size_arguments",
+        id: "CS-sizeof-size-arguments",
+        category: "memory",
+        power_of_ten: None,
+        default_severity: Severity::Error,
+        summary: "Derive the size argument of bounded buffer functions from sizeof(destination)",
+        rationale: "A hard-coded size silently overflows the buffer once the array is shrunk, and the size of another buffer is a copy and paste error waiting to happen.",
+        non_compliant: "char name[16];\nstrncpy(name, input, 32);",
+        compliant: "char name[16];\nstrncpy(name, input, sizeof(name) - 1);",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "restrict_offset_buffer_overrun",
+        id: "CS-offset-buffer-overrun",
+        category: "memory",
+        power_of_ten: None,
+        default_severity: Severity::Error,
+        summary: "Do not pass a size larger than what is left of an array passed from an offset",
+        rationale: "Passing `&array[k]` or `array + k` hands the callee a pointer without the array's bounds, so a size computed for the whole array writes past its end.",
+        non_compliant: "char packet[64];\nmemcpy(&packet[4], payload, sizeof(packet));",
+        compliant: "char packet[64];\nmemcpy(&packet[4], payload, sizeof(packet) - 4);",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "restrict_stack_frame_size",
+        id: "CS-stack-frame-size",
+        category: "memory",
+        power_of_ten: None,
+        default_severity: Severity::Error,
+        summary: "Keep the local variables of a function within a stack budget",
+        rationale: "Task stacks on flight processors are small and fixed; a large local buffer overflows them silently. The frame is estimated from the declared locals of every block, as if all were live at once, with 8-byte pointers and longs and no padding. Locals of unknown size are left out.",
+        non_compliant: "void downlink(void) {\n    uint8_t frame[4096];\n    encode(frame);\n}",
+        compliant: "static uint8_t frame[4096];\n\nvoid downlink(void) {\n    encode(frame);\n}",
+        options: &["max_stack_frame_bytes"],
+    },
+    RuleMetadata {
+        name: "restrict_volatile_toctou",
+        id: "CS-volatile-toctou",
+        category: "concurrency",
+        power_of_ten: None,
+        default_severity: Severity::Warning,
+        summary: "Do not re-read a volatile register after checking it, or test stale copies of registers that must be read fresh",
+        rationale: "Hardware or an interrupt can change a volatile register between the check and the second read, so the guarded code acts on a value that was never checked.",
+        non_compliant: "if (UART_STATUS & RX_READY) {\n    handle(UART_STATUS);\n}",
+        compliant: "uint32_t status = UART_STATUS;\nif (status & RX_READY) {\n    handle(status);\n}",
+        options: &["fresh_read_registers"],
+    },
+    RuleMetadata {
+        name: "restrict_locale_apis",
+        id: "CS-locale-apis",
+        category: "portability",
+        power_of_ten: None,
+        default_severity: Severity::Error,
+        summary: "Do not use locale-dependent or wide-character APIs",
+        rationale: "Flight targets typically run without locale support, so these functions behave differently or not at all.",
+        non_compliant: "char c = read_char();\nif (isalpha(c)) { ... }",
+        compliant: "unsigned char c = read_char();\nif (isalpha(c)) { ... }",
+        options: &[],
+    },
+    RuleMetadata {
+        name: "restrict_bit_field_types",
+        id: "CS-bit-field-types",
+        category: "portability",
+        power_of_ten: None,
+        default_severity: Severity::Error,
+        summary: "Declare bit-fields with a fixed-width type",
+        rationale: "Whether a plain int bit-field is signed, and how bit-fields of other types are allocated, is implementation-defined, so a new compiler can change what a field holds. A signed field of one bit can only hold 0 and -1.",
+        non_compliant: "struct status {\n    int ready : 1;\n    int mode : 3;\n};",
+        compliant: "struct status {\n    uint8_t ready : 1;\n    uint8_t mode : 3;\n};",
+        options: &["bit_field_types"],
+    },
+];
+
+// Look up a rule by its ruleset key or by its ID
+pub fn find_rule(name_or_id: &str) -> Option<&'static RuleMetadata> {
+    RULES
+        .iter()
+        .find(|rule| rule.name == name_or_id || rule.id == name_or_id)
+}
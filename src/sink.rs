@@ -0,0 +1,28 @@
+use crate::diagnostic::Diagnostic;
+
+/// Receives findings as the analyzer reports them. Embedders implement this to
+/// decide how findings are emitted, e.g. by forwarding them to their own logging.
+pub trait DiagnosticSink {
+    fn report(&mut self, diagnostic: Diagnostic);
+}
+
+/// Collects findings in order
+impl DiagnosticSink for Vec<Diagnostic> {
+    fn report(&mut self, diagnostic: Diagnostic) {
+        self.push(diagnostic);
+    }
+}
+
+/// Passes each finding to a closure:
+///
+/// ```ignore
+/// let mut sink = CallbackSink(|diagnostic| log::warn!("{}", diagnostic));
+/// analyze_file_to(path, &rule_set, &mut sink)?;
+/// ```
+pub struct CallbackSink<F>(pub F);
+
+impl<F: FnMut(Diagnostic)> DiagnosticSink for CallbackSink<F> {
+    fn report(&mut self, diagnostic: Diagnostic) {
+        (self.0)(diagnostic);
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+
+use nasa_static_analyzer::AnalyzerError;
+
+// Time to wait for further events after a change, as editors often save in several steps
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+// Call `on_change` with the files that changed, in the order they were given, until
+// the process is stopped. The directories of the files are watched rather than the
+// files themselves, since editors often save by replacing the file.
+pub fn watch_files(
+    files: &[String],
+    mut on_change: impl FnMut(&[String]),
+) -> Result<(), AnalyzerError> {
+    let watch_error = |error: notify::Error| AnalyzerError::Watch(error.to_string());
+
+    let watched: HashMap<PathBuf, usize> = files
+        .iter()
+        .enumerate()
+        .filter_map(|(index, file)| Some((fs::canonicalize(file).ok()?, index)))
+        .collect();
+    let directories: BTreeSet<PathBuf> = watched
+        .keys()
+        .filter_map(|path| path.parent().map(PathBuf::from))
+        .collect();
+
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender).map_err(watch_error)?;
+    for directory in &directories {
+        watcher
+            .watch(directory, RecursiveMode::NonRecursive)
+            .map_err(watch_error)?;
+    }
+    println!();
+    println!("Watching {} files for changes", watched.len());
+
+    loop {
+        let mut changed = BTreeSet::new();
+        let event = receiver
+            .recv()
+            .map_err(|error| AnalyzerError::Watch(error.to_string()))?;
+        collect_changes(event, &watched, &mut changed);
+        while let Ok(event) = receiver.recv_timeout(DEBOUNCE) {
+            collect_changes(event, &watched, &mut changed);
+        }
+
+        if !changed.is_empty() {
+            let changed: Vec<String> = changed
+                .into_iter()
+                .map(|index| files[index].clone())
+                .collect();
+            on_change(&changed);
+        }
+    }
+}
+
+// Add the watched files a filesystem event created or modified
+fn collect_changes(
+    event: notify::Result<Event>,
+    watched: &HashMap<PathBuf, usize>,
+    changed: &mut BTreeSet<usize>,
+) {
+    let event = match event {
+        Ok(event) => event,
+        Err(error) => {
+            eprintln!("warning: {}", error);
+            return;
+        }
+    };
+    if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+        return;
+    }
+    for path in &event.paths {
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+        if let Some(&index) = watched.get(&path) {
+            changed.insert(index);
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::config::{glob_matches, ConfigFormat};
+use crate::error::AnalyzerError;
+
+// A subsystem of a workspace, analyzed with its own sources and optionally its own ruleset
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Module {
+    pub name: String,
+    // Source files or glob patterns such as "gnc/src/**/*.c", relative to the workspace file
+    pub sources: Vec<String>,
+    // Used in place of the base ruleset for the module's files, relative to the workspace file.
+    // Config files discovered next to the sources still apply on top of it.
+    #[serde(default)]
+    pub ruleset: Option<String>,
+}
+
+// Top-level config listing the modules analyzed together in one invocation
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Workspace {
+    pub modules: Vec<Module>,
+}
+
+impl Module {
+    // Every source file of the module, sorted so reports do not depend on directory order
+    pub fn source_files(&self) -> Result<Vec<String>, AnalyzerError> {
+        let mut files = Vec::new();
+        for pattern in &self.sources {
+            if !pattern.contains(['*', '?']) {
+                files.push(pattern.clone());
+                continue;
+            }
+
+            let matched = glob_files(pattern);
+            if matched.is_empty() {
+                return Err(AnalyzerError::ConfigParse {
+                    location: format!("module '{}'", self.name),
+                    message: format!("Source pattern '{}' matches no files", pattern),
+                });
+            }
+            files.extend(matched);
+        }
+        files.sort();
+        files.dedup();
+        Ok(files)
+    }
+}
+
+// Load a workspace file, making the module paths relative to the working directory
+pub fn load_workspace(path: &Path) -> Result<Workspace, AnalyzerError> {
+    let location = path.display().to_string();
+    let content = fs::read_to_string(path).map_err(|error| AnalyzerError::ConfigIo {
+        path: path.to_path_buf(),
+        error,
+    })?;
+    let mut workspace: Workspace = ConfigFormat::from_path(path)
+        .deserialize(&content)
+        .map_err(|message| AnalyzerError::ConfigParse {
+            location: location.clone(),
+            message,
+        })?;
+
+    let mut names = HashSet::new();
+    for module in &workspace.modules {
+        if !names.insert(module.name.as_str()) {
+            return Err(AnalyzerError::ConfigParse {
+                location,
+                message: format!("Module '{}' is defined more than once", module.name),
+            });
+        }
+    }
+
+    let directory = path.parent().unwrap_or(Path::new(""));
+    let anchor = |relative: &str| directory.join(relative).to_string_lossy().to_string();
+    for module in &mut workspace.modules {
+        for source in &mut module.sources {
+            *source = anchor(source);
+        }
+        module.ruleset = module.ruleset.as_deref().map(anchor);
+    }
+
+    Ok(workspace)
+}
+
+// Files matching a glob pattern, found by walking the directory before its first wildcard
+fn glob_files(pattern: &str) -> Vec<String> {
+    let wildcard = pattern.find(['*', '?']).unwrap_or(pattern.len());
+    let root = match pattern[..wildcard].rfind('/') {
+        Some(end) => PathBuf::from(&pattern[..end]),
+        None => PathBuf::from("."),
+    };
+
+    let mut files = Vec::new();
+    let mut directories = vec![root];
+    while let Some(directory) = directories.pop() {
+        let Ok(entries) = fs::read_dir(&directory) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                directories.push(path);
+                continue;
+            }
+            let path = path.to_string_lossy();
+            let path = path.strip_prefix("./").unwrap_or(&path);
+            if glob_matches(pattern, path) {
+                files.push(path.to_string());
+            }
+        }
+    }
+    files
+}